@@ -1,6 +1,8 @@
 use std::sync::{Arc, Mutex};
 
-/// Simple thread-safe audio buffer that accumulates f32 samples at 16kHz.
+use super::TARGET_SAMPLE_RATE;
+
+/// Simple thread-safe audio buffer that accumulates f32 samples at TARGET_SAMPLE_RATE.
 /// Phase 1 uses a record-all-then-transcribe pattern.
 #[derive(Clone)]
 pub struct AudioBuffer {
@@ -10,8 +12,10 @@ pub struct AudioBuffer {
 impl AudioBuffer {
     pub fn new() -> Self {
         Self {
-            // Pre-allocate for 30 seconds of 16kHz audio
-            samples: Arc::new(Mutex::new(Vec::with_capacity(16000 * 30))),
+            // Pre-allocate for 30 seconds of audio
+            samples: Arc::new(Mutex::new(Vec::with_capacity(
+                TARGET_SAMPLE_RATE as usize * 30,
+            ))),
         }
     }
 
@@ -39,6 +43,15 @@ impl AudioBuffer {
         self.samples.lock().map(|b| b.len()).unwrap_or(0)
     }
 
+    /// Copy of the newest `n` samples (fewer if the buffer is shorter).
+    pub fn tail(&self, n: usize) -> Vec<f32> {
+        if let Ok(buf) = self.samples.lock() {
+            buf[buf.len().saturating_sub(n)..].to_vec()
+        } else {
+            Vec::new()
+        }
+    }
+
     /// Return a copy of the current samples without clearing the buffer.
     pub fn snapshot(&self) -> Vec<f32> {
         if let Ok(buf) = self.samples.lock() {
@@ -1,7 +1,12 @@
+use std::sync::{Arc, Mutex};
+
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{SampleFormat, Stream, StreamConfig};
 
 use super::buffer::AudioBuffer;
+use super::clipping::ClipMonitor;
+use super::filter::HighPass;
+use super::TARGET_SAMPLE_RATE;
 
 /// Microphone gain multiplier. Boost quiet mics for better recognition.
 const MIC_GAIN: f32 = 4.0;
@@ -11,9 +16,42 @@ const MIC_GAIN: f32 = 4.0;
 struct SendStream(Stream);
 unsafe impl Send for SendStream {}
 
+/// Set when cpal reports the input device is gone, shared so the recording
+/// flow can notice it.
+#[derive(Clone, Default)]
+pub struct StreamErrorFlag(Arc<Mutex<Option<String>>>);
+
+impl StreamErrorFlag {
+    fn set(&self, error: String) {
+        if let Ok(mut slot) = self.0.lock() {
+            slot.get_or_insert(error);
+        }
+    }
+
+    fn clear(&self) {
+        if let Ok(mut slot) = self.0.lock() {
+            *slot = None;
+        }
+    }
+
+    /// The first error since the stream started, if any.
+    pub fn get(&self) -> Option<String> {
+        self.0.lock().ok().and_then(|slot| slot.clone())
+    }
+}
+
 pub struct AudioCapture {
     stream: Option<SendStream>,
     buffer: AudioBuffer,
+    clip_monitor: ClipMonitor,
+    stream_error: StreamErrorFlag,
+    /// Cutoff of the high-pass filter run before the gain (None = off).
+    high_pass_hz: Option<f32>,
+    /// Input device to record from, by name (None = system default).
+    device_name: Option<String>,
+    /// Set by `start` when `device_name` wasn't connected and the default
+    /// device was used instead.
+    missing_device: Option<String>,
     device_sample_rate: u32,
 }
 
@@ -25,15 +63,51 @@ impl AudioCapture {
         Self {
             stream: None,
             buffer,
+            clip_monitor: ClipMonitor::new(),
+            stream_error: StreamErrorFlag::default(),
+            high_pass_hz: None,
+            device_name: None,
+            missing_device: None,
             device_sample_rate: 48000,
         }
     }
 
-    pub fn start(&mut self) -> Result<u32, String> {
-        let host = cpal::default_host();
-        let device = host
+    /// Takes effect on the next start.
+    pub fn set_high_pass(&mut self, cutoff_hz: Option<f32>) {
+        self.high_pass_hz = cutoff_hz;
+    }
+
+    /// Record from the named input device (None = system default). Takes
+    /// effect on the next start.
+    pub fn set_device(&mut self, device_name: Option<String>) {
+        self.device_name = device_name;
+    }
+
+    /// The chosen device, if the last start fell back to the default because
+    /// it wasn't connected.
+    pub fn missing_device(&self) -> Option<&str> {
+        self.missing_device.as_deref()
+    }
+
+    /// The chosen input device, or the default when none is chosen or it is gone.
+    fn input_device(&mut self) -> Result<cpal::Device, String> {
+        self.missing_device = None;
+        if let Some(name) = &self.device_name {
+            match super::devices::find_input_device(name) {
+                Some(device) => return Ok(device),
+                None => {
+                    log::warn!("Input device {:?} not found, using the default", name);
+                    self.missing_device = Some(name.clone());
+                }
+            }
+        }
+        cpal::default_host()
             .default_input_device()
-            .ok_or("No input device found")?;
+            .ok_or_else(|| "No input device found".to_string())
+    }
+
+    pub fn start(&mut self) -> Result<u32, String> {
+        let device = self.input_device()?;
 
         let supported_config = device
             .default_input_config()
@@ -46,6 +120,19 @@ impl AudioCapture {
         let native_rate = self.device_sample_rate;
 
         let buffer = self.buffer.clone();
+        let clip_monitor = self.clip_monitor.clone();
+        let mut resampler = Resampler::new(native_rate, TARGET_SAMPLE_RATE);
+        let mut high_pass = self.high_pass_hz.map(|hz| HighPass::new(hz, TARGET_SAMPLE_RATE));
+        self.stream_error.clear();
+        let stream_error = self.stream_error.clone();
+        let on_error = move |err: cpal::StreamError| {
+            log::error!("Audio stream error: {}", err);
+            // Backend errors can be transient (overruns); a stream that really
+            // died also stops delivering audio, which the recording flow notices
+            if matches!(err, cpal::StreamError::DeviceNotAvailable) {
+                stream_error.set(err.to_string());
+            }
+        };
 
         let stream = match sample_format {
             SampleFormat::F32 => device
@@ -53,11 +140,15 @@ impl AudioCapture {
                     &config,
                     move |data: &[f32], _info: &cpal::InputCallbackInfo| {
                         let mono = to_mono(data, channels);
-                        let resampled = resample(&mono, native_rate, 16000);
-                        let amplified = apply_gain(&resampled, MIC_GAIN);
+                        let resampled = resampler.process(&mono);
+                        let filtered = match high_pass.as_mut() {
+                            Some(filter) => filter.process(&resampled),
+                            None => resampled,
+                        };
+                        let amplified = apply_gain(&filtered, MIC_GAIN, &clip_monitor);
                         buffer.push_samples(&amplified);
                     },
-                    |err| log::error!("Audio stream error: {}", err),
+                    on_error,
                     None,
                 )
                 .map_err(|e| format!("Failed to build f32 input stream: {}", e))?,
@@ -68,11 +159,15 @@ impl AudioCapture {
                         let float_data: Vec<f32> =
                             data.iter().map(|&s| s as f32 / i16::MAX as f32).collect();
                         let mono = to_mono(&float_data, channels);
-                        let resampled = resample(&mono, native_rate, 16000);
-                        let amplified = apply_gain(&resampled, MIC_GAIN);
+                        let resampled = resampler.process(&mono);
+                        let filtered = match high_pass.as_mut() {
+                            Some(filter) => filter.process(&resampled),
+                            None => resampled,
+                        };
+                        let amplified = apply_gain(&filtered, MIC_GAIN, &clip_monitor);
                         buffer.push_samples(&amplified);
                     },
-                    |err| log::error!("Audio stream error: {}", err),
+                    on_error,
                     None,
                 )
                 .map_err(|e| format!("Failed to build i16 input stream: {}", e))?,
@@ -97,10 +192,20 @@ impl AudioCapture {
     pub fn device_sample_rate(&self) -> u32 {
         self.device_sample_rate
     }
+
+    /// Counter of samples clamped by the gain stage.
+    pub fn clip_monitor(&self) -> ClipMonitor {
+        self.clip_monitor.clone()
+    }
+
+    /// Error flag of the current stream, checked while recording.
+    pub fn stream_error(&self) -> StreamErrorFlag {
+        self.stream_error.clone()
+    }
 }
 
 /// Convert multi-channel audio to mono by averaging channels.
-fn to_mono(data: &[f32], channels: usize) -> Vec<f32> {
+pub(crate) fn to_mono(data: &[f32], channels: usize) -> Vec<f32> {
     if channels == 1 {
         return data.to_vec();
     }
@@ -109,27 +214,139 @@ fn to_mono(data: &[f32], channels: usize) -> Vec<f32> {
         .collect()
 }
 
-/// Apply gain and clamp to [-1.0, 1.0] to avoid clipping.
-fn apply_gain(data: &[f32], gain: f32) -> Vec<f32> {
-    data.iter().map(|&s| (s * gain).clamp(-1.0, 1.0)).collect()
+/// Apply gain and clamp to [-1.0, 1.0], counting clamped samples in `monitor`.
+fn apply_gain(data: &[f32], gain: f32, monitor: &ClipMonitor) -> Vec<f32> {
+    let mut clipped = 0;
+    let out = data
+        .iter()
+        .map(|&s| {
+            let v = s * gain;
+            if v.abs() >= 1.0 {
+                clipped += 1;
+            }
+            v.clamp(-1.0, 1.0)
+        })
+        .collect();
+    monitor.record(clipped, data.len());
+    out
 }
 
-/// Simple linear interpolation resampler (e.g., 48000 -> 16000 Hz).
-fn resample(data: &[f32], source_rate: u32, target_rate: u32) -> Vec<f32> {
-    if source_rate == target_rate || data.is_empty() {
-        return data.to_vec();
+/// Linear interpolation resampler (e.g., 48000 -> 16000 Hz) for a continuous
+/// stream. Input the next output sample still needs is carried over to the
+/// next callback instead of being dropped, so tiny cpal buffers lose nothing.
+/// The read position is kept as an exact fraction, so uneven ratios like
+/// 44100 -> 16000 don't drift over long recordings.
+pub(crate) struct Resampler {
+    source_rate: u32,
+    target_rate: u32,
+    pending: Vec<f32>,
+    /// Position of the next output sample from `pending[0]`, in units of
+    /// 1/target_rate input samples. Each output step advances it by source_rate.
+    pos: u64,
+}
+
+impl Resampler {
+    pub(crate) fn new(source_rate: u32, target_rate: u32) -> Self {
+        Self {
+            source_rate,
+            target_rate,
+            pending: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    pub(crate) fn process(&mut self, data: &[f32]) -> Vec<f32> {
+        if self.source_rate == self.target_rate {
+            return data.to_vec();
+        }
+        self.pending.extend_from_slice(data);
+
+        let step = self.source_rate as u64;
+        let unit = self.target_rate as u64;
+        let len = self.pending.len();
+        let mut output = Vec::with_capacity(len * unit as usize / step as usize + 1);
+
+        // Emit every output sample whose right-hand neighbour is already here
+        while ((self.pos / unit) as usize) + 1 < len {
+            let idx_floor = (self.pos / unit) as usize;
+            let frac = (self.pos % unit) as f64 / unit as f64;
+            let sample = self.pending[idx_floor] as f64 * (1.0 - frac)
+                + self.pending[idx_floor + 1] as f64 * frac;
+            output.push(sample as f32);
+            self.pos += step;
+        }
+
+        // Drop input before the next output sample and rebase the position
+        let consumed = ((self.pos / unit) as usize).min(len);
+        self.pending.drain(..consumed);
+        self.pos -= consumed as u64 * unit;
+        output
     }
-    let ratio = source_rate as f64 / target_rate as f64;
-    let output_len = (data.len() as f64 / ratio) as usize;
-    let mut output = Vec::with_capacity(output_len);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::samples_to_secs;
+
+    #[test]
+    fn capture_resamples_to_target_rate() {
+        for source_rate in [16000, 44100, 48000] {
+            let mut resampler = Resampler::new(source_rate, TARGET_SAMPLE_RATE);
+            let output = resampler.process(&vec![0.0; source_rate as usize]);
+            let expected = TARGET_SAMPLE_RATE as usize;
+            assert!(
+                output.len().abs_diff(expected) <= 1,
+                "{} Hz: {} samples for one second",
+                source_rate,
+                output.len()
+            );
+            assert!((samples_to_secs(output.len()) - 1.0).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn tiny_buffers_lose_no_samples() {
+        let input: Vec<f32> = (0..48000).map(|i| (i as f32 * 0.01).sin()).collect();
+        let whole = Resampler::new(48000, TARGET_SAMPLE_RATE).process(&input);
+
+        // Buffers of 1-7 samples, most shorter than one output step
+        let mut resampler = Resampler::new(48000, TARGET_SAMPLE_RATE);
+        let mut chunked = Vec::new();
+        let mut start = 0;
+        for size in (1..=7).cycle() {
+            if start >= input.len() {
+                break;
+            }
+            let end = (start + size).min(input.len());
+            chunked.extend(resampler.process(&input[start..end]));
+            start = end;
+        }
+
+        assert_eq!(chunked.len(), whole.len());
+        assert_eq!(chunked, whole);
+    }
+
+    #[test]
+    fn long_44k_stream_keeps_the_rate_ratio() {
+        // Ten minutes and a bit, in 10ms callbacks, so rounding can't drift
+        let total = 44100 * 600 + 12345;
+        let mut resampler = Resampler::new(44100, TARGET_SAMPLE_RATE);
+        let mut produced = 0;
+        let mut start = 0;
+        while start < total {
+            let end = (start + 441).min(total);
+            let input: Vec<f32> = (start..end).map(|i| (i as f32 * 0.0627).sin()).collect();
+            produced += resampler.process(&input).len();
+            start = end;
+        }
 
-    for i in 0..output_len {
-        let src_idx = i as f64 * ratio;
-        let idx_floor = src_idx.floor() as usize;
-        let idx_ceil = (idx_floor + 1).min(data.len() - 1);
-        let frac = src_idx - idx_floor as f64;
-        let sample = data[idx_floor] as f64 * (1.0 - frac) + data[idx_ceil] as f64 * frac;
-        output.push(sample as f32);
+        let expected = total as f64 * TARGET_SAMPLE_RATE as f64 / 44100.0;
+        assert!(
+            (produced as f64 - expected).abs() <= 1.0,
+            "{} samples, expected {:.1}",
+            produced,
+            expected
+        );
     }
-    output
 }
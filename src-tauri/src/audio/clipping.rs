@@ -0,0 +1,51 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Fraction of clamped samples in a window above which we warn (1%).
+pub const CLIP_WARN_RATIO: f32 = 0.01;
+
+/// Payload of the "clipping" event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClippingWarning {
+    /// Fraction of samples in the window that hit full scale.
+    pub ratio: f32,
+    pub message: String,
+}
+
+/// Counts samples clamped by the gain stage, shared between the audio
+/// callback and whoever polls for clipping.
+#[derive(Clone, Default)]
+pub struct ClipMonitor {
+    clipped: Arc<AtomicUsize>,
+    total: Arc<AtomicUsize>,
+}
+
+impl ClipMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Called from the audio callback with the counts for one block.
+    pub fn record(&self, clipped: usize, total: usize) {
+        self.clipped.fetch_add(clipped, Ordering::Relaxed);
+        self.total.fetch_add(total, Ordering::Relaxed);
+    }
+
+    /// Clipping ratio since the last call, resetting the window.
+    /// Returns None if no audio arrived in the window.
+    pub fn take_ratio(&self) -> Option<f32> {
+        let clipped = self.clipped.swap(0, Ordering::Relaxed);
+        let total = self.total.swap(0, Ordering::Relaxed);
+        (total > 0).then(|| clipped as f32 / total as f32)
+    }
+
+    /// A warning if the window's clipping ratio exceeds CLIP_WARN_RATIO.
+    pub fn check(&self) -> Option<ClippingWarning> {
+        let ratio = self.take_ratio()?;
+        (ratio > CLIP_WARN_RATIO).then(|| ClippingWarning {
+            ratio,
+            message: "Your microphone input is clipping. Lower the input gain or move back from the mic.".to_string(),
+        })
+    }
+}
@@ -0,0 +1,108 @@
+//! Spectral subtraction: subtract the calibrated noise spectrum from each
+//! short-time frame and resynthesize by overlap-add.
+
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use std::f32::consts::PI;
+
+/// FFT frame length (32ms at 16kHz).
+pub const FRAME_LEN: usize = 512;
+/// 50% overlap; with a sqrt-Hann window on both analysis and synthesis the
+/// overlapped windows sum to one, so unmodified frames reconstruct exactly.
+const HOP: usize = FRAME_LEN / 2;
+/// Number of magnitude bins in a noise profile.
+pub const PROFILE_BINS: usize = FRAME_LEN / 2 + 1;
+
+/// How much of the noise estimate to subtract (>1 over-subtracts to catch
+/// noise peaks above the average).
+const OVER_SUBTRACTION: f32 = 1.5;
+/// Fraction of the original magnitude always kept, which avoids "musical
+/// noise" from bins being zeroed out.
+const SPECTRAL_FLOOR: f32 = 0.05;
+
+fn sqrt_hann() -> Vec<f32> {
+    (0..FRAME_LEN)
+        .map(|i| (0.5 - 0.5 * (2.0 * PI * i as f32 / FRAME_LEN as f32).cos()).sqrt())
+        .collect()
+}
+
+/// Average magnitude spectrum of a noise-only recording, used as the profile.
+/// Returns an empty profile if there's less than one frame of audio.
+pub fn noise_spectrum(samples: &[f32]) -> Vec<f32> {
+    if samples.len() < FRAME_LEN {
+        return Vec::new();
+    }
+    let fft = FftPlanner::new().plan_fft_forward(FRAME_LEN);
+    let window = sqrt_hann();
+    let mut profile = vec![0.0f32; PROFILE_BINS];
+    let mut frames = 0;
+
+    let mut start = 0;
+    while start + FRAME_LEN <= samples.len() {
+        let mut buf: Vec<Complex<f32>> = samples[start..start + FRAME_LEN]
+            .iter()
+            .zip(&window)
+            .map(|(s, w)| Complex::new(s * w, 0.0))
+            .collect();
+        fft.process(&mut buf);
+        for (p, c) in profile.iter_mut().zip(&buf) {
+            *p += c.norm();
+        }
+        frames += 1;
+        start += HOP;
+    }
+
+    for p in &mut profile {
+        *p /= frames as f32;
+    }
+    profile
+}
+
+/// Remove stationary noise described by `profile` (from `noise_spectrum`).
+/// Returns the input unchanged if the profile doesn't match the frame size.
+pub fn spectral_subtract(samples: &[f32], profile: &[f32]) -> Vec<f32> {
+    if profile.len() != PROFILE_BINS || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let mut planner = FftPlanner::new();
+    let fft = planner.plan_fft_forward(FRAME_LEN);
+    let ifft = planner.plan_fft_inverse(FRAME_LEN);
+    let window = sqrt_hann();
+
+    // Pad by one hop on each side so the first and last samples get full overlap
+    let mut padded = vec![0.0f32; HOP];
+    padded.extend_from_slice(samples);
+    padded.resize(padded.len() + FRAME_LEN, 0.0);
+    let mut output = vec![0.0f32; padded.len()];
+
+    let mut start = 0;
+    while start + FRAME_LEN <= padded.len() {
+        let mut buf: Vec<Complex<f32>> = padded[start..start + FRAME_LEN]
+            .iter()
+            .zip(&window)
+            .map(|(s, w)| Complex::new(s * w, 0.0))
+            .collect();
+        fft.process(&mut buf);
+
+        for (i, c) in buf.iter_mut().enumerate() {
+            // Bins above Nyquist mirror the ones below
+            let bin = if i < PROFILE_BINS { i } else { FRAME_LEN - i };
+            let mag = c.norm();
+            if mag > 0.0 {
+                let cleaned = (mag - OVER_SUBTRACTION * profile[bin]).max(SPECTRAL_FLOOR * mag);
+                *c *= cleaned / mag;
+            }
+        }
+
+        ifft.process(&mut buf);
+        // rustfft doesn't normalize the inverse transform
+        let scale = 1.0 / FRAME_LEN as f32;
+        for (j, c) in buf.iter().enumerate() {
+            output[start + j] += c.re * scale * window[j];
+        }
+        start += HOP;
+    }
+
+    output[HOP..HOP + samples.len()].to_vec()
+}
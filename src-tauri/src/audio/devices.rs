@@ -1,5 +1,9 @@
 use cpal::traits::{DeviceTrait, HostTrait};
+use serde::Serialize;
 
+use super::TARGET_SAMPLE_RATE;
+
+#[derive(Debug, Clone, Serialize)]
 pub struct AudioDeviceInfo {
     pub name: String,
     pub sample_rate: u32,
@@ -23,9 +27,51 @@ pub fn list_input_devices() -> Vec<AudioDeviceInfo> {
     devices
 }
 
+/// The connected input device called `name`, if any.
+pub fn find_input_device(name: &str) -> Option<cpal::Device> {
+    cpal::default_host()
+        .input_devices()
+        .ok()?
+        .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+}
+
 pub fn get_default_input_device() -> Option<(cpal::Device, cpal::SupportedStreamConfig)> {
     let host = cpal::default_host();
     let device = host.default_input_device()?;
     let config = device.default_input_config().ok()?;
     Some((device, config))
 }
+
+/// One supported input configuration range of a device.
+#[derive(Debug, Clone, Serialize)]
+pub struct SupportedConfigInfo {
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: String,
+    /// The range includes TARGET_SAMPLE_RATE, so no resampling is needed.
+    pub supports_target_rate: bool,
+}
+
+/// Every input configuration the named device supports, not just its default.
+pub fn supported_input_configs(device_name: &str) -> Result<Vec<SupportedConfigInfo>, String> {
+    let device = find_input_device(device_name)
+        .ok_or_else(|| format!("Input device not found: {}", device_name))?;
+
+    let configs = device
+        .supported_input_configs()
+        .map_err(|e| format!("Failed to query configs for {}: {}", device_name, e))?;
+
+    Ok(configs
+        .map(|c| {
+            let (min, max) = (c.min_sample_rate().0, c.max_sample_rate().0);
+            SupportedConfigInfo {
+                min_sample_rate: min,
+                max_sample_rate: max,
+                channels: c.channels(),
+                sample_format: c.sample_format().to_string(),
+                supports_target_rate: (min..=max).contains(&TARGET_SAMPLE_RATE),
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,141 @@
+//! Decode audio files for transcription, e.g. recordings dropped on the window.
+
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use super::capture::{to_mono, Resampler};
+use super::TARGET_SAMPLE_RATE;
+
+/// Extensions `load_audio_file` can decode.
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["wav", "mp3", "flac", "ogg", "m4a"];
+
+/// Read an audio file as mono f32 samples at TARGET_SAMPLE_RATE. The format
+/// is picked from the extension; anything else is refused with a message
+/// naming the supported types.
+pub fn load_audio_file(path: &Path) -> Result<Vec<f32>, String> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "wav" => super::wav::load_wav(path),
+        "mp3" | "flac" | "ogg" | "m4a" => decode(path),
+        _ => Err(format!(
+            "Unsupported file type{}: use {}",
+            if extension.is_empty() {
+                String::new()
+            } else {
+                format!(" .{}", extension)
+            },
+            SUPPORTED_EXTENSIONS.join(", ")
+        )),
+    }
+}
+
+/// Compressed formats are decoded with symphonia directly rather than
+/// through rodio, whose decoder panics on some malformed MP4 files instead of
+/// returning an error.
+fn decode(path: &Path) -> Result<Vec<f32>, String> {
+    let failed = |e: SymphoniaError| format!("Failed to decode {}: {}", path.display(), e);
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+    let mut format = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(failed)?
+        .format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| format!("No audio track in {}", path.display()))?;
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(TARGET_SAMPLE_RATE);
+    let mut channels = track.codec_params.channels.map_or(1, |c| c.count());
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(failed)?;
+
+    let mut samples = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(failed(e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        match decoder.decode(&packet) {
+            Ok(audio) => {
+                let spec = *audio.spec();
+                sample_rate = spec.rate;
+                channels = spec.channels.count();
+                let mut buffer = SampleBuffer::<f32>::new(audio.capacity() as u64, spec);
+                buffer.copy_interleaved_ref(audio);
+                samples.extend_from_slice(buffer.samples());
+            }
+            // Skip a corrupt frame and keep going, as players do
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(failed(e)),
+        }
+    }
+
+    let mono = to_mono(&samples, channels);
+    Ok(Resampler::new(sample_rate, TARGET_SAMPLE_RATE).process(&mono))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+    }
+
+    #[test]
+    fn decodes_aac_in_m4a() {
+        // About 10 s of stereo music at 44.1 kHz
+        let samples = load_audio_file(&fixture("music.m4a")).unwrap();
+        assert!(samples.len().abs_diff(10 * 16_000) < 8_000, "{} samples", samples.len());
+        assert!(samples.iter().any(|s| s.abs() > 0.1));
+    }
+
+    #[test]
+    fn decodes_vorbis_in_ogg() {
+        // A 10 s beep
+        let samples = load_audio_file(&fixture("beep.ogg")).unwrap();
+        assert!(samples.len().abs_diff(10 * 16_000) < 8_000, "{} samples", samples.len());
+        assert!(samples.iter().any(|s| s.abs() > 0.1));
+    }
+
+    #[test]
+    fn truncated_files_are_an_error() {
+        let bytes = std::fs::read(fixture("music.m4a")).unwrap();
+        let path = std::env::temp_dir().join(format!("truncated-{}.m4a", std::process::id()));
+        std::fs::write(&path, &bytes[..4096]).unwrap();
+        let result = load_audio_file(&path);
+        std::fs::remove_file(&path).ok();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn refuses_unknown_extensions() {
+        let err = load_audio_file(Path::new("notes.aiff")).unwrap_err();
+        assert!(err.contains(".aiff"), "{}", err);
+        assert!(err.contains("m4a"), "{}", err);
+    }
+}
@@ -0,0 +1,215 @@
+use super::clipping::CLIP_WARN_RATIO;
+use super::TARGET_SAMPLE_RATE;
+use serde::Serialize;
+use std::ops::Range;
+
+/// Frame length used for level analysis (20ms).
+const FRAME_SAMPLES: usize = TARGET_SAMPLE_RATE as usize / 50;
+
+/// Audio kept around detected speech when trimming silence (200ms).
+const TRIM_PADDING_SAMPLES: usize = TARGET_SAMPLE_RATE as usize / 5;
+
+/// Shortest quiet stretch treated as a pause between phrases (300ms); the
+/// gaps between words inside a phrase are shorter.
+const MIN_PAUSE_FRAMES: usize = 15;
+
+/// A mic test peaking below this (-20 dBFS after gain) is too quiet.
+const MIN_GOOD_PEAK: f32 = 0.1;
+
+/// Verdict of a microphone test.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MicLevel {
+    TooQuiet,
+    Good,
+    Clipping,
+}
+
+/// Judge a test recording by its peak and the share of samples the gain
+/// stage clamped.
+pub fn classify_mic_level(peak: f32, clipped_ratio: f32) -> MicLevel {
+    if clipped_ratio > CLIP_WARN_RATIO {
+        MicLevel::Clipping
+    } else if peak < MIN_GOOD_PEAK {
+        MicLevel::TooQuiet
+    } else {
+        MicLevel::Good
+    }
+}
+
+/// Largest absolute sample value.
+pub fn peak(samples: &[f32]) -> f32 {
+    samples.iter().fold(0.0, |max, s| max.max(s.abs()))
+}
+
+/// Root-mean-square level of a block of samples.
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum_sq: f32 = samples.iter().map(|s| s * s).sum();
+    (sum_sq / samples.len() as f32).sqrt()
+}
+
+/// Convert a linear level to dBFS (0 dBFS = full scale).
+pub fn to_dbfs(level: f32) -> f32 {
+    if level <= 0.0 {
+        return f32::NEG_INFINITY;
+    }
+    20.0 * level.log10()
+}
+
+/// Speech threshold derived from a measured ambient level: ~6 dB above the
+/// noise floor, with a small minimum so a dead-silent room doesn't gate everything.
+pub fn threshold_from_noise_floor(noise_rms: f32) -> f32 {
+    (noise_rms * 2.0).max(0.001)
+}
+
+/// Level separating speech from room noise: the configured silence threshold,
+/// else one derived from the calibrated noise floor, else a conservative default.
+pub fn speech_threshold(silence_threshold: f32, noise_floor_rms: f32) -> f32 {
+    if silence_threshold > 0.0 {
+        silence_threshold
+    } else if noise_floor_rms > 0.0 {
+        threshold_from_noise_floor(noise_floor_rms)
+    } else {
+        0.01
+    }
+}
+
+/// Whether any 20ms frame reaches `threshold`.
+pub fn has_speech(samples: &[f32], threshold: f32) -> bool {
+    samples.chunks(FRAME_SAMPLES).any(|frame| rms(frame) >= threshold)
+}
+
+/// Cut leading and trailing audio whose level stays below `threshold`,
+/// keeping a little padding so word onsets aren't clipped.
+/// Returns the input unchanged if no frame exceeds the threshold.
+pub fn trim_silence(samples: &[f32], threshold: f32) -> Vec<f32> {
+    let loud = |frame: &[f32]| rms(frame) >= threshold;
+
+    let frames: Vec<&[f32]> = samples.chunks(FRAME_SAMPLES).collect();
+    let Some(first) = frames.iter().position(|f| loud(f)) else {
+        return samples.to_vec();
+    };
+    let last = frames.iter().rposition(|f| loud(f)).unwrap_or(first);
+
+    let start = (first * FRAME_SAMPLES).saturating_sub(TRIM_PADDING_SAMPLES);
+    let end = ((last + 1) * FRAME_SAMPLES + TRIM_PADDING_SAMPLES).min(samples.len());
+    samples[start..end].to_vec()
+}
+
+/// Split a long recording into chunks of at most `max_len` samples, cutting
+/// only in the middle of pauses (quiet stretches of 300ms or more) so no word
+/// is cut in half. A stretch of speech longer than `max_len` with no pause in
+/// it is cut at its quietest frame in the second half of the allowed length.
+/// Chunks with no frame above `threshold` are dropped.
+pub fn split_at_pauses(samples: &[f32], threshold: f32, max_len: usize) -> Vec<Range<usize>> {
+    let levels: Vec<f32> = samples.chunks(FRAME_SAMPLES).map(rms).collect();
+    let loud: Vec<bool> = levels.iter().map(|&level| level >= threshold).collect();
+
+    // Middle of every pause, in samples
+    let mut pauses = Vec::new();
+    let mut quiet_from = None;
+    for (i, &is_loud) in loud.iter().chain(std::iter::once(&true)).enumerate() {
+        match (is_loud, quiet_from) {
+            (false, None) => quiet_from = Some(i),
+            (true, Some(from)) => {
+                if i - from >= MIN_PAUSE_FRAMES && from > 0 && i < loud.len() {
+                    pauses.push((from + i) / 2 * FRAME_SAMPLES);
+                }
+                quiet_from = None;
+            }
+            _ => {}
+        }
+    }
+
+    // Cut at the last pause that keeps each chunk within max_len
+    let mut cuts = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<usize> = None;
+    for pause in pauses.into_iter().chain(std::iter::once(samples.len())) {
+        while pause - start > max_len {
+            let cut = previous
+                .filter(|&p| p > start)
+                .unwrap_or_else(|| quietest_cut(&levels, start, max_len));
+            cuts.push(cut);
+            start = cut;
+        }
+        previous = Some(pause);
+    }
+
+    std::iter::once(0)
+        .chain(cuts.iter().copied())
+        .zip(cuts.iter().copied().chain(std::iter::once(samples.len())))
+        .map(|(start, end)| start..end)
+        .filter(|range| {
+            let frames = range.start / FRAME_SAMPLES..range.end.div_ceil(FRAME_SAMPLES);
+            loud[frames].iter().any(|&l| l)
+        })
+        .collect()
+}
+
+/// Start of the quietest frame between half of `max_len` and `max_len` past
+/// `start`, the latest one on a tie so chunks stay as long as allowed.
+fn quietest_cut(levels: &[f32], start: usize, max_len: usize) -> usize {
+    let first = (start + max_len / 2).div_ceil(FRAME_SAMPLES);
+    let last = ((start + max_len) / FRAME_SAMPLES).min(levels.len().saturating_sub(1));
+    if first > last {
+        return start + max_len;
+    }
+    let quietest = (first..=last)
+        .reduce(|best, i| if levels[i] <= levels[best] { i } else { best })
+        .unwrap_or(last);
+    quietest * FRAME_SAMPLES
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: usize = FRAME_SAMPLES;
+
+    /// `frames` frames of a steady level.
+    fn level(value: f32, frames: usize) -> Vec<f32> {
+        vec![value; frames * FRAME]
+    }
+
+    #[test]
+    fn speech_is_any_frame_at_the_threshold() {
+        assert!(!has_speech(&level(0.0, 10), 0.01));
+        assert!(has_speech(&[level(0.0, 10), level(0.5, 1)].concat(), 0.01));
+    }
+
+    #[test]
+    fn all_silence_gives_no_chunks() {
+        assert!(split_at_pauses(&level(0.0, 200), 0.01, 50 * FRAME).is_empty());
+    }
+
+    #[test]
+    fn speech_without_pauses_is_cut_at_max_len() {
+        let samples = level(0.5, 150);
+        assert_eq!(
+            split_at_pauses(&samples, 0.01, 50 * FRAME),
+            vec![0..50 * FRAME, 50 * FRAME..100 * FRAME, 100 * FRAME..150 * FRAME]
+        );
+    }
+
+    #[test]
+    fn cut_is_made_at_a_pause_that_ends_exactly_at_max_len() {
+        // Silence, speech, a pause, speech, silence. The edge silences are no
+        // cut points; the middle pause's midpoint is at frame 85
+        let samples = [
+            level(0.0, 25),
+            level(0.5, 50),
+            level(0.0, 20),
+            level(0.5, 50),
+            level(0.0, 25),
+        ]
+        .concat();
+        assert_eq!(
+            split_at_pauses(&samples, 0.01, 85 * FRAME),
+            vec![0..85 * FRAME, 85 * FRAME..170 * FRAME]
+        );
+    }
+}
@@ -1,3 +1,19 @@
 pub mod buffer;
 pub mod capture;
+pub mod clipping;
+pub mod denoise;
 pub mod devices;
+pub mod file;
+pub mod filter;
+pub mod level;
+pub mod preprocess;
+pub mod wav;
+
+/// Sample rate Whisper expects. Capture resamples to this rate and every
+/// buffer downstream of it holds samples at this rate.
+pub const TARGET_SAMPLE_RATE: u32 = 16000;
+
+/// Duration in seconds of `n` samples at TARGET_SAMPLE_RATE.
+pub fn samples_to_secs(n: usize) -> f32 {
+    n as f32 / TARGET_SAMPLE_RATE as f32
+}
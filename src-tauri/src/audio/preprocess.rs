@@ -0,0 +1,98 @@
+//! Clean-up applied to captured audio before Whisper sees it. The streaming
+//! preview and the final transcription both go through `run`, so the preview
+//! hears the same audio the final pass will.
+
+use super::{denoise, level, samples_to_secs, TARGET_SAMPLE_RATE};
+
+/// Fade length used when none is configured. Long enough to smooth a cut
+/// mid-word, short enough not to eat a consonant.
+pub const DEFAULT_FADE_MS: u32 = 5;
+
+/// Which stages are enabled, taken from the settings.
+#[derive(Debug, Clone, Default)]
+pub struct Preprocess {
+    /// Calibrated noise spectrum to subtract (empty = no denoising).
+    pub noise_profile: Vec<f32>,
+    /// RMS below which leading/trailing audio is trimmed (0 = no trimming).
+    pub silence_threshold: f32,
+    /// Length of the fade-out at the end of the audio (0 = none).
+    pub fade_ms: u32,
+    /// Also fade in over `fade_ms` at the start.
+    pub fade_in: bool,
+}
+
+/// Denoise, trim leading/trailing silence, then fade the edges.
+pub fn run(samples: Vec<f32>, options: &Preprocess) -> Vec<f32> {
+    let samples = if !options.noise_profile.is_empty() {
+        let started = std::time::Instant::now();
+        let cleaned = denoise::spectral_subtract(&samples, &options.noise_profile);
+        log::debug!(
+            "Denoised {:.1}s of audio in {:?}",
+            samples_to_secs(samples.len()),
+            started.elapsed()
+        );
+        cleaned
+    } else {
+        samples
+    };
+
+    let mut samples = if options.silence_threshold > 0.0 {
+        level::trim_silence(&samples, options.silence_threshold)
+    } else {
+        samples
+    };
+
+    let fade_len = (options.fade_ms as usize * TARGET_SAMPLE_RATE as usize) / 1000;
+    fade_edges(&mut samples, if options.fade_in { fade_len } else { 0 }, fade_len);
+    samples
+}
+
+/// Ramp the first `fade_in` samples up from zero and the last `fade_out`
+/// down to zero, so a recording cut off mid-word doesn't end on a click
+/// Whisper may hear as a token. Fades are shortened to fit short audio.
+pub fn fade_edges(samples: &mut [f32], fade_in: usize, fade_out: usize) {
+    let len = samples.len();
+    let fade_in = fade_in.min(len / 2);
+    let fade_out = fade_out.min(len / 2);
+    for (i, sample) in samples[..fade_in].iter_mut().enumerate() {
+        *sample *= i as f32 / fade_in as f32;
+    }
+    for (i, sample) in samples[len - fade_out..].iter_mut().rev().enumerate() {
+        *sample *= i as f32 / fade_out as f32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fades_scale_the_edges_toward_zero() {
+        let mut samples = vec![1.0; 100];
+        fade_edges(&mut samples, 10, 20);
+        assert_eq!(samples[0], 0.0);
+        assert_eq!(samples[99], 0.0);
+        assert!((samples[5] - 0.5).abs() < 1e-6);
+        assert!((samples[89] - 0.5).abs() < 1e-6);
+        // Ramps rise monotonically into the untouched middle
+        assert!(samples[..10].windows(2).all(|w| w[0] < w[1]));
+        assert!(samples[80..].windows(2).all(|w| w[0] > w[1]));
+        assert!(samples[10..80].iter().all(|&s| s == 1.0));
+    }
+
+    #[test]
+    fn fade_out_only_leaves_the_start() {
+        let mut samples = vec![0.8; 50];
+        fade_edges(&mut samples, 0, 10);
+        assert_eq!(samples[0], 0.8);
+        assert_eq!(samples[49], 0.0);
+    }
+
+    #[test]
+    fn fades_shrink_to_fit_short_audio() {
+        let mut samples = vec![1.0; 6];
+        fade_edges(&mut samples, 100, 100);
+        assert_eq!(samples, [0.0, 1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0, 1.0 / 3.0, 0.0]);
+        fade_edges(&mut [], 10, 10);
+    }
+}
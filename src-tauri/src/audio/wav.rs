@@ -0,0 +1,29 @@
+use std::path::Path;
+
+use super::capture::{to_mono, Resampler};
+use super::TARGET_SAMPLE_RATE;
+
+/// Read a WAV file as mono f32 samples at TARGET_SAMPLE_RATE, ready for Whisper.
+pub fn load_wav(path: &Path) -> Result<Vec<f32>, String> {
+    let mut reader = hound::WavReader::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let spec = reader.spec();
+
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        }
+    };
+
+    let mono = to_mono(&samples, spec.channels as usize);
+    Ok(Resampler::new(spec.sample_rate, TARGET_SAMPLE_RATE).process(&mono))
+}
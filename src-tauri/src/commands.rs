@@ -1,247 +1,2664 @@
+use std::collections::BTreeMap;
 use std::sync::Mutex;
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut};
 
 use crate::audio::buffer::AudioBuffer;
 use crate::audio::capture::AudioCapture;
+use crate::audio::devices::{self, AudioDeviceInfo, SupportedConfigInfo};
+use crate::audio::{denoise, level};
 use crate::config::AppConfig;
-use crate::settings::Settings;
-use crate::state::{AppState, AppStatus};
+use crate::postprocess::PostProcessSettings;
+use crate::formatting::PromptPreset;
+use crate::settings::{
+    AutoAdvance, ClipboardRestore, EmptyFeedback, HotkeyBinding, IndicatorMonitor,
+    IndicatorPosition, InjectionMethod, OutputMode, RecordingMode, SaveScheduler, Settings,
+    TranscriptionMode,
+};
+use crate::state::{AppState, AppStatus, HistoryEntry, TranscriptionPair};
+use crate::system::double_tap::DoubleTap;
 use crate::system::sounds::SoundPlayer;
 use crate::system::text_injection;
-use crate::transcription::engine::WhisperEngine;
+use crate::transcription::engine::{
+    ContextSettings, ModelOverrides, TranscriptionSettings, WhisperEngine,
+};
+use crate::transcription::benchmark::{self, BenchmarkResult};
+use crate::transcription::models::{self, MemoryAdvisory, ModelCapabilities, ModelInfo};
 
 #[tauri::command]
 pub async fn start_recording(
+    app: AppHandle,
     state: State<'_, Mutex<AppState>>,
     capture: State<'_, Mutex<AudioCapture>>,
     buffer: State<'_, AudioBuffer>,
 ) -> Result<String, String> {
-    {
+    let session = {
         let mut app_state = state.lock().map_err(|e| e.to_string())?;
-        if app_state.status == AppStatus::Recording {
+        if app_state.status.in_recording() {
             return Err("Already recording".to_string());
         }
+        if !app_state.status.allows(&AppStatus::Recording) {
+            let status = app_state.status.clone();
+            drop(app_state);
+            crate::reject_recording_start(&app, &status);
+            return Err("Busy — wait for the current dictation to finish".to_string());
+        }
         buffer.clear();
         app_state.status = AppStatus::Recording;
-    }
+        app_state.recording_session += 1;
+        app_state.recording_session
+    };
+    let _ = app.emit("status-changed", "Recording");
 
     let mut cap = capture.lock().map_err(|e| e.to_string())?;
-    let sample_rate = cap.start()?;
+    let sample_rate = match cap.start() {
+        Ok(rate) => rate,
+        Err(e) => {
+            state.lock().map_err(|e| e.to_string())?.status = AppStatus::Error(e.clone());
+            let _ = app.emit("status-changed", "Error");
+            return Err(e);
+        }
+    };
+
+    {
+        let mut app_state = state.lock().map_err(|e| e.to_string())?;
+        app_state.device_sample_rate = sample_rate;
+    }
+    crate::spawn_recording_monitors(&app, session, &cap);
+
+    Ok(format!("Recording at {} Hz", sample_rate))
+}
+
+#[tauri::command]
+pub async fn stop_recording_and_transcribe(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    capture: State<'_, Mutex<AudioCapture>>,
+    buffer: State<'_, AudioBuffer>,
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<String, String> {
+    // Claim the stop under one lock, so a hotkey or auto-stop racing this
+    // call can't take the buffer too
+    {
+        let mut app_state = state.lock().map_err(|e| e.to_string())?;
+        if !app_state.status.in_recording() {
+            return Err("Not recording".to_string());
+        }
+        app_state.status = AppStatus::Transcribing;
+    }
+    let _ = app.emit("status-changed", "Transcribing");
+
+    // Stop recording
+    {
+        let mut cap = capture.lock().map_err(|e| e.to_string())?;
+        cap.stop();
+    }
+
+    let samples = buffer.take_samples();
+    if samples.is_empty() {
+        state.lock().map_err(|e| e.to_string())?.status = AppStatus::Idle;
+        let _ = app.emit("status-changed", "Idle");
+        return Err("No audio recorded".to_string());
+    }
+
+    log::info!(
+        "Transcribing {} samples ({:.1}s of audio)",
+        samples.len(),
+        crate::audio::samples_to_secs(samples.len())
+    );
+
+    // Transcribe. Every way out below leaves the status Idle, or a later
+    // recording would be refused as still in flight
+    let language = crate::session_language(&app, &samples).await;
+    let text = match crate::transcribe_blocking(&app, samples, language).await {
+        Ok(text) => text,
+        Err(e) if crate::transcription::engine::is_model_crash(&e) => {
+            log::error!("Transcription failed: {}", e);
+            state.lock().map_err(|e| e.to_string())?.status = AppStatus::Error(e.clone());
+            let _ = app.emit("status-changed", "Error");
+            let _ = app.emit("app-error", e.clone());
+            return Err(e);
+        }
+        Err(e) => {
+            log::error!("Transcription failed: {}", e);
+            state.lock().map_err(|e| e.to_string())?.status = AppStatus::Idle;
+            let _ = app.emit("status-changed", "Idle");
+            return Err(e);
+        }
+    };
+
+    if text.is_empty() {
+        state.lock().map_err(|e| e.to_string())?.status = AppStatus::Idle;
+        let _ = app.emit("status-changed", "Idle");
+        return Err("No speech detected".to_string());
+    }
+
+    // Deliver the text the way the output mode says, as the hotkey flow does
+    let (output_mode, output_file, postprocess_settings) = {
+        let s = settings.lock().map_err(|e| e.to_string())?;
+        (s.output_mode, s.output_file.clone(), s.postprocess.clone())
+    };
+    let text = crate::postprocess::redact_raw(&text, &postprocess_settings);
+    log::info!("Transcription: {}", text);
+
+    if output_mode.writes_file() {
+        match crate::system::file_output::append_dictation(&output_file, &text) {
+            Ok(path) => log::info!("Dictation appended to {:?}", path),
+            Err(e) => {
+                log::error!("Appending dictation failed: {}", e);
+                let _ = app.emit("app-error", e);
+            }
+        }
+    }
+    let injected = if output_mode.injects() {
+        state.lock().map_err(|e| e.to_string())?.status = AppStatus::Injecting;
+        let _ = app.emit("status-changed", "Injecting");
+
+        let options = crate::injection_options(&app);
+        let pasted = text.clone();
+        tauri::async_runtime::spawn_blocking(move || text_injection::inject_text(&pasted, &options))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|injected| injected.map_err(|e| e.user_message()))
+            .map(|injected| crate::report_injection(&app, &injected))
+    } else {
+        Ok(())
+    };
+
+    // Done
+    {
+        let mut app_state = state.lock().map_err(|e| e.to_string())?;
+        app_state.last_transcription = text.clone();
+        app_state.last_raw_transcription = text.clone();
+        app_state.status = AppStatus::Idle;
+    }
+    let _ = app.emit("status-changed", "Idle");
+
+    injected?;
+    Ok(text)
+}
+
+/// Stop capturing but keep the buffer, so resume_recording appends to it.
+#[tauri::command]
+pub fn pause_recording(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    capture: State<'_, Mutex<AudioCapture>>,
+) -> Result<(), String> {
+    let mut app_state = state.lock().map_err(|e| e.to_string())?;
+    if app_state.status != AppStatus::Recording {
+        return Err("Not recording".to_string());
+    }
+    capture.lock().map_err(|e| e.to_string())?.stop();
+    app_state.status = AppStatus::Paused;
+    drop(app_state);
+
+    log::info!("Recording paused");
+    let _ = app.emit("status-changed", "Paused");
+    Ok(())
+}
+
+/// Restart capture after pause_recording, appending to the same buffer.
+#[tauri::command]
+pub fn resume_recording(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    capture: State<'_, Mutex<AudioCapture>>,
+) -> Result<(), String> {
+    let mut app_state = state.lock().map_err(|e| e.to_string())?;
+    if app_state.status != AppStatus::Paused {
+        return Err("Recording is not paused".to_string());
+    }
+    let rate = capture.lock().map_err(|e| e.to_string())?.start()?;
+    app_state.status = AppStatus::Recording;
+    app_state.device_sample_rate = rate;
+    drop(app_state);
+
+    log::info!("Recording resumed at {} Hz", rate);
+    let _ = app.emit("status-changed", "Recording");
+    Ok(())
+}
+
+/// The `n` most recent dictations, newest first.
+#[tauri::command]
+pub fn get_recent_transcriptions(
+    n: usize,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Vec<HistoryEntry>, String> {
+    let app_state = state.lock().map_err(|e| e.to_string())?;
+    Ok(app_state.history.iter().take(n).cloned().collect())
+}
+
+/// Paste history entry `index` (0 = newest) into the focused app.
+#[tauri::command]
+pub async fn reinject_history(index: usize, app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || crate::reinject_history(&app, index))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Default wait before a test paste, so the user can focus their editor.
+const TEST_INJECTION_DELAY_MS: u64 = 3000;
+
+/// Paste `text` into the focused app without recording, to debug injection
+/// (permissions, clipboard) on its own. Returns the structured error on failure.
+/// Refused unless idle; the status reads Injecting from the delay to the end
+/// of the paste.
+#[tauri::command]
+pub async fn test_injection(
+    text: String,
+    delay_ms: Option<u64>,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<(), text_injection::InjectionError> {
+    let internal = |e: String| text_injection::InjectionError::Internal(e);
+    {
+        let mut s = state.lock().map_err(|e| internal(e.to_string()))?;
+        if !s.claim_from_idle(AppStatus::Injecting) {
+            return Err(text_injection::InjectionError::Busy);
+        }
+    }
+    let _ = app.emit("status-changed", "Injecting");
+
+    let delay = delay_ms.unwrap_or(TEST_INJECTION_DELAY_MS);
+    tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+
+    let options = crate::injection_options(&app);
+    let injected =
+        tauri::async_runtime::spawn_blocking(move || text_injection::inject_text(&text, &options))
+            .await
+            .map_err(|e| internal(e.to_string()))
+            .and_then(|injected| injected);
+
+    state.lock().map_err(|e| internal(e.to_string()))?.status = AppStatus::Idle;
+    let _ = app.emit("status-changed", "Idle");
+
+    match injected {
+        Ok(injected) => {
+            log::info!("Test injection succeeded");
+            crate::report_injection(&app, &injected);
+            Ok(())
+        }
+        Err(e) => {
+            log::warn!("Test injection failed: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// What `inject` did with the text.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct InjectResult {
+    /// False when the text was deliberately not sent; `skipped` says why.
+    pub injected: bool,
+    pub skipped: Option<String>,
+    /// How the text went in: Paste or Type (Auto is resolved per text).
+    pub method: InjectionMethod,
+    /// Window title the text was pasted into (None = the focused app).
+    pub target_window: Option<String>,
+    pub chars: usize,
+}
+
+/// Paste arbitrary text like a finished dictation, for scripts that want
+/// Wispr as a paste engine. Uses the configured injection method and paste
+/// shortcut; `target_window` overrides the configured target (Some("") =
+/// the focused app). Skipped, not failed, while the output mode doesn't
+/// paste or a dictation is in progress. The status reads Injecting during
+/// the paste, so no dictation starts meanwhile. Undo works on it like on a
+/// dictation.
+#[tauri::command]
+pub async fn inject(
+    text: String,
+    target_window: Option<String>,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<InjectResult, text_injection::InjectionError> {
+    let internal = |e: String| text_injection::InjectionError::Internal(e);
+    let options = crate::injection_options(&app);
+    let (output_mode, target, restore_focus) = {
+        let s = settings.lock().map_err(|e| internal(e.to_string()))?;
+        let target = target_window.unwrap_or_else(|| s.target_window.clone());
+        (s.output_mode, target.trim().to_string(), s.restore_focus)
+    };
+    let mut result = InjectResult {
+        injected: false,
+        skipped: None,
+        method: if options.types(&text) {
+            InjectionMethod::Type
+        } else {
+            InjectionMethod::Paste
+        },
+        target_window: (!target.is_empty()).then(|| target.clone()),
+        chars: text.chars().count(),
+    };
+
+    {
+        // Checked and claimed under one lock, so a hotkey press can't start
+        // a recording in between
+        let mut s = state.lock().map_err(|e| internal(e.to_string()))?;
+        result.skipped = if text.is_empty() {
+            Some("Nothing to paste".to_string())
+        } else if !output_mode.injects() {
+            Some(format!("Output mode {:?} doesn't paste", output_mode))
+        } else if !s.claim_from_idle(AppStatus::Injecting) {
+            Some("Busy — wait for the current dictation to finish".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = &result.skipped {
+            log::info!("Not injecting external text: {}", reason);
+            return Ok(result);
+        }
+    }
+    let _ = app.emit("status-changed", "Injecting");
+
+    let injected = tauri::async_runtime::spawn_blocking(move || {
+        let window = if target.is_empty() {
+            crate::system::window::foreground_window()
+        } else {
+            crate::system::window::find_window(&target).ok().flatten()
+        };
+        let injected = if target.is_empty() {
+            text_injection::inject_text(&text, &options)
+        } else {
+            text_injection::inject_text_into_window(&text, &target, restore_focus, &options)
+        };
+        injected.map(|injected| (injected, window))
+    })
+    .await
+    .map_err(|e| internal(e.to_string()))
+    .and_then(|injected| injected);
+
+    let mut s = state.lock().map_err(|e| internal(e.to_string()))?;
+    s.status = AppStatus::Idle;
+    if let Ok((_, window)) = &injected {
+        s.last_injected_window = *window;
+        s.last_injected_chars = Some(result.chars);
+    }
+    drop(s);
+    let _ = app.emit("status-changed", "Idle");
+
+    let (injected, _) = injected?;
+    crate::report_injection(&app, &injected);
+    log::info!("Injected {} characters from the inject command", result.chars);
+    result.injected = true;
+    Ok(result)
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TargetWindowSettings {
+    /// Title substring of the window to paste into (empty = focused app).
+    pub title: String,
+    pub restore_focus: bool,
+}
+
+#[tauri::command]
+pub fn get_target_window(
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<TargetWindowSettings, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(TargetWindowSettings {
+        title: s.target_window.clone(),
+        restore_focus: s.restore_focus,
+    })
+}
+
+/// Set the window dictation is always pasted into. Rejected on platforms
+/// without window lookup; a window that isn't open yet is only logged.
+#[tauri::command]
+pub fn set_target_window(
+    target: TargetWindowSettings,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let title = target.title.trim().to_string();
+    if !title.is_empty() && crate::system::window::find_window(&title)?.is_none() {
+        log::warn!("Target window {:?} is not open right now", title);
+    }
+
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.target_window = title;
+    s.restore_focus = target.restore_focus;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_auto_advance(settings: State<'_, Mutex<Settings>>) -> Result<AutoAdvance, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.auto_advance)
+}
+
+/// Form-filling: press Tab or Enter after each pasted dictation ("off" to
+/// disable). Skipped when focus is handed back after a target-window paste.
+#[tauri::command]
+pub fn set_auto_advance(
+    advance: AutoAdvance,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.auto_advance = advance;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_paste_shortcut(settings: State<'_, Mutex<Settings>>) -> Result<String, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.paste_shortcut.clone())
+}
+
+/// Override the keys used to paste, for apps with a nonstandard binding
+/// (e.g. "Ctrl+Shift+V" in terminals). Empty restores the platform default.
+#[tauri::command]
+pub fn set_paste_shortcut(
+    shortcut: String,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let shortcut = shortcut.trim().to_string();
+    text_injection::PasteShortcut::parse(&shortcut)?;
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.paste_shortcut = shortcut;
+    saver.schedule();
+    Ok(())
+}
+
+/// Longest text the Auto method may type; typing is much slower than pasting.
+const MAX_TYPE_CHARS: usize = 500;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct InjectionMethodSettings {
+    pub method: InjectionMethod,
+    /// With "auto", text up to this many characters is typed, longer text pasted.
+    pub type_max_chars: usize,
+}
+
+#[tauri::command]
+pub fn get_injection_method(
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<InjectionMethodSettings, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(InjectionMethodSettings {
+        method: s.injection_method,
+        type_max_chars: s.type_max_chars,
+    })
+}
+
+/// Paste through the clipboard, type as keystrokes, or pick by length
+/// ("auto": short dictations are typed, keeping the clipboard untouched).
+#[tauri::command]
+pub fn set_injection_method(
+    injection: InjectionMethodSettings,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    if injection.type_max_chars == 0 || injection.type_max_chars > MAX_TYPE_CHARS {
+        return Err(format!(
+            "Typing threshold must be between 1 and {} characters",
+            MAX_TYPE_CHARS
+        ));
+    }
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.injection_method = injection.method;
+    s.type_max_chars = injection.type_max_chars;
+    saver.schedule();
+    log::info!(
+        "Injection method set to {:?} (type up to {} chars)",
+        injection.method,
+        injection.type_max_chars
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_clipboard_restore(
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<ClipboardRestore, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.clipboard_restore)
+}
+
+/// After a paste, put the old clipboard back ("always"), leave the dictation
+/// on it ("never"), or put it back and raise "clipboard-restore-failed" when
+/// that doesn't work ("restore_or_warn").
+#[tauri::command]
+pub fn set_clipboard_restore(
+    restore: ClipboardRestore,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.clipboard_restore = restore;
+    saver.schedule();
+    log::info!("Clipboard restore set to {:?}", restore);
+    Ok(())
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct IndicatorSettings {
+    pub enabled: bool,
+    pub position: IndicatorPosition,
+    #[serde(default)]
+    pub monitor: IndicatorMonitor,
+}
+
+#[tauri::command]
+pub fn get_indicator_settings(
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<IndicatorSettings, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(IndicatorSettings {
+        enabled: s.show_indicator,
+        position: s.indicator_position,
+        monitor: s.indicator_monitor.clone(),
+    })
+}
+
+/// Turn the recording indicator on/off or move it to another corner or
+/// display. Applies immediately, including mid-dictation.
+#[tauri::command]
+pub fn set_indicator_settings(
+    indicator: IndicatorSettings,
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    {
+        let mut s = settings.lock().map_err(|e| e.to_string())?;
+        s.show_indicator = indicator.enabled;
+        s.indicator_position = indicator.position;
+        s.indicator_monitor = indicator.monitor;
+        saver.schedule();
+    }
+    crate::system::indicator::update(&app);
+    Ok(())
+}
+
+/// A connected display, for choosing where the indicator appears.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MonitorInfo {
+    /// Pass as IndicatorMonitor::Named to pin the indicator to this display.
+    pub name: Option<String>,
+    pub primary: bool,
+    /// Physical pixels.
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+}
+
+#[tauri::command]
+pub fn list_monitors(app: AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    let primary = app.primary_monitor().map_err(|e| e.to_string())?;
+    let primary_name = primary.as_ref().and_then(|m| m.name().cloned());
+    let monitors = app.available_monitors().map_err(|e| e.to_string())?;
+    Ok(monitors
+        .into_iter()
+        .map(|m| MonitorInfo {
+            primary: primary_name.is_some() && m.name() == primary_name.as_ref(),
+            name: m.name().cloned(),
+            x: m.position().x,
+            y: m.position().y,
+            width: m.size().width,
+            height: m.size().height,
+            scale_factor: m.scale_factor(),
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OutputSettings {
+    pub mode: OutputMode,
+    /// Absolute path; {date}, {time} (as HH-MM) and {weekday} are expanded on
+    /// each write.
+    pub file_path: String,
+}
+
+#[tauri::command]
+pub fn get_output_settings(settings: State<'_, Mutex<Settings>>) -> Result<OutputSettings, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(OutputSettings {
+        mode: s.output_mode,
+        file_path: s.output_file.clone(),
+    })
+}
+
+/// Choose where dictations go. File modes need a usable path; the file and
+/// its folders are created on the first write.
+#[tauri::command]
+pub fn set_output_settings(
+    output: OutputSettings,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let mode = output.mode;
+    let file_path = output.file_path.trim().to_string();
+    if mode.writes_file() {
+        crate::system::file_output::validate_path(&file_path)?;
+    }
+
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.output_mode = mode;
+    s.output_file = file_path;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_post_command(settings: State<'_, Mutex<Settings>>) -> Result<String, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.post_command.clone())
+}
+
+/// Set the command run with every finished dictation (empty = off). The
+/// dictation is passed on stdin, or in place of "{text}" in the arguments.
+#[tauri::command]
+pub fn set_post_command(
+    command: String,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let command = command.trim().to_string();
+    if !command.is_empty() {
+        crate::system::post_command::parse(&command)?;
+        log::warn!("Post-dictation command set: every dictation will be passed to {:?}", command);
+    } else {
+        log::info!("Post-dictation command cleared");
+    }
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.post_command = command;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_status(state: State<'_, Mutex<AppState>>) -> Result<String, String> {
+    let app_state = state.lock().map_err(|e| e.to_string())?;
+    let status = match &app_state.status {
+        AppStatus::Idle => "Idle".to_string(),
+        AppStatus::Recording => "Recording".to_string(),
+        AppStatus::Paused => "Paused".to_string(),
+        AppStatus::Transcribing => "Transcribing".to_string(),
+        AppStatus::Formatting => "Formatting".to_string(),
+        AppStatus::Injecting => "Injecting".to_string(),
+        AppStatus::Measuring => "Measuring".to_string(),
+        AppStatus::Error(e) => format!("Error: {}", e),
+    };
+    Ok(status)
+}
+
+/// Seconds of audio captured so far (paused time excluded), or 0 when no
+/// recording is open. Cheap enough to poll for an elapsed-time display.
+#[tauri::command]
+pub fn get_recording_duration(
+    state: State<'_, Mutex<AppState>>,
+    buffer: State<'_, AudioBuffer>,
+) -> Result<f32, String> {
+    let recording = state.lock().map_err(|e| e.to_string())?.status.in_recording();
+    if !recording {
+        return Ok(0.0);
+    }
+    Ok(crate::audio::samples_to_secs(buffer.len()))
+}
+
+#[tauri::command]
+pub fn is_model_loaded(state: State<'_, Mutex<AppState>>) -> Result<bool, String> {
+    // Read the mirrored flag: the engine lock is held for the whole model load
+    let app_state = state.lock().map_err(|e| e.to_string())?;
+    Ok(app_state.model_loaded)
+}
+
+#[tauri::command]
+pub fn get_last_transcription(state: State<'_, Mutex<AppState>>) -> Result<String, String> {
+    let app_state = state.lock().map_err(|e| e.to_string())?;
+    Ok(app_state.last_transcription.clone())
+}
+
+/// Put the last dictation on the clipboard without pasting it. Errors when
+/// nothing has been dictated yet.
+#[tauri::command]
+pub fn copy_last_transcription(app: AppHandle) -> Result<(), String> {
+    crate::copy_last_transcription(&app)
+}
+
+#[tauri::command]
+pub fn get_last_transcription_pair(
+    state: State<'_, Mutex<AppState>>,
+) -> Result<TranscriptionPair, String> {
+    let app_state = state.lock().map_err(|e| e.to_string())?;
+    Ok(TranscriptionPair {
+        raw: app_state.last_raw_transcription.clone(),
+        formatted: app_state.last_transcription.clone(),
+    })
+}
+
+/// Run the configured formatting chain (primary provider, then fallbacks) on
+/// `text` without dictating, for trying out prompts. Nothing is pasted.
+#[tauri::command]
+pub async fn format_preview(
+    text: String,
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<crate::formatting::FormatOutcome, String> {
+    if text.trim().is_empty() {
+        return Err("Enter some text to format".to_string());
+    }
+    let ai_chain = settings.lock().map_err(|e| e.to_string())?.ai_chain();
+    if ai_chain.is_empty() {
+        return Err("No AI provider is configured".to_string());
+    }
+    Ok(crate::formatting::format_text_detailed(&text, &ai_chain).await)
+}
+
+/// Re-run AI formatting on the last raw transcription with a different prompt,
+/// for tuning prompts without re-dictating. Optionally pastes the result.
+#[tauri::command]
+pub async fn reformat_last(
+    prompt: String,
+    inject: bool,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<String, String> {
+    if prompt.trim().is_empty() {
+        return Err("Prompt must not be empty".to_string());
+    }
+
+    let (ai_chain, postprocess_settings) = {
+        let s = settings.lock().map_err(|e| e.to_string())?;
+        let chain: Vec<_> = s
+            .ai_chain()
+            .into_iter()
+            .map(|mut ai| {
+                ai.prompt = prompt.clone();
+                ai
+            })
+            .collect();
+        (chain, s.postprocess.clone())
+    };
+    if ai_chain.is_empty() {
+        return Err("No AI provider is configured".to_string());
+    }
+
+    let (raw, language) = {
+        // Checked and claimed under one lock, so a hotkey press can't start
+        // a recording in between
+        let mut app_state = state.lock().map_err(|e| e.to_string())?;
+        if app_state.last_raw_transcription.is_empty() {
+            return Err("No transcription to reformat yet".to_string());
+        }
+        if !app_state.claim_from_idle(AppStatus::Formatting) {
+            return Err("Busy — wait for the current dictation to finish".to_string());
+        }
+        // The last recording's language is the last dictation's
+        let language = app_state.recording_language.clone().and_then(|(_, language)| language);
+        (app_state.last_raw_transcription.clone(), language)
+    };
+    let _ = app.emit("status-changed", "Formatting");
+
+    let text = crate::formatting::format_text(&raw, &ai_chain).await;
+    let text = crate::postprocess::process(&text, &postprocess_settings, language.as_deref());
+
+    let injected = if inject {
+        state.lock().map_err(|e| e.to_string())?.status = AppStatus::Injecting;
+        let _ = app.emit("status-changed", "Injecting");
+        let options = crate::injection_options(&app);
+        let pasted = text.clone();
+        tauri::async_runtime::spawn_blocking(move || text_injection::inject_text(&pasted, &options))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|injected| injected.map_err(|e| e.user_message()))
+            .map(|injected| crate::report_injection(&app, &injected))
+    } else {
+        Ok(())
+    };
+
+    {
+        let mut app_state = state.lock().map_err(|e| e.to_string())?;
+        app_state.last_transcription = text.clone();
+        app_state.status = AppStatus::Idle;
+    }
+    let _ = app.emit("status-changed", "Idle");
+    let _ = app.emit(
+        "transcription-complete",
+        TranscriptionPair {
+            raw,
+            formatted: text.clone(),
+        },
+    );
+
+    injected?;
+    Ok(text)
+}
+
+#[tauri::command]
+pub fn get_models_dir(config: State<'_, crate::config::AppConfig>) -> Result<String, String> {
+    Ok(config.models_dir.to_string_lossy().to_string())
+}
+
+/// Everything a bug report usually needs, safe to paste into a public issue.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Diagnostics {
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    /// whisper.cpp's build and CPU/GPU feature summary.
+    pub whisper_system_info: String,
+    pub model_filename: String,
+    pub model_loaded: bool,
+    /// Settings with secrets and personal text masked, see `scrub_settings`.
+    pub settings: serde_json::Value,
+}
+
+/// Settings fields that hold personal text, paths, commands or bulky data
+/// and are left out of diagnostics entirely, wherever they are nested.
+const DIAGNOSTICS_OMITTED: &[&str] = &[
+    "noise_profile",
+    "redact_words",
+    "prompt_presets",
+    "prompt",
+    "initial_prompt",
+    "prefix_text",
+    "suffix_text",
+    "post_command",
+    "output_file",
+    "start_sound",
+    "stop_sound",
+    "target_window",
+    "model_mirror_url",
+    "base_url",
+    "input_device",
+    "azure_resource",
+    "azure_deployment",
+];
+
+#[tauri::command]
+pub fn get_diagnostics(
+    settings: State<'_, Mutex<Settings>>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Diagnostics, String> {
+    let model_loaded = state.lock().map_err(|e| e.to_string())?.model_loaded;
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    let mut settings_json = serde_json::to_value(&*s).map_err(|e| e.to_string())?;
+    scrub_settings(&mut settings_json);
+    Ok(Diagnostics {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        whisper_system_info: whisper_rs::print_system_info().trim().to_string(),
+        model_filename: s.model_filename.clone(),
+        model_loaded,
+        settings: settings_json,
+    })
+}
+
+/// Mask anything that looks like a credential (a field named like api_key,
+/// *_token, *_secret, *_password or *_headers) and drop DIAGNOSTICS_OMITTED,
+/// at any depth, so fields added later are covered too. Empty values are
+/// kept so "not configured" is still visible.
+fn scrub_settings(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|name, _| !DIAGNOSTICS_OMITTED.contains(&name.as_str()));
+            for (name, field) in map.iter_mut() {
+                // Whole words only: "hotkey" is not a key
+                let secret = name.to_lowercase().split('_').any(|word| {
+                    matches!(word, "key" | "token" | "secret" | "password" | "headers")
+                });
+                let empty = match field {
+                    serde_json::Value::String(text) => text.is_empty(),
+                    serde_json::Value::Array(items) => items.is_empty(),
+                    serde_json::Value::Object(entries) => entries.is_empty(),
+                    serde_json::Value::Null | serde_json::Value::Bool(_) => true,
+                    serde_json::Value::Number(_) => false,
+                };
+                if secret && !empty {
+                    *field = serde_json::Value::String("[redacted]".to_string());
+                } else {
+                    scrub_settings(field);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(scrub_settings),
+        _ => {}
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RecordingLimits {
+    /// Shorter recordings are dropped without transcribing (0 = keep all).
+    pub min_recording_ms: u64,
+    /// Recordings are stopped automatically after this long (0 = no limit).
+    pub max_recording_seconds: u64,
+    /// Countdown before the automatic stop (0 = none).
+    pub warning_seconds: u64,
+    pub warning_sound: bool,
+}
+
+#[tauri::command]
+pub fn get_recording_limits(settings: State<'_, Mutex<Settings>>) -> Result<RecordingLimits, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(RecordingLimits {
+        min_recording_ms: s.min_recording_ms,
+        max_recording_seconds: s.max_recording_seconds,
+        warning_seconds: s.recording_warning_seconds,
+        warning_sound: s.recording_warning_sound,
+    })
+}
+
+#[tauri::command]
+pub fn set_recording_limits(
+    limits: RecordingLimits,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    if limits.max_recording_seconds > 0
+        && limits.min_recording_ms >= limits.max_recording_seconds * 1000
+    {
+        return Err("Minimum recording length must be below the maximum".to_string());
+    }
+    if limits.max_recording_seconds > 0 && limits.warning_seconds >= limits.max_recording_seconds {
+        return Err("The auto-stop warning must start before the maximum length".to_string());
+    }
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.min_recording_ms = limits.min_recording_ms;
+    s.max_recording_seconds = limits.max_recording_seconds;
+    s.recording_warning_seconds = limits.warning_seconds;
+    s.recording_warning_sound = limits.warning_sound;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_idle_unload_minutes(settings: State<'_, Mutex<Settings>>) -> Result<u64, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.idle_unload_minutes)
+}
+
+/// Free the model's memory after this many idle minutes (0 = never). It is
+/// reloaded when the next recording starts, which may delay that dictation's
+/// transcription by the model's load time.
+#[tauri::command]
+pub fn set_idle_unload_minutes(
+    minutes: u64,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.idle_unload_minutes = minutes;
+    saver.schedule();
+    Ok(())
+}
+
+/// Shortest accepted auto-stop silence. Anything less would cut people off
+/// during ordinary pauses between sentences.
+const MIN_AUTO_STOP_SILENCE_MS: u64 = 1000;
+
+#[tauri::command]
+pub fn get_auto_stop_silence(settings: State<'_, Mutex<Settings>>) -> Result<u64, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.auto_stop_silence_ms)
+}
+
+/// Hands-free stop: end a toggle/tray recording after `silence_ms` of quiet
+/// once speech was heard (0 = off). Takes effect from the next recording.
+#[tauri::command]
+pub fn set_auto_stop_silence(
+    silence_ms: u64,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    if silence_ms > 0 && silence_ms < MIN_AUTO_STOP_SILENCE_MS {
+        return Err(format!(
+            "Auto-stop silence must be at least {}ms (or 0 to disable)",
+            MIN_AUTO_STOP_SILENCE_MS
+        ));
+    }
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.auto_stop_silence_ms = silence_ms;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_chunk_seconds(settings: State<'_, Mutex<Settings>>) -> Result<u64, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.chunk_seconds)
+}
+
+/// Split recordings longer than `seconds` at pauses and transcribe the
+/// pieces separately, which keeps Whisper accurate on long dictations
+/// (0 = off). Whisper works in 30s windows, so 20-30 suits most speech.
+#[tauri::command]
+pub fn set_chunk_seconds(
+    seconds: u64,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    if seconds > 0 && !(10..=120).contains(&seconds) {
+        return Err(
+            "Chunk length must be between 10 and 120 seconds (or 0 to disable)".to_string(),
+        );
+    }
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.chunk_seconds = seconds;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_recording_mode(settings: State<'_, Mutex<Settings>>) -> Result<RecordingMode, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.recording_mode)
+}
+
+/// Switch hold/toggle/hybrid. The hotkey handler reads the mode on every
+/// event, so there's nothing to re-register.
+#[tauri::command]
+pub fn set_recording_mode(
+    app: AppHandle,
+    mode: String,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<RecordingMode, String> {
+    let mode = RecordingMode::parse(&mode)?;
+    {
+        let mut s = settings.lock().map_err(|e| e.to_string())?;
+        s.recording_mode = mode;
+        saver.schedule();
+    }
+    crate::system::tray::sync_recording_mode(&app, mode);
+    log::info!("Recording mode set to {:?}", mode);
+    Ok(mode)
+}
+
+/// Longer than this and a deliberate stop-then-start would be merged too.
+const MAX_STUTTER_TOLERANCE_MS: u64 = 1000;
+
+#[tauri::command]
+pub fn get_stutter_tolerance(settings: State<'_, Mutex<Settings>>) -> Result<u64, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.stutter_tolerance_ms)
+}
+
+/// How long after a hold-to-talk release a new press still continues the same
+/// recording (0 = off). Smooths over flaky key contacts.
+#[tauri::command]
+pub fn set_stutter_tolerance(
+    tolerance_ms: u64,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    if tolerance_ms > MAX_STUTTER_TOLERANCE_MS {
+        return Err(format!(
+            "Stutter tolerance must be at most {}ms",
+            MAX_STUTTER_TOLERANCE_MS
+        ));
+    }
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.stutter_tolerance_ms = tolerance_ms;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_start_minimized(settings: State<'_, Mutex<Settings>>) -> Result<bool, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.start_minimized)
+}
+
+/// Start hidden in the tray on the next launch.
+#[tauri::command]
+pub fn set_start_minimized(
+    enabled: bool,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.start_minimized = enabled;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_tap_to_reinject(settings: State<'_, Mutex<Settings>>) -> Result<bool, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.tap_to_reinject)
+}
+
+/// Hold mode only: a tap shorter than the hold threshold pastes the newest
+/// history entry again, a longer hold dictates as usual.
+#[tauri::command]
+pub fn set_tap_to_reinject(
+    enabled: bool,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.tap_to_reinject = enabled;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_undo_injection(settings: State<'_, Mutex<Settings>>) -> Result<bool, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.undo_injection)
+}
+
+#[tauri::command]
+pub fn set_undo_injection(
+    enabled: bool,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.undo_injection = enabled;
+    saver.schedule();
+    Ok(())
+}
+
+/// Delete the text of the last paste with Backspaces (or Ctrl+Z), e.g. after
+/// it landed in the wrong field. Best-effort; needs undo_injection enabled.
+#[tauri::command]
+pub fn undo_last_injection(app: AppHandle) -> Result<(), String> {
+    crate::undo_last_injection(&app)
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DoubleTapSettings {
+    /// "ctrl", "shift", "alt" or "super"; empty = off.
+    pub modifier: String,
+    pub window_ms: u64,
+}
+
+#[tauri::command]
+pub fn get_double_tap(settings: State<'_, Mutex<Settings>>) -> Result<DoubleTapSettings, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(DoubleTapSettings {
+        modifier: s.double_tap_modifier.clone(),
+        window_ms: s.double_tap_window_ms,
+    })
+}
+
+/// Opt-in trigger: double-tap a lone modifier to start or stop dictation
+/// like the tray does. Takes effect right away.
+#[tauri::command]
+pub fn set_double_tap(
+    double_tap: DoubleTapSettings,
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+    listener: State<'_, DoubleTap>,
+) -> Result<(), String> {
+    let modifier = double_tap.modifier.trim().to_lowercase();
+    if !modifier.is_empty() {
+        if crate::system::keyboard::modifier_virtual_key(&modifier).is_none() {
+            return Err(format!(
+                "Unknown modifier: {} (expected ctrl, shift, alt or super)",
+                modifier
+            ));
+        }
+        if !crate::system::double_tap::is_supported() {
+            return Err("The double-tap trigger is not supported on this platform".to_string());
+        }
+    }
+    if !(150..=1000).contains(&double_tap.window_ms) {
+        return Err("Double-tap window must be between 150 and 1000ms".to_string());
+    }
+
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.double_tap_modifier = modifier.clone();
+    s.double_tap_window_ms = double_tap.window_ms;
+    saver.schedule();
+    drop(s);
+    listener.configure(&app, &modifier, double_tap.window_ms);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_log_level(settings: State<'_, Mutex<Settings>>) -> Result<String, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.log_level.clone())
+}
+
+/// Change log verbosity without a restart, e.g. flip to "debug" to capture an issue.
+#[tauri::command]
+pub fn set_log_level(
+    level: String,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let filter = crate::logging::parse_level(&level)?;
+    crate::logging::set_level(filter);
+
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.log_level = filter.to_string().to_lowercase();
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_hotkey(settings: State<'_, Mutex<Settings>>) -> Result<String, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.hotkey.clone())
+}
+
+#[tauri::command]
+pub fn set_hotkey(
+    app: AppHandle,
+    hotkey: String,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<String, String> {
+    // Parse the new hotkey string
+    let new_shortcut = parse_hotkey(&hotkey)?;
+
+    // Get the old hotkey to unregister
+    let old_hotkey = {
+        let s = settings.lock().map_err(|e| e.to_string())?;
+        let taken = s
+            .hotkey_bindings
+            .iter()
+            .any(|b| parse_hotkey(&b.hotkey).is_ok_and(|sc| sc.id() == new_shortcut.id()));
+        if taken {
+            return Err(format!("Hotkey {} is already bound to a preset", hotkey));
+        }
+        s.hotkey.clone()
+    };
+    let old_shortcut = parse_hotkey(&old_hotkey)?;
+
+    // Unregister old, register new
+    let gs = app.global_shortcut();
+    gs.unregister(old_shortcut)
+        .map_err(|e| format!("Failed to unregister old hotkey: {}", e))?;
+    gs.register(new_shortcut)
+        .map_err(|e| format!("Failed to register new hotkey: {}", e))?;
+
+    // Save to settings
+    {
+        let mut s = settings.lock().map_err(|e| e.to_string())?;
+        s.hotkey = hotkey.clone();
+    }
+    saver.schedule();
+
+    log::info!("Hotkey changed to: {}", hotkey);
+    Ok(hotkey)
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct SoundSettings {
+    pub start_sound: String,
+    pub stop_sound: String,
+    pub sound_volume: f32,
+}
+
+#[tauri::command]
+pub fn get_sound_settings(settings: State<'_, Mutex<Settings>>) -> Result<SoundSettings, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(SoundSettings {
+        start_sound: s.start_sound.clone(),
+        stop_sound: s.stop_sound.clone(),
+        sound_volume: s.sound_volume,
+    })
+}
+
+#[tauri::command]
+pub fn get_keep_sound_output_open(settings: State<'_, Mutex<Settings>>) -> Result<bool, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.keep_sound_output_open)
+}
+
+/// Keep the sound output open for low-latency cues, or open it per sound so
+/// the audio device can idle. Applies without a restart.
+#[tauri::command]
+pub fn set_keep_sound_output_open(
+    keep: bool,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+    player: State<'_, SoundPlayer>,
+) -> Result<(), String> {
+    player.set_keep_output_open(keep);
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.keep_sound_output_open = keep;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_sound_settings(
+    start_sound: String,
+    stop_sound: String,
+    sound_volume: f32,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+    player: State<'_, SoundPlayer>,
+) -> Result<(), String> {
+    let volume = sound_volume.clamp(0.0, 1.0);
+
+    // Update sound player at runtime
+    player.update_config(start_sound.clone(), stop_sound.clone(), volume);
+
+    // Save to settings
+    {
+        let mut s = settings.lock().map_err(|e| e.to_string())?;
+        s.start_sound = start_sound;
+        s.stop_sound = stop_sound;
+        s.sound_volume = volume;
+    }
+    // Debounced: the volume slider calls this on every tick
+    saver.schedule();
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_empty_feedback(settings: State<'_, Mutex<Settings>>) -> Result<EmptyFeedback, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.empty_feedback)
+}
+
+/// What happens when a recording transcribes to nothing: a beep, a system
+/// notification, both, or nothing beyond the log.
+#[tauri::command]
+pub fn set_empty_feedback(
+    feedback: EmptyFeedback,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.empty_feedback = feedback;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn test_sound(which: String, player: State<'_, SoundPlayer>) -> Result<(), String> {
+    match which.as_str() {
+        "start" => player.play_start(),
+        "stop" => player.play_stop(),
+        "empty" => player.play_empty(),
+        "warning" => player.play_warning(),
+        _ => return Err("Unknown sound: use 'start', 'stop', 'empty' or 'warning'".to_string()),
+    }
+    Ok(())
+}
+
+/// How long test_mic records.
+const MIC_TEST_MS: u64 = 3000;
+
+#[derive(serde::Serialize)]
+pub struct MicTest {
+    pub peak_dbfs: f32,
+    pub rms_dbfs: f32,
+    /// Fraction of samples the gain stage clamped.
+    pub clipped_ratio: f32,
+    pub level: level::MicLevel,
+}
+
+/// Record `duration_ms` on a separate capture and buffer, so mic tests and
+/// calibration never touch the dictation buffer. Filtered the way dictation
+/// hears it. Returns the samples and the share the gain stage clamped.
+async fn record_clip(
+    settings: &Mutex<Settings>,
+    duration_ms: u64,
+) -> Result<(Vec<f32>, f32), String> {
+    let buffer = AudioBuffer::new();
+    let mut capture = AudioCapture::new(buffer.clone());
+    {
+        let s = settings.lock().map_err(|e| e.to_string())?;
+        capture.set_high_pass(s.high_pass_cutoff());
+        capture.set_device(s.input_device.clone());
+    }
+    let clip_monitor = capture.clip_monitor();
+    capture.start()?;
+    tokio::time::sleep(std::time::Duration::from_millis(duration_ms)).await;
+    capture.stop();
+    Ok((buffer.take_samples(), clip_monitor.take_ratio().unwrap_or(0.0)))
+}
+
+/// Record a few seconds from the microphone, play it back through the sound
+/// output and report how loud it was. The user should speak normally; the
+/// status reads Measuring meanwhile.
+#[tauri::command]
+pub async fn test_mic(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    settings: State<'_, Mutex<Settings>>,
+    player: State<'_, SoundPlayer>,
+) -> Result<MicTest, String> {
+    // Claimed under the Idle check's lock, so the hotkey can't start a
+    // recording on the same microphone meanwhile
+    {
+        let mut app_state = state.lock().map_err(|e| e.to_string())?;
+        if !app_state.claim_from_idle(AppStatus::Measuring) {
+            return Err("Cannot test the microphone while recording or transcribing".to_string());
+        }
+    }
+    let _ = app.emit("status-changed", "Measuring");
+
+    let recorded = record_clip(&settings, MIC_TEST_MS).await;
+    state.lock().map_err(|e| e.to_string())?.status = AppStatus::Idle;
+    let _ = app.emit("status-changed", "Idle");
+    let (samples, clipped_ratio) = recorded?;
+    if samples.is_empty() {
+        return Err(
+            "No audio captured. Check that the microphone is connected and allowed.".to_string(),
+        );
+    }
+
+    let peak = level::peak(&samples);
+    let result = MicTest {
+        peak_dbfs: level::to_dbfs(peak),
+        rms_dbfs: level::to_dbfs(level::rms(&samples)),
+        clipped_ratio,
+        level: level::classify_mic_level(peak, clipped_ratio),
+    };
+    log::info!(
+        "Mic test: peak {:.1} dBFS, RMS {:.1} dBFS, {:.1}% clipped -> {:?}",
+        result.peak_dbfs,
+        result.rms_dbfs,
+        clipped_ratio * 100.0,
+        result.level
+    );
+    player.play_samples(samples);
+    Ok(result)
+}
+
+/// How long calibrate_noise listens to the room.
+const NOISE_CALIBRATION_MS: u64 = 2000;
+
+#[derive(serde::Serialize)]
+pub struct NoiseCalibration {
+    pub noise_rms: f32,
+    pub noise_dbfs: f32,
+    pub threshold: f32,
+}
+
+/// Record a couple of seconds of ambient noise and derive the silence
+/// threshold from it. The user should stay quiet while this runs; the
+/// status reads Measuring meanwhile.
+#[tauri::command]
+pub async fn calibrate_noise(
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<NoiseCalibration, String> {
+    // Claimed under the Idle check's lock, so the hotkey can't start a
+    // recording on the same microphone meanwhile
+    {
+        let mut app_state = state.lock().map_err(|e| e.to_string())?;
+        if !app_state.claim_from_idle(AppStatus::Measuring) {
+            return Err("Cannot calibrate while recording or transcribing".to_string());
+        }
+    }
+    let _ = app.emit("status-changed", "Measuring");
+
+    let recorded = record_clip(&settings, NOISE_CALIBRATION_MS).await;
+    state.lock().map_err(|e| e.to_string())?.status = AppStatus::Idle;
+    let _ = app.emit("status-changed", "Idle");
+    let (samples, _) = recorded?;
+    if samples.is_empty() {
+        return Err("No audio captured during calibration".to_string());
+    }
+
+    let noise_rms = level::rms(&samples);
+    let noise_profile = denoise::noise_spectrum(&samples);
+    let threshold = level::threshold_from_noise_floor(noise_rms);
+    let noise_dbfs = level::to_dbfs(noise_rms);
+    log::info!(
+        "Noise calibration: {:.1} dBFS, silence threshold {:.4}",
+        noise_dbfs,
+        threshold
+    );
+
+    {
+        let mut s = settings.lock().map_err(|e| e.to_string())?;
+        s.noise_floor_rms = noise_rms;
+        s.silence_threshold = threshold;
+        s.noise_profile = noise_profile;
+        saver.schedule();
+    }
+
+    Ok(NoiseCalibration {
+        noise_rms,
+        noise_dbfs,
+        threshold,
+    })
+}
+
+#[tauri::command]
+pub fn get_denoise(settings: State<'_, Mutex<Settings>>) -> Result<bool, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.denoise)
+}
+
+/// Toggle spectral-subtraction denoising. Needs a noise profile from calibrate_noise.
+#[tauri::command]
+pub fn set_denoise(
+    enabled: bool,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    if enabled && s.noise_profile.is_empty() {
+        return Err("Run noise calibration before enabling denoising".to_string());
+    }
+    s.denoise = enabled;
+    saver.schedule();
+    Ok(())
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct HighPassSettings {
+    pub enabled: bool,
+    pub cutoff_hz: f32,
+}
+
+#[tauri::command]
+pub fn get_high_pass(settings: State<'_, Mutex<Settings>>) -> Result<HighPassSettings, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(HighPassSettings {
+        enabled: s.high_pass,
+        cutoff_hz: s.high_pass_hz,
+    })
+}
+
+/// Toggle the rumble filter and set its cutoff (20-300 Hz). Applies from
+/// the next recording.
+#[tauri::command]
+pub fn set_high_pass(
+    high_pass: HighPassSettings,
+    settings: State<'_, Mutex<Settings>>,
+    capture: State<'_, Mutex<AudioCapture>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    if !(20.0..=300.0).contains(&high_pass.cutoff_hz) {
+        return Err("High-pass cutoff must be between 20 and 300 Hz".to_string());
+    }
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.high_pass = high_pass.enabled;
+    s.high_pass_hz = high_pass.cutoff_hz;
+    saver.schedule();
+    capture
+        .lock()
+        .map_err(|e| e.to_string())?
+        .set_high_pass(s.high_pass_cutoff());
+    Ok(())
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct EdgeFadeSettings {
+    /// Fade-out length in milliseconds (0 = off).
+    pub fade_ms: u32,
+    pub fade_in: bool,
+}
+
+#[tauri::command]
+pub fn get_edge_fade(settings: State<'_, Mutex<Settings>>) -> Result<EdgeFadeSettings, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(EdgeFadeSettings {
+        fade_ms: s.fade_ms,
+        fade_in: s.fade_in,
+    })
+}
+
+/// Set the fade applied to the end (and optionally the start) of recordings
+/// before transcription, up to 50 ms. Applies from the next transcription.
+#[tauri::command]
+pub fn set_edge_fade(
+    fade: EdgeFadeSettings,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    if fade.fade_ms > 50 {
+        return Err("Fade must be at most 50 ms".to_string());
+    }
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.fade_ms = fade.fade_ms;
+    s.fade_in = fade.fade_in;
+    saver.schedule();
+    Ok(())
+}
+
+/// Connected input devices with their default sample rate and channel count.
+#[tauri::command]
+pub fn list_audio_devices() -> Vec<AudioDeviceInfo> {
+    devices::list_input_devices()
+}
+
+/// The chosen input device and whether it is connected right now.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct InputDevice {
+    /// None = system default.
+    pub name: Option<String>,
+    /// False when the chosen device is gone and recordings use the default.
+    pub connected: bool,
+}
+
+/// The chosen input device, so the UI can warn on launch when it isn't
+/// connected (the "input-device-missing" event only covers recordings).
+#[tauri::command]
+pub fn get_input_device(settings: State<'_, Mutex<Settings>>) -> Result<InputDevice, String> {
+    let name = settings.lock().map_err(|e| e.to_string())?.input_device.clone();
+    let connected = name.as_deref().is_none_or(|n| devices::find_input_device(n).is_some());
+    Ok(InputDevice { name, connected })
+}
+
+/// Record from the named input device (None or empty = system default).
+/// Applies from the next recording.
+#[tauri::command]
+pub fn set_input_device(
+    device_name: Option<String>,
+    settings: State<'_, Mutex<Settings>>,
+    capture: State<'_, Mutex<AudioCapture>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let device_name = device_name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    if let Some(name) = &device_name {
+        if devices::find_input_device(name).is_none() {
+            return Err(format!("Input device not found: {}", name));
+        }
+    }
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.input_device = device_name.clone();
+    saver.schedule();
+    capture
+        .lock()
+        .map_err(|e| e.to_string())?
+        .set_device(device_name);
+    Ok(())
+}
+
+/// Supported sample rate ranges, channel counts and formats of an input device.
+#[tauri::command]
+pub fn get_device_configs(device_name: String) -> Result<Vec<SupportedConfigInfo>, String> {
+    devices::supported_input_configs(&device_name)
+}
+
+#[tauri::command]
+pub fn get_ai_settings(settings: State<'_, Mutex<Settings>>) -> Result<crate::formatting::AiSettings, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.ai.clone())
+}
+
+#[tauri::command]
+pub fn set_ai_settings(
+    ai: crate::formatting::AiSettings,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    ai.validate()?;
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    log::info!("AI settings updated: provider={:?}", ai.provider);
+    s.ai = ai;
+    // Debounced: the settings window sends every keystroke in its fields
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_ai_enabled(settings: State<'_, Mutex<Settings>>) -> Result<bool, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.ai_enabled)
+}
+
+/// Turn AI formatting of dictations on or off without touching the provider
+/// settings. format_preview and reformat_last still use the providers.
+#[tauri::command]
+pub fn set_ai_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    crate::set_ai_enabled(&app, enabled)
+}
+
+/// Flip the AI formatting switch; returns the new state.
+#[tauri::command]
+pub fn toggle_ai_enabled(
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<bool, String> {
+    let enabled = !settings.lock().map_err(|e| e.to_string())?.ai_enabled;
+    crate::set_ai_enabled(&app, enabled)?;
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub fn get_ai_fallbacks(
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<Vec<crate::formatting::AiSettings>, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.ai_fallbacks.clone())
+}
+
+#[tauri::command]
+pub fn set_ai_fallbacks(
+    fallbacks: Vec<crate::formatting::AiSettings>,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    for (i, ai) in fallbacks.iter().enumerate() {
+        ai.validate()
+            .map_err(|e| format!("Fallback #{}: {}", i + 1, e))?;
+    }
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    log::info!("AI fallback chain updated: {} provider(s)", fallbacks.len());
+    s.ai_fallbacks = fallbacks;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_prompt_presets(settings: State<'_, Mutex<Settings>>) -> Result<Vec<PromptPreset>, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.prompt_presets.clone())
+}
+
+/// Presets that ship with the app, e.g. "markdown".
+#[tauri::command]
+pub fn get_builtin_presets() -> Vec<PromptPreset> {
+    crate::formatting::builtin_presets()
+}
+
+#[tauri::command]
+pub fn set_prompt_presets(
+    presets: Vec<PromptPreset>,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    for (i, p) in presets.iter().enumerate() {
+        if p.name.trim().is_empty() {
+            return Err(format!("Preset #{}: name is required", i + 1));
+        }
+        if p.prompt.trim().is_empty() {
+            return Err(format!("Preset \"{}\": prompt must not be empty", p.name));
+        }
+        if presets[..i].iter().any(|other| other.name == p.name) {
+            return Err(format!("Duplicate preset name: {}", p.name));
+        }
+        if crate::formatting::builtin_presets().iter().any(|b| b.name == p.name) {
+            return Err(format!("\"{}\" is a built-in preset name", p.name));
+        }
+    }
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.prompt_presets = presets;
+    saver.schedule();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_hotkey_bindings(
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<Vec<HotkeyBinding>, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.hotkey_bindings.clone())
+}
+
+/// Replace the extra dictation hotkeys and re-register them.
+#[tauri::command]
+pub fn set_hotkey_bindings(
+    app: AppHandle,
+    bindings: Vec<HotkeyBinding>,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+
+    let main_id = parse_hotkey(&s.hotkey)?.id();
+    let mut shortcuts: Vec<Shortcut> = Vec::with_capacity(bindings.len());
+    for b in &bindings {
+        let sc = parse_hotkey(&b.hotkey)?;
+        if sc.id() == main_id || shortcuts.iter().any(|other| other.id() == sc.id()) {
+            return Err(format!("Hotkey {} is already in use", b.hotkey));
+        }
+        if let Some(name) = &b.preset {
+            if s.find_preset(name).is_none() {
+                return Err(format!("Unknown prompt preset: {}", name));
+            }
+        }
+        shortcuts.push(sc);
+    }
+
+    let gs = app.global_shortcut();
+    for old in &s.hotkey_bindings {
+        if let Ok(sc) = parse_hotkey(&old.hotkey) {
+            let _ = gs.unregister(sc);
+        }
+    }
+    for (sc, b) in shortcuts.into_iter().zip(&bindings) {
+        gs.register(sc)
+            .map_err(|e| format!("Failed to register {}: {}", b.hotkey, e))?;
+    }
+
+    log::info!("Preset hotkeys updated: {} binding(s)", bindings.len());
+    s.hotkey_bindings = bindings;
+    saver.schedule();
+    Ok(())
+}
+
+/// One configured global shortcut, for the settings UI.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HotkeyInfo {
+    pub hotkey: String,
+    /// What the shortcut does, e.g. "Dictation" or "Dictation with preset \"email\"".
+    pub purpose: String,
+    /// Whether the shortcut is currently registered with the OS.
+    pub registered: bool,
+    /// Why it can't work as configured (unparseable, or a duplicate of an earlier entry).
+    pub conflict: Option<String>,
+}
+
+/// Every configured shortcut with its purpose, main hotkey first.
+fn configured_hotkeys(s: &Settings) -> Vec<(String, String)> {
+    std::iter::once((s.hotkey.clone(), "Dictation".to_string()))
+        .chain(s.hotkey_bindings.iter().map(|b| {
+            let purpose = match &b.preset {
+                Some(name) => format!("Dictation with preset \"{}\"", name),
+                None => "Raw dictation (no AI formatting)".to_string(),
+            };
+            (b.hotkey.clone(), purpose)
+        }))
+        .collect()
+}
+
+/// All configured shortcuts with their registration status and conflicts.
+/// A shortcut that isn't registered usually failed because another
+/// application already owns it.
+#[tauri::command]
+pub fn get_registered_hotkeys(
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<Vec<HotkeyInfo>, String> {
+    let configured = configured_hotkeys(&*settings.lock().map_err(|e| e.to_string())?);
+    let gs = app.global_shortcut();
+
+    let mut seen: Vec<(u32, &str)> = Vec::new();
+    let mut infos = Vec::with_capacity(configured.len());
+    for (hotkey, purpose) in &configured {
+        let (registered, conflict) = match parse_hotkey(hotkey) {
+            Ok(sc) => {
+                let conflict = seen
+                    .iter()
+                    .find(|(id, _)| *id == sc.id())
+                    .map(|(_, other)| format!("Same keys as \"{}\"", other));
+                seen.push((sc.id(), purpose));
+                (gs.is_registered(sc), conflict)
+            }
+            Err(e) => (false, Some(e)),
+        };
+        infos.push(HotkeyInfo {
+            hotkey: hotkey.clone(),
+            purpose: purpose.clone(),
+            registered,
+            conflict,
+        });
+    }
+    Ok(infos)
+}
+
+/// Check a hotkey before binding it: returns what already uses it, either
+/// one of our own shortcuts or another application, or None if it's free.
+#[tauri::command]
+pub fn check_hotkey_conflict(
+    hotkey: String,
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<Option<String>, String> {
+    let shortcut = parse_hotkey(&hotkey)?;
+    let configured = configured_hotkeys(&*settings.lock().map_err(|e| e.to_string())?);
+    let owner = configured
+        .iter()
+        .find(|(hk, _)| parse_hotkey(hk).is_ok_and(|sc| sc.id() == shortcut.id()));
+    if let Some((_, purpose)) = owner {
+        return Ok(Some(format!("Already used for: {}", purpose)));
+    }
+
+    // The only way to learn whether another app holds it is to try
+    let gs = app.global_shortcut();
+    if gs.is_registered(shortcut) {
+        return Ok(None);
+    }
+    match gs.register(shortcut) {
+        Ok(_) => {
+            let _ = gs.unregister(shortcut);
+            Ok(None)
+        }
+        Err(e) => Ok(Some(format!("Unavailable, likely taken by another application ({})", e))),
+    }
+}
+
+/// Transcription settings in effect for the configured model, with its
+/// overrides applied.
+#[tauri::command]
+pub fn get_transcription_settings(
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<TranscriptionSettings, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.active_transcription())
+}
+
+/// Run `f` on the engine from a blocking thread. A transcription holds the
+/// engine lock for the whole decode, so a command waiting on it must not
+/// tie up the main thread.
+async fn with_engine<R: Send + 'static>(
+    app: &AppHandle,
+    f: impl FnOnce(&mut WhisperEngine) -> R + Send + 'static,
+) -> Result<R, String> {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let engine = app.state::<Mutex<WhisperEngine>>();
+        let mut eng = engine.lock().map_err(|e| e.to_string())?;
+        Ok(f(&mut eng))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Give the engine the configured model's transcription settings. They are
+/// read once the engine lock is held, so commands that queued behind the
+/// same decode can't apply their changes out of order.
+async fn sync_engine_settings(app: &AppHandle) -> Result<(), String> {
+    let handle = app.clone();
+    with_engine(app, move |eng| {
+        let settings = handle.state::<Mutex<Settings>>();
+        let transcription = settings.lock().map_err(|e| e.to_string())?.active_transcription();
+        eng.set_settings(transcription);
+        Ok(())
+    })
+    .await?
+}
+
+#[tauri::command]
+pub async fn set_transcription_settings(
+    transcription: TranscriptionSettings,
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    if transcription.min_segment_ms < 0 {
+        return Err("Minimum segment length must not be negative".to_string());
+    }
+    if !(0.0..=1.0).contains(&transcription.min_segment_probability) {
+        return Err("Minimum segment probability must be between 0 and 1".to_string());
+    }
+    validate_best_of(transcription.best_of)?;
+    validate_no_speech_threshold(transcription.no_speech_threshold)?;
+    if transcription.max_repeats == 1 {
+        // "no no" and "very very" are real speech
+        return Err("Max repeats must be at least 2 (or 0 to disable)".to_string());
+    }
+
+    if transcription.threads < 1 {
+        return Err("Threads must be at least 1".to_string());
+    }
+
+    settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .set_active_transcription(transcription);
+    saver.schedule();
+
+    sync_engine_settings(&app).await?;
+    Ok(())
+}
+
+fn validate_best_of(best_of: i32) -> Result<(), String> {
+    if best_of < 1 {
+        return Err("best_of must be at least 1".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_best_of(settings: State<'_, Mutex<Settings>>) -> Result<i32, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.transcription.best_of)
+}
+
+/// Greedy candidates per segment. Higher values help on hard audio but
+/// slow transcription down roughly in proportion.
+#[tauri::command]
+pub async fn set_best_of(
+    best_of: i32,
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    validate_best_of(best_of)?;
+
+    {
+        let mut s = settings.lock().map_err(|e| e.to_string())?;
+        s.transcription.best_of = best_of;
+        saver.schedule();
+    }
+    sync_engine_settings(&app).await?;
+
+    if best_of > 1 {
+        log::info!(
+            "best_of set to {}: expect transcription to take up to ~{}x longer",
+            best_of,
+            best_of
+        );
+    }
+    Ok(())
+}
+
+fn validate_no_speech_threshold(threshold: f32) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err("No-speech threshold must be between 0 and 1".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_no_speech_threshold(settings: State<'_, Mutex<Settings>>) -> Result<f32, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.transcription.no_speech_threshold)
+}
+
+/// Recordings whose no-speech probability reaches this, and whose text is
+/// low-confidence, are discarded as silence instead of pasting a
+/// hallucinated phrase (0 = off).
+#[tauri::command]
+pub async fn set_no_speech_threshold(
+    threshold: f32,
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    validate_no_speech_threshold(threshold)?;
+
+    {
+        let mut s = settings.lock().map_err(|e| e.to_string())?;
+        s.transcription.no_speech_threshold = threshold;
+        saver.schedule();
+    }
+    sync_engine_settings(&app).await?;
+    Ok(())
+}
+
+/// Whisper's decoder-level guards against non-speech output.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TokenSuppression {
+    pub suppress_blank: bool,
+    pub suppress_non_speech_tokens: bool,
+}
+
+#[tauri::command]
+pub fn get_token_suppression(
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<TokenSuppression, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(TokenSuppression {
+        suppress_blank: s.transcription.suppress_blank,
+        suppress_non_speech_tokens: s.transcription.suppress_non_speech_tokens,
+    })
+}
 
+/// Applies from the next transcription; no model reload needed.
+#[tauri::command]
+pub async fn set_token_suppression(
+    suppression: TokenSuppression,
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
     {
-        let mut app_state = state.lock().map_err(|e| e.to_string())?;
-        app_state.device_sample_rate = sample_rate;
+        let mut s = settings.lock().map_err(|e| e.to_string())?;
+        s.transcription.suppress_blank = suppression.suppress_blank;
+        s.transcription.suppress_non_speech_tokens = suppression.suppress_non_speech_tokens;
+        saver.schedule();
     }
+    sync_engine_settings(&app).await?;
+    Ok(())
+}
 
-    Ok(format!("Recording at {} Hz", sample_rate))
+/// Outcome of switching transcription mode.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TranscriptionModeStatus {
+    pub mode: TranscriptionMode,
+    pub model_filename: String,
+    /// False when the mode's model still has to be downloaded.
+    pub model_present: bool,
 }
 
 #[tauri::command]
-pub async fn stop_recording_and_transcribe(
-    state: State<'_, Mutex<AppState>>,
-    capture: State<'_, Mutex<AudioCapture>>,
-    buffer: State<'_, AudioBuffer>,
-    engine: State<'_, Mutex<WhisperEngine>>,
-) -> Result<String, String> {
-    // Stop recording
-    {
-        let mut cap = capture.lock().map_err(|e| e.to_string())?;
-        cap.stop();
-    }
+pub fn get_model_context_settings(
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<ContextSettings, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.model_context.clone())
+}
 
-    {
-        let mut app_state = state.lock().map_err(|e| e.to_string())?;
-        app_state.status = AppStatus::Transcribing;
+/// Change load-time model options. The current model is reloaded in the
+/// background when anything changed; "model-loaded" fires once it's ready.
+#[tauri::command]
+pub async fn set_model_context_settings(
+    context: ContextSettings,
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+    config: State<'_, AppConfig>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    if context.gpu_device < 0 {
+        return Err("GPU device index must not be negative".to_string());
     }
-
-    let samples = buffer.take_samples();
-    if samples.is_empty() {
-        let mut app_state = state.lock().map_err(|e| e.to_string())?;
-        app_state.status = AppStatus::Idle;
-        return Err("No audio recorded".to_string());
+    if context.flash_attn && context.dtw_token_timestamps {
+        return Err("Flash attention can't be combined with DTW timestamps".to_string());
     }
 
-    log::info!(
-        "Transcribing {} samples ({:.1}s of audio)",
-        samples.len(),
-        samples.len() as f32 / 16000.0
-    );
-
-    // Transcribe
-    let text = {
-        let eng = engine.lock().map_err(|e| e.to_string())?;
-        eng.transcribe(&samples)?
+    let model_filename = {
+        let mut s = settings.lock().map_err(|e| e.to_string())?;
+        if s.model_context == context {
+            return Ok(());
+        }
+        s.model_context = context.clone();
+        saver.schedule();
+        s.model_filename.clone()
     };
 
-    if text.is_empty() {
-        let mut app_state = state.lock().map_err(|e| e.to_string())?;
-        app_state.status = AppStatus::Idle;
-        return Err("No speech detected".to_string());
+    with_engine(&app, move |eng| eng.set_context_settings(context)).await?;
+
+    let model_path = config.model_path(&model_filename);
+    if model_path.exists() {
+        crate::spawn_model_load(app, model_path);
     }
+    Ok(())
+}
 
-    log::info!("Transcription: {}", text);
+#[tauri::command]
+pub fn get_transcription_mode(
+    settings: State<'_, Mutex<Settings>>,
+    config: State<'_, AppConfig>,
+) -> Result<TranscriptionModeStatus, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(TranscriptionModeStatus {
+        mode: s.transcription_mode,
+        model_filename: s.model_filename.clone(),
+        model_present: config.model_path(&s.model_filename).exists(),
+    })
+}
 
-    // Inject text
-    {
-        let mut app_state = state.lock().map_err(|e| e.to_string())?;
-        app_state.status = AppStatus::Injecting;
+/// Switch model, language and bias prompt together. The new model is loaded
+/// in the background if present; otherwise `model_present` is false and the
+/// current model stays loaded until it is downloaded.
+#[tauri::command]
+pub async fn set_transcription_mode(
+    mode: String,
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+    config: State<'_, AppConfig>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<TranscriptionModeStatus, String> {
+    let mode = TranscriptionMode::parse(&mode)?;
+
+    let model_filename = {
+        let mut s = settings.lock().map_err(|e| e.to_string())?;
+        s.apply_transcription_mode(mode);
+        saver.schedule();
+        s.model_filename.clone()
+    };
+
+    sync_engine_settings(&app).await?;
+
+    let model_path = config.model_path(&model_filename);
+    let model_present = model_path.exists();
+    if model_present {
+        crate::spawn_model_load(app, model_path);
+    } else {
+        log::warn!(
+            "Transcription mode {:?} needs {}, which is not downloaded",
+            mode,
+            model_filename
+        );
     }
 
-    text_injection::inject_text(&text)?;
+    Ok(TranscriptionModeStatus {
+        mode,
+        model_filename,
+        model_present,
+    })
+}
 
-    // Done
-    {
-        let mut app_state = state.lock().map_err(|e| e.to_string())?;
-        app_state.last_transcription = text.clone();
-        app_state.status = AppStatus::Idle;
+#[tauri::command]
+pub fn get_language_models(
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<BTreeMap<String, String>, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.language_models.clone())
+}
+
+/// Map language codes ("en", "ru", or "auto") to model files. Models don't
+/// have to be downloaded yet; set_language reports when one is missing.
+#[tauri::command]
+pub fn set_language_models(
+    language_models: BTreeMap<String, String>,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let mut cleaned = BTreeMap::new();
+    for (language, model) in language_models {
+        let language = language.trim().to_lowercase();
+        let model = model.trim().to_string();
+        if language.is_empty() {
+            return Err("Language code must not be empty".to_string());
+        }
+        if !model.ends_with(".bin") || model.contains(['/', '\\']) {
+            return Err(format!("Invalid model file for {}: {:?}", language, model));
+        }
+        cleaned.insert(language, model);
     }
 
-    Ok(text)
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.language_models = cleaned;
+    saver.schedule();
+    Ok(())
 }
 
 #[tauri::command]
-pub fn get_status(state: State<'_, Mutex<AppState>>) -> Result<String, String> {
-    let app_state = state.lock().map_err(|e| e.to_string())?;
-    let status = match &app_state.status {
-        AppStatus::Idle => "Idle".to_string(),
-        AppStatus::Recording => "Recording".to_string(),
-        AppStatus::Transcribing => "Transcribing".to_string(),
-        AppStatus::Formatting => "Formatting".to_string(),
-        AppStatus::Injecting => "Injecting".to_string(),
-        AppStatus::Error(e) => format!("Error: {}", e),
+pub fn get_model_overrides(
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<BTreeMap<String, ModelOverrides>, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.model_overrides.clone())
+}
+
+/// Set what `model_filename` uses instead of the global language, bias
+/// prompt and thread count. Empty overrides forget the model. Applies right
+/// away if it is the configured model, otherwise when it is next loaded.
+#[tauri::command]
+pub async fn set_model_overrides(
+    model_filename: String,
+    overrides: ModelOverrides,
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    let model_filename = model_filename.trim().to_string();
+    if !model_filename.ends_with(".bin") || model_filename.contains(['/', '\\']) {
+        return Err(format!("Invalid model file: {:?}", model_filename));
+    }
+    if overrides.threads.is_some_and(|t| t < 1) {
+        return Err("Threads must be at least 1".to_string());
+    }
+    let mut overrides = overrides;
+    overrides.language = overrides
+        .language
+        .map(|l| l.trim().to_lowercase())
+        .map(|l| if l.is_empty() { "auto".to_string() } else { l });
+
+    let active = {
+        let mut s = settings.lock().map_err(|e| e.to_string())?;
+        if overrides.is_empty() {
+            s.model_overrides.remove(&model_filename);
+        } else {
+            s.model_overrides.insert(model_filename.clone(), overrides);
+        }
+        s.model_filename == model_filename
     };
-    Ok(status)
+    saver.schedule();
+
+    if active {
+        sync_engine_settings(&app).await?;
+    }
+    Ok(())
 }
 
+/// Outcome of selecting a dictation language.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LanguageStatus {
+    pub language: Option<String>,
+    /// Model that is (or is being) loaded.
+    pub model_filename: String,
+    /// Set when the language has a mapped model that isn't downloaded.
+    pub warning: Option<String>,
+}
+
+/// Pin the transcription language ("auto" or empty = auto-detect,
+/// "auto_lock" = detect once per recording and keep it) and, if
+/// language_models maps it to a downloaded model, switch to that model in
+/// the background. A mapped model that is missing leaves the current one
+/// loaded and is reported in `warning`.
 #[tauri::command]
-pub fn is_model_loaded(engine: State<'_, Mutex<WhisperEngine>>) -> Result<bool, String> {
-    let eng = engine.lock().map_err(|e| e.to_string())?;
-    Ok(eng.is_loaded())
+pub async fn set_language(
+    language: String,
+    app: AppHandle,
+    settings: State<'_, Mutex<Settings>>,
+    config: State<'_, AppConfig>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<LanguageStatus, String> {
+    let language = language.trim().to_lowercase();
+    let auto_lock = language == "auto_lock";
+    let language = match language {
+        code if code.is_empty() || code == "auto" || auto_lock => None,
+        code => Some(code),
+    };
+
+    let mut warning = None;
+    let mut load = None;
+    let model_filename = {
+        let mut s = settings.lock().map_err(|e| e.to_string())?;
+        s.transcription.language = language.clone();
+        if language.is_none() {
+            s.transcription.auto_lock = auto_lock;
+        }
+        let mapped = s.model_for_language(language.as_deref()).map(str::to_string);
+        if let Some(model) = mapped.filter(|m| *m != s.model_filename) {
+            let model_path = config.model_path(&model);
+            if model_path.exists() {
+                s.model_filename = model;
+                load = Some(model_path);
+            } else {
+                warning = Some(format!(
+                    "{} is set for {} but isn't downloaded. Keeping {} until it is.",
+                    model,
+                    language.as_deref().unwrap_or("auto-detect"),
+                    s.model_filename
+                ));
+            }
+        }
+        let model_filename = s.model_filename.clone();
+        s.remember_language(&model_filename, language.as_deref());
+        model_filename
+    };
+    saver.schedule();
+
+    sync_engine_settings(&app).await?;
+    if let Some(model_path) = load {
+        log::info!("Switching to {} for language {:?}", model_filename, language);
+        // The mismatch check runs once the new model is in
+        crate::spawn_model_load(app, model_path);
+    } else if let Some(warning) = &warning {
+        log::warn!("{}", warning);
+    } else {
+        let handle = app.clone();
+        warning = tauri::async_runtime::spawn_blocking(move || crate::check_model_language(&handle))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(LanguageStatus {
+        language,
+        model_filename,
+        warning,
+    })
 }
 
 #[tauri::command]
-pub fn get_last_transcription(state: State<'_, Mutex<AppState>>) -> Result<String, String> {
-    let app_state = state.lock().map_err(|e| e.to_string())?;
-    Ok(app_state.last_transcription.clone())
+pub fn get_postprocess_settings(
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<PostProcessSettings, String> {
+    let s = settings.lock().map_err(|e| e.to_string())?;
+    Ok(s.postprocess.clone())
 }
 
 #[tauri::command]
-pub fn get_models_dir(config: State<'_, crate::config::AppConfig>) -> Result<String, String> {
-    Ok(config.models_dir.to_string_lossy().to_string())
+pub fn set_postprocess_settings(
+    postprocess: PostProcessSettings,
+    settings: State<'_, Mutex<Settings>>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<(), String> {
+    crate::postprocess::template::format_timestamp(
+        &postprocess.timestamp_format,
+        &chrono::Local::now(),
+    )?;
+    let mut s = settings.lock().map_err(|e| e.to_string())?;
+    s.postprocess = postprocess;
+    saver.schedule();
+    Ok(())
 }
 
 #[tauri::command]
-pub fn get_hotkey(settings: State<'_, Mutex<Settings>>) -> Result<String, String> {
+pub fn list_models(settings: State<'_, Mutex<Settings>>) -> Result<Vec<ModelInfo>, String> {
     let s = settings.lock().map_err(|e| e.to_string())?;
-    Ok(s.hotkey.clone())
+    Ok(models::get_available_models(&s.model_mirror_url))
+}
+
+/// Payload of the "model-download-error" event.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ModelDownloadFailure {
+    pub filename: String,
+    pub error: models::ModelDownloadError,
+    /// user_message() of the error.
+    pub message: String,
 }
 
+/// Download a model from the catalog (through the configured mirror) into
+/// the models directory and return its path. Failures are also emitted as
+/// "model-download-error" with the structured error.
 #[tauri::command]
-pub fn set_hotkey(
+pub async fn download_model(
+    filename: String,
     app: AppHandle,
-    hotkey: String,
     settings: State<'_, Mutex<Settings>>,
     config: State<'_, AppConfig>,
 ) -> Result<String, String> {
-    // Parse the new hotkey string
-    let new_shortcut = parse_hotkey(&hotkey)?;
+    let mirror = settings.lock().map_err(|e| e.to_string())?.model_mirror_url.clone();
+    let model = models::get_available_models(&mirror)
+        .into_iter()
+        .find(|m| m.filename == filename)
+        .ok_or_else(|| format!("Unknown model: {}", filename))?;
 
-    // Get the old hotkey to unregister
-    let old_hotkey = {
+    match models::download_model(&config.models_dir, &model).await {
+        Ok(path) => Ok(path.to_string_lossy().to_string()),
+        Err(e) => {
+            log::error!("{} ({})", e, filename);
+            let message = e.user_message();
+            let _ = app.emit(
+                "model-download-error",
+                ModelDownloadFailure {
+                    filename,
+                    error: e,
+                    message: message.clone(),
+                },
+            );
+            Err(message)
+        }
+    }
+}
+
+/// Transcribe an audio file (wav, mp3, flac, ogg or m4a) with the loaded model
+/// and return the raw text.
+#[tauri::command]
+pub async fn transcribe_file(path: String, app: AppHandle) -> Result<String, String> {
+    crate::transcribe_file(&app, std::path::Path::new(&path)).await
+}
+
+/// Transcribe the WAV clip at `clip_path` with every downloaded model and
+/// report text and speed for each. Holds the engine for the whole run, with
+/// the status at Transcribing so no dictation starts meanwhile, then reloads
+/// the model that was active before.
+#[tauri::command]
+pub async fn benchmark_models(
+    clip_path: String,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    settings: State<'_, Mutex<Settings>>,
+    config: State<'_, AppConfig>,
+    saver: State<'_, SaveScheduler>,
+) -> Result<Vec<BenchmarkResult>, String> {
+    let audio = crate::audio::wav::load_wav(std::path::Path::new(&clip_path))?;
+    if audio.is_empty() {
+        return Err("The benchmark clip is empty".to_string());
+    }
+    let models_list = models::downloaded_models(&config.models_dir);
+    if models_list.is_empty() {
+        return Err("No models downloaded".to_string());
+    }
+
+    let (original, active, runs) = {
         let s = settings.lock().map_err(|e| e.to_string())?;
-        s.hotkey.clone()
+        let runs: Vec<_> = models_list
+            .iter()
+            .map(|model| (model.clone(), s.transcription_for(model)))
+            .collect();
+        (config.model_path(&s.model_filename), s.active_transcription(), runs)
     };
-    let old_shortcut = parse_hotkey(&old_hotkey)?;
-
-    // Unregister old, register new
-    let gs = app.global_shortcut();
-    gs.unregister(old_shortcut)
-        .map_err(|e| format!("Failed to unregister old hotkey: {}", e))?;
-    gs.register(new_shortcut)
-        .map_err(|e| format!("Failed to register new hotkey: {}", e))?;
+    let models_dir = config.models_dir.clone();
+    let clip_secs = crate::audio::samples_to_secs(audio.len());
+    log::info!("Benchmarking {} models on {:.1}s of audio", models_list.len(), clip_secs);
 
-    // Save to settings
     {
+        let mut app_state = state.lock().map_err(|e| e.to_string())?;
+        // Keeps the hotkey from starting a dictation that would wait on the
+        // engine for the whole run
+        if !app_state.claim_from_idle(AppStatus::Transcribing) {
+            return Err("Busy — wait for the current dictation to finish".to_string());
+        }
+    }
+    let _ = app.emit("status-changed", "Transcribing");
+
+    let handle = app.clone();
+    let results = tauri::async_runtime::spawn_blocking(move || {
+        let engine = handle.state::<Mutex<WhisperEngine>>();
+        let mut eng = engine.lock().unwrap();
+        let results = benchmark::run(&mut eng, &models_dir, &runs, &audio);
+        eng.set_settings(active);
+
+        // Never leave a benchmarked model standing in for the configured one
+        eng.unload();
+        let restored = if original.exists() {
+            eng.load_model(&original)
+        } else {
+            Err(format!("{:?} not found", original))
+        };
+        let loaded = eng.is_loaded();
+        drop(eng);
+        {
+            let state = handle.state::<Mutex<AppState>>();
+            let mut s = state.lock().unwrap();
+            s.model_loaded = loaded;
+            if loaded {
+                s.model_idle_unloaded = false;
+            }
+        }
+        if let Err(e) = restored {
+            log::error!("Failed to restore the model after benchmark: {}", e);
+            let _ = handle.emit("model-load-failed", e);
+        }
+        results
+    })
+    .await;
+    state.lock().map_err(|e| e.to_string())?.status = AppStatus::Idle;
+    let _ = app.emit("status-changed", "Idle");
+    let results = results.map_err(|e| e.to_string())?;
+
+    // The measured speeds feed estimate_transcription_time
+    if clip_secs >= crate::MIN_SPEED_SAMPLE_SECS {
         let mut s = settings.lock().map_err(|e| e.to_string())?;
-        s.hotkey = hotkey.clone();
-        s.save(&config.data_dir)?;
+        for result in results.iter().filter(|r| r.error.is_none()) {
+            let threads = s.transcription_for(&result.model).threads;
+            s.record_model_speed(&result.model, result.rtf, threads);
+        }
+        saver.schedule();
     }
 
-    log::info!("Hotkey changed to: {}", hotkey);
-    Ok(hotkey)
+    Ok(results)
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-pub struct SoundSettings {
-    pub start_sound: String,
-    pub stop_sound: String,
-    pub sound_volume: f32,
+/// How long transcribing a recording is expected to take.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TranscriptionEstimate {
+    pub seconds: f32,
+    /// Expected processing time / audio duration.
+    pub rtf: f32,
+    pub model_filename: String,
+    pub threads: i32,
+    /// Transcriptions of at least MIN_SPEED_SAMPLE_SECS measured with this
+    /// model (0 = the estimate is a rough default for the model size).
+    pub measured_samples: u32,
 }
 
+/// Estimate how long `seconds` of audio takes to transcribe with the
+/// configured model and thread count, from the model's measured speed on
+/// this machine (rough defaults per model size until it has been measured).
 #[tauri::command]
-pub fn get_sound_settings(settings: State<'_, Mutex<Settings>>) -> Result<SoundSettings, String> {
+pub fn estimate_transcription_time(
+    seconds: f32,
+    settings: State<'_, Mutex<Settings>>,
+) -> Result<TranscriptionEstimate, String> {
+    if seconds.is_nan() || seconds < 0.0 {
+        return Err("Audio length must not be negative".to_string());
+    }
     let s = settings.lock().map_err(|e| e.to_string())?;
-    Ok(SoundSettings {
-        start_sound: s.start_sound.clone(),
-        stop_sound: s.stop_sound.clone(),
-        sound_volume: s.sound_volume,
+    let threads = s.active_transcription().threads;
+    let measured = s.model_speeds.get(&s.model_filename);
+    let rtf = models::expected_rtf(&s.model_filename, measured, threads);
+    Ok(TranscriptionEstimate {
+        seconds: seconds * rtf,
+        rtf,
+        model_filename: s.model_filename.clone(),
+        threads,
+        measured_samples: measured.map_or(0, |m| m.samples),
     })
 }
 
+/// Whether the configured model is multilingual or English-only. Read from
+/// the model when it is loaded, otherwise guessed from the file name.
 #[tauri::command]
-pub fn set_sound_settings(
-    start_sound: String,
-    stop_sound: String,
-    sound_volume: f32,
+pub async fn get_model_capabilities(
+    app: AppHandle,
     settings: State<'_, Mutex<Settings>>,
-    config: State<'_, AppConfig>,
-    player: State<'_, SoundPlayer>,
-) -> Result<(), String> {
-    let volume = sound_volume.clamp(0.0, 1.0);
-
-    // Update sound player at runtime
-    player.update_config(start_sound.clone(), stop_sound.clone(), volume);
-
-    // Save to settings
-    {
-        let mut s = settings.lock().map_err(|e| e.to_string())?;
-        s.start_sound = start_sound;
-        s.stop_sound = stop_sound;
-        s.sound_volume = volume;
-        s.save(&config.data_dir)?;
-    }
-
-    Ok(())
+) -> Result<ModelCapabilities, String> {
+    let model_filename = settings.lock().map_err(|e| e.to_string())?.model_filename.clone();
+    let loaded = with_engine(&app, |eng| eng.is_multilingual()).await?;
+    Ok(ModelCapabilities {
+        multilingual: loaded.unwrap_or_else(|| !models::is_english_only(&model_filename)),
+        loaded: loaded.is_some(),
+        model_filename,
+    })
 }
 
+/// Preflight before downloading or loading a model: does it fit in RAM?
 #[tauri::command]
-pub fn test_sound(which: String, player: State<'_, SoundPlayer>) -> Result<(), String> {
-    match which.as_str() {
-        "start" => player.play_start(),
-        "stop" => player.play_stop(),
-        _ => return Err("Unknown sound: use 'start' or 'stop'".to_string()),
-    }
-    Ok(())
+pub fn check_model_memory(
+    filename: String,
+    config: State<'_, AppConfig>,
+) -> Result<MemoryAdvisory, String> {
+    // Prefer the real file size, fall back to the catalog for undownloaded models
+    let size = match std::fs::metadata(config.model_path(&filename)) {
+        Ok(meta) => meta.len(),
+        Err(_) => models::get_available_models("")
+            .into_iter()
+            .find(|m| m.filename == filename)
+            .map(|m| m.size_bytes)
+            .ok_or_else(|| format!("Unknown model: {}", filename))?,
+    };
+    Ok(models::memory_advisory(
+        &filename,
+        size,
+        crate::system::memory::available_memory_bytes(),
+    ))
 }
 
 #[tauri::command]
-pub fn get_ai_settings(settings: State<'_, Mutex<Settings>>) -> Result<crate::formatting::AiSettings, String> {
+pub fn get_model_mirror(settings: State<'_, Mutex<Settings>>) -> Result<String, String> {
     let s = settings.lock().map_err(|e| e.to_string())?;
-    Ok(s.ai.clone())
+    Ok(s.model_mirror_url.clone())
 }
 
 #[tauri::command]
-pub fn set_ai_settings(
-    ai: crate::formatting::AiSettings,
+pub fn set_model_mirror(
+    mirror: String,
     settings: State<'_, Mutex<Settings>>,
-    config: State<'_, AppConfig>,
-) -> Result<(), String> {
+    saver: State<'_, SaveScheduler>,
+) -> Result<String, String> {
+    // Empty resets to the default host
+    let mirror = if mirror.trim().is_empty() {
+        String::new()
+    } else {
+        models::validate_mirror_url(&mirror)?
+    };
+
     let mut s = settings.lock().map_err(|e| e.to_string())?;
-    log::info!("AI settings updated: provider={:?}", ai.provider);
-    s.ai = ai;
-    s.save(&config.data_dir)?;
-    Ok(())
+    s.model_mirror_url = mirror.clone();
+    saver.schedule();
+    log::info!("Model mirror set to: {}", models::model_base_url(&mirror));
+    Ok(mirror)
 }
 
 /// Parse a hotkey string like "Ctrl+Shift+Space" into a tauri Shortcut.
@@ -358,3 +2775,50 @@ fn parse_key_code(key: &str) -> Result<Code, String> {
         other => Err(format!("Unknown key: {}", other)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnostics_leave_out_personal_text_and_mask_secrets() {
+        let mut settings = Settings::default();
+        settings.ai.api_key = "sk-secret".to_string();
+        settings.ai.prompt = "Format my notes".to_string();
+        settings.ai_fallbacks.push(settings.ai.clone());
+        settings.transcription.initial_prompt = "Jane Doe".to_string();
+        settings.postprocess.prefix_text = "Dear Jane".to_string();
+        settings.post_command = "notify-send {text}".to_string();
+        settings.output_file = "/home/jane/journal.md".to_string();
+        settings.target_window = "Jane's notes".to_string();
+        settings.model_mirror_url = "https://mirror.example".to_string();
+        settings.start_sound = "C:\\Users\\jane\\ding.wav".to_string();
+        settings.stop_sound = "/home/jane/dong.wav".to_string();
+        settings.input_device = Some("Jane's AirPods".to_string());
+        settings.ai.azure_resource = "jane-corp".to_string();
+        settings.ai.azure_deployment = "jane-gpt".to_string();
+
+        let mut json = serde_json::to_value(&settings).unwrap();
+        scrub_settings(&mut json);
+        let text = json.to_string();
+        let leaks = [
+            "sk-secret",
+            "Format my notes",
+            "Jane",
+            "jane",
+            "notify-send",
+            "mirror.example",
+        ];
+        for leaked in leaks {
+            assert!(!text.contains(leaked), "{} leaked", leaked);
+        }
+        assert_eq!(json["ai"]["api_key"], "[redacted]");
+        assert_eq!(json["ai_fallbacks"][0]["api_key"], "[redacted]");
+        assert!(json.get("hotkey").is_some());
+
+        // A custom endpoint names the user's own server
+        let mut json = serde_json::json!({ "ai": { "base_url": "https://llm.jane.example" } });
+        scrub_settings(&mut json);
+        assert!(!json.to_string().contains("jane"), "{}", json);
+    }
+}
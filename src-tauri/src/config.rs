@@ -1,5 +1,10 @@
 use directories::ProjectDirs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Overrides the whole data directory (settings + models).
+pub const DATA_DIR_ENV: &str = "WISPR_DATA_DIR";
+/// Overrides only the models directory, e.g. to keep large models on another drive.
+pub const MODELS_DIR_ENV: &str = "WISPR_MODELS_DIR";
 
 pub struct AppConfig {
     pub data_dir: PathBuf,
@@ -8,10 +13,12 @@ pub struct AppConfig {
 
 impl AppConfig {
     pub fn new() -> Self {
-        let proj_dirs = ProjectDirs::from("com", "wispr-local", "WisprLocal")
-            .expect("Failed to determine project directories");
-        let data_dir = proj_dirs.data_dir().to_path_buf();
-        let models_dir = data_dir.join("models");
+        let data_dir = env_dir(DATA_DIR_ENV).unwrap_or_else(|| {
+            let proj_dirs = ProjectDirs::from("com", "wispr-local", "WisprLocal")
+                .expect("Failed to determine project directories");
+            proj_dirs.data_dir().to_path_buf()
+        });
+        let models_dir = env_dir(MODELS_DIR_ENV).unwrap_or_else(|| data_dir.join("models"));
         Self {
             data_dir,
             models_dir,
@@ -19,7 +26,10 @@ impl AppConfig {
     }
 
     pub fn ensure_dirs(&self) -> std::io::Result<()> {
-        std::fs::create_dir_all(&self.models_dir)?;
+        for dir in [&self.data_dir, &self.models_dir] {
+            std::fs::create_dir_all(dir).map_err(|e| with_path(e, dir))?;
+            check_writable(dir)?;
+        }
         Ok(())
     }
 
@@ -27,3 +37,22 @@ impl AppConfig {
         self.models_dir.join(model_name)
     }
 }
+
+/// Read a directory override from the environment, ignoring empty values.
+fn env_dir(var: &str) -> Option<PathBuf> {
+    let value = std::env::var_os(var).filter(|v| !v.is_empty())?;
+    log::info!("Using {} override: {:?}", var, value);
+    Some(PathBuf::from(value))
+}
+
+/// Fail early if we can't write to `dir` rather than on the first save or download.
+fn check_writable(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(".wispr-write-test");
+    std::fs::write(&probe, b"").map_err(|e| with_path(e, dir))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
+fn with_path(e: std::io::Error, dir: &Path) -> std::io::Error {
+    std::io::Error::new(e.kind(), format!("{}: {}", dir.display(), e))
+}
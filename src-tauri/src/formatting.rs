@@ -1,5 +1,7 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 const DEFAULT_PROMPT: &str = "You are a text formatting assistant. The user dictated the following text via speech-to-text. \
 Format it into well-structured text:\n\
@@ -10,6 +12,38 @@ Format it into well-structured text:\n\
 - Do NOT change the meaning, rephrase, or add new content\n\
 - Output ONLY the formatted text, nothing else (no explanations, no quotes)";
 
+const MARKDOWN_PROMPT: &str = "You are a text formatting assistant. The user dictated the following text via speech-to-text. \
+Format it as valid Markdown for pasting into a Markdown editor:\n\
+- Add proper punctuation and capitalization\n\
+- Use # headings only where the user clearly introduces sections\n\
+- Format enumerations as - bullet lists, or 1. numbered lists for steps\n\
+- Put code, commands and file names in backticks; use fenced code blocks for multi-line code\n\
+- Do NOT change the meaning, rephrase, or add new content\n\
+- Output ONLY the Markdown itself, not wrapped in a code fence, with no explanations";
+
+/// Context carried from the end of one chunk into the next request.
+const CHUNK_OVERLAP_CHARS: usize = 300;
+
+/// Smallest max_input_chars accepted; anything lower makes chunks of a
+/// sentence or two, losing most of the formatting context.
+const MIN_INPUT_CHARS: usize = 500;
+
+/// Openers and sign-offs of a model talking about the text instead of just
+/// returning it, lowercase. Only flagged when the dictation didn't say them.
+const COMMENTARY_OPENERS: &[&str] = &[
+    "here is",
+    "here's",
+    "sure,",
+    "sure!",
+    "certainly",
+    "of course",
+    "formatted text:",
+    "the formatted text",
+    "i've formatted",
+    "i have formatted",
+];
+const COMMENTARY_CLOSERS: &[&str] = &["let me know", "i hope this", "feel free to", "note:"];
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AiProvider {
     #[serde(rename = "none")]
@@ -18,6 +52,11 @@ pub enum AiProvider {
     OpenAi,
     #[serde(rename = "claude")]
     Claude,
+    #[serde(rename = "azure_openai")]
+    AzureOpenAi,
+    /// Built-in deterministic clean-up; no network call.
+    #[serde(rename = "rules")]
+    Rules,
 }
 
 impl Default for AiProvider {
@@ -38,6 +77,106 @@ pub struct AiSettings {
     pub claude_model: String,
     #[serde(default = "default_prompt")]
     pub prompt: String,
+    /// Azure resource name: https://{resource}.openai.azure.com
+    #[serde(default)]
+    pub azure_resource: String,
+    /// Azure deployment name (stands in for the model name)
+    #[serde(default)]
+    pub azure_deployment: String,
+    #[serde(default = "default_azure_api_version")]
+    pub azure_api_version: String,
+    /// Send the prompt at the top of the user message instead of as a system
+    /// message, for OpenAI-compatible models that ignore the system role.
+    #[serde(default)]
+    pub prompt_in_user_message: bool,
+    /// Longer dictations are split on sentence boundaries and formatted in
+    /// chunks of at most this many characters (0 = never split).
+    #[serde(default = "default_max_input_chars")]
+    pub max_input_chars: usize,
+    /// Keep the raw text when the output looks like the model rewrote it or
+    /// added commentary (see `check_output`).
+    #[serde(default = "crate::settings::default_true")]
+    pub raw_fallback: bool,
+    /// Output more than this many times longer than the input is rejected (0 = no limit).
+    #[serde(default = "default_max_length_ratio")]
+    pub max_length_ratio: f32,
+    /// Output shorter than this fraction of the input is rejected (0 = no limit).
+    #[serde(default = "default_min_length_ratio")]
+    pub min_length_ratio: f32,
+    /// Extra headers sent with every provider request, e.g. for API gateways.
+    /// A header with the same name as a built-in one (auth, version) replaces it.
+    #[serde(default)]
+    pub custom_headers: BTreeMap<String, String>,
+}
+
+/// A named formatting prompt, e.g. "email" or "notes".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptPreset {
+    pub name: String,
+    pub prompt: String,
+    /// Check the AI output is well-formed Markdown and repair what it can.
+    #[serde(default)]
+    pub markdown: bool,
+}
+
+/// Presets that ship with the app. User presets can't reuse these names.
+pub fn builtin_presets() -> Vec<PromptPreset> {
+    vec![PromptPreset {
+        name: "markdown".to_string(),
+        prompt: MARKDOWN_PROMPT.to_string(),
+        markdown: true,
+    }]
+}
+
+/// Light Markdown clean-up for AI output: unwrap a reply wrapped whole in a
+/// ```markdown fence, and close a code fence left open by a truncated reply.
+pub fn validate_markdown(text: &str) -> String {
+    let mut text = text.trim().to_string();
+
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.len() >= 2 {
+        let first = lines[0].trim();
+        let last = lines[lines.len() - 1].trim();
+        if matches!(first, "```markdown" | "```md") && last == "```" {
+            text = lines[1..lines.len() - 1].join("\n");
+        }
+    }
+
+    // A fence is closed only by a bare run of its own character at least as
+    // long, so a ~~~ inside a ``` block is content
+    let mut open: Option<&str> = None;
+    for line in text.lines() {
+        let line = line.trim();
+        let Some(c) = line.chars().next().filter(|c| matches!(c, '`' | '~')) else {
+            continue;
+        };
+        let run = &line[..line.len() - line.trim_start_matches(c).len()];
+        match open {
+            None if run.len() >= 3 => open = Some(run),
+            Some(fence) if fence.starts_with(c) && run.len() >= fence.len() && run == line => {
+                open = None
+            }
+            _ => {}
+        }
+    }
+    if let Some(fence) = open.map(str::to_string) {
+        log::warn!("AI output has an unclosed code fence, closing it");
+        text.push('\n');
+        text.push_str(&fence);
+    }
+    text
+}
+
+/// How a single recording session is formatted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum FormatChoice {
+    /// Use the configured providers and their own prompts.
+    #[default]
+    Configured,
+    /// Skip AI formatting entirely.
+    Raw,
+    /// Use the configured providers with this preset's prompt.
+    Preset(String),
 }
 
 fn default_openai_model() -> String {
@@ -49,6 +188,18 @@ fn default_claude_model() -> String {
 fn default_prompt() -> String {
     DEFAULT_PROMPT.to_string()
 }
+fn default_azure_api_version() -> String {
+    "2024-06-01".to_string()
+}
+fn default_max_input_chars() -> usize {
+    6000
+}
+fn default_max_length_ratio() -> f32 {
+    2.0
+}
+fn default_min_length_ratio() -> f32 {
+    0.5
+}
 
 impl Default for AiSettings {
     fn default() -> Self {
@@ -58,35 +209,337 @@ impl Default for AiSettings {
             openai_model: default_openai_model(),
             claude_model: default_claude_model(),
             prompt: default_prompt(),
+            azure_resource: String::new(),
+            azure_deployment: String::new(),
+            azure_api_version: default_azure_api_version(),
+            prompt_in_user_message: false,
+            max_input_chars: default_max_input_chars(),
+            raw_fallback: true,
+            max_length_ratio: default_max_length_ratio(),
+            min_length_ratio: default_min_length_ratio(),
+            custom_headers: BTreeMap::new(),
+        }
+    }
+}
+
+impl AiSettings {
+    /// Check that the selected provider has what it needs to make a request.
+    /// A `None` provider is always valid so partial configs can be saved, and
+    /// `Rules` needs nothing.
+    pub fn validate(&self) -> Result<(), String> {
+        self.header_map()?;
+        let (name, model) = match self.provider {
+            AiProvider::None | AiProvider::Rules => return Ok(()),
+            AiProvider::OpenAi => ("OpenAI", &self.openai_model),
+            AiProvider::Claude => ("Claude", &self.claude_model),
+            AiProvider::AzureOpenAi => {
+                if self.azure_resource.trim().is_empty() {
+                    return Err("Azure OpenAI resource name is required".to_string());
+                }
+                if self.azure_deployment.trim().is_empty() {
+                    return Err("Azure OpenAI deployment name is required".to_string());
+                }
+                ("Azure OpenAI", &self.azure_deployment)
+            }
+        };
+        if self.api_key.trim().is_empty() {
+            return Err(format!("{} API key is required", name));
+        }
+        if model.trim().is_empty() {
+            return Err(format!("{} model name is required", name));
+        }
+        if self.prompt.trim().is_empty() {
+            return Err("Formatting prompt must not be empty".to_string());
+        }
+        if self.max_input_chars > 0 && self.max_input_chars < MIN_INPUT_CHARS {
+            return Err(format!(
+                "Max input length must be at least {} characters (or 0 for no limit)",
+                MIN_INPUT_CHARS
+            ));
+        }
+        if self.max_length_ratio != 0.0 && self.max_length_ratio <= 1.0 {
+            return Err("Max length ratio must be above 1 (or 0 for no limit)".to_string());
+        }
+        if !(0.0..1.0).contains(&self.min_length_ratio) {
+            return Err("Min length ratio must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// `custom_headers` as a header map, or an error naming the first
+    /// invalid header name or value.
+    pub fn header_map(&self) -> Result<HeaderMap, String> {
+        let mut map = HeaderMap::new();
+        for (name, value) in &self.custom_headers {
+            let header = HeaderName::from_bytes(name.trim().as_bytes())
+                .map_err(|_| format!("Invalid header name \"{}\"", name))?;
+            let mut value = HeaderValue::from_str(value.trim())
+                .map_err(|_| format!("Invalid value for header \"{}\"", name))?;
+            // Gateway headers often carry tokens; keep them out of debug output
+            value.set_sensitive(true);
+            map.insert(header, value);
+        }
+        Ok(map)
+    }
+
+    /// Model (or Azure deployment) this provider sends requests to.
+    pub fn model_name(&self) -> &str {
+        match self.provider {
+            AiProvider::None | AiProvider::Rules => "",
+            AiProvider::OpenAi => &self.openai_model,
+            AiProvider::Claude => &self.claude_model,
+            AiProvider::AzureOpenAi => &self.azure_deployment,
         }
     }
 }
 
-/// Format transcribed text using the configured AI provider.
-/// Returns the original text if provider is None or on error.
-pub async fn format_text(text: &str, settings: &AiSettings) -> String {
-    if settings.provider == AiProvider::None || text.trim().is_empty() {
-        return text.to_string();
+/// What `format_text_detailed` did: the text plus which provider produced
+/// it and why earlier providers in the chain were skipped.
+#[derive(Debug, Clone, Serialize)]
+pub struct FormatOutcome {
+    pub text: String,
+    /// None when every provider failed and the input came back unchanged.
+    pub provider: Option<AiProvider>,
+    pub model: Option<String>,
+    /// One message per provider that failed, in chain order.
+    pub errors: Vec<String>,
+    /// Why the last rejected output was thrown away, when the raw text was
+    /// kept because of it.
+    pub rejected: Option<String>,
+}
+
+/// Sanity check on a provider's output before it replaces the raw text: the
+/// length must stay within the configured ratios of the input, and it must
+/// not open or close with chatter the dictation didn't contain. Catches
+/// models that ignore the "do not add content" instruction. The Rules
+/// provider is exempt, as its output is deterministic.
+pub fn check_output(raw: &str, formatted: &str, settings: &AiSettings) -> Result<(), String> {
+    if !settings.raw_fallback || settings.provider == AiProvider::Rules {
+        return Ok(());
+    }
+    let raw_len = raw.trim().chars().count().max(1) as f32;
+    let ratio = formatted.trim().chars().count() as f32 / raw_len;
+    if settings.max_length_ratio > 0.0 && ratio > settings.max_length_ratio {
+        return Err(format!("output is {:.1}x the length of the input", ratio));
+    }
+    if ratio < settings.min_length_ratio {
+        return Err(format!("output is only {:.0}% of the length of the input", ratio * 100.0));
+    }
+
+    let raw = raw.trim().to_lowercase();
+    let formatted = formatted.trim().to_lowercase();
+    if let Some(opener) = COMMENTARY_OPENERS
+        .iter()
+        .find(|o| formatted.starts_with(*o) && !raw.starts_with(*o))
+    {
+        return Err(format!("output starts with commentary ({:?})", opener));
     }
+    let last_line = formatted.lines().last().unwrap_or_default().trim_start_matches(['-', '*', ' ']);
+    if let Some(closer) = COMMENTARY_CLOSERS
+        .iter()
+        .find(|c| last_line.starts_with(*c) && !raw.contains(*c))
+    {
+        return Err(format!("output ends with commentary ({:?})", closer));
+    }
+    Ok(())
+}
 
-    log::info!("AI formatting with {:?} provider ({} chars)", settings.provider, text.len());
+/// Format transcribed text, trying each provider in order until one succeeds.
+/// Entries with provider None are skipped.
+/// Returns the original text if no provider is configured or all of them fail.
+pub async fn format_text(text: &str, providers: &[AiSettings]) -> String {
+    format_text_detailed(text, providers).await.text
+}
 
-    let result = match settings.provider {
-        AiProvider::OpenAi => format_with_openai(text, settings).await,
-        AiProvider::Claude => format_with_claude(text, settings).await,
-        AiProvider::None => return text.to_string(),
+/// `format_text`, also reporting the provider that ran and the errors of
+/// those that failed.
+pub async fn format_text_detailed(text: &str, providers: &[AiSettings]) -> FormatOutcome {
+    let mut outcome = FormatOutcome {
+        text: text.to_string(),
+        provider: None,
+        model: None,
+        errors: Vec::new(),
+        rejected: None,
     };
+    if text.trim().is_empty() {
+        return outcome;
+    }
+
+    for (i, settings) in providers.iter().enumerate() {
+        if settings.provider == AiProvider::None {
+            continue;
+        }
+
+        log::info!("AI formatting with {:?} provider ({} chars)", settings.provider, text.len());
+
+        let result = format_chunked(text, settings).await.and_then(|formatted| {
+            check_output(text, &formatted, settings)
+                .map(|_| formatted)
+                .map_err(|reason| {
+                    outcome.rejected = Some(reason.clone());
+                    format!("rejected, {}", reason)
+                })
+        });
+        match result {
+            Ok(formatted) => {
+                log::info!(
+                    "AI formatted by {:?} (chain position {}): {} chars -> {} chars",
+                    settings.provider,
+                    i,
+                    text.len(),
+                    formatted.len()
+                );
+                outcome.text = formatted;
+                outcome.provider = Some(settings.provider.clone());
+                outcome.model = Some(settings.model_name().to_string()).filter(|m| !m.is_empty());
+                outcome.rejected = None;
+                return outcome;
+            }
+            Err(e) => {
+                log::warn!("AI formatting with {:?} failed: {}", settings.provider, e);
+                outcome.errors.push(format!("{:?}: {}", settings.provider, e));
+            }
+        }
+    }
+
+    if providers.iter().any(|p| p.provider != AiProvider::None) {
+        log::error!("All AI providers failed, using raw text");
+    }
+    outcome
+}
+
+/// Split `text` into sentences, keeping each sentence's trailing whitespace.
+fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        if matches!(c, '.' | '!' | '?' | '…') {
+            // End the sentence after the punctuation and any following whitespace
+            let mut end = None;
+            while let Some(&(j, next)) = chars.peek() {
+                if !next.is_whitespace() {
+                    break;
+                }
+                end = Some(j + next.len_utf8());
+                chars.next();
+            }
+            if let Some(end) = end {
+                sentences.push(&text[start..end]);
+                start = end;
+            }
+        }
+    }
+    if start < text.len() {
+        sentences.push(&text[start..]);
+    }
+    sentences
+}
 
-    match result {
-        Ok(formatted) => {
-            log::info!("AI formatted: {} chars -> {} chars", text.len(), formatted.len());
-            formatted
+/// Pack sentences into chunks of at most `max_chars` characters. A single
+/// sentence longer than that is split between words.
+pub fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0;
+
+    let mut push = |piece: &str, current: &mut String, current_len: &mut usize| {
+        let len = piece.chars().count();
+        if *current_len + len > max_chars && !current.is_empty() {
+            chunks.push(std::mem::take(current).trim().to_string());
+            *current_len = 0;
         }
-        Err(e) => {
-            log::error!("AI formatting failed: {}, using raw text", e);
-            text.to_string()
+        current.push_str(piece);
+        *current_len += len;
+    };
+
+    for sentence in split_sentences(text) {
+        if sentence.chars().count() <= max_chars {
+            push(sentence, &mut current, &mut current_len);
+        } else {
+            for word in sentence.split_inclusive(char::is_whitespace) {
+                push(word, &mut current, &mut current_len);
+            }
         }
     }
+    if !current.trim().is_empty() {
+        chunks.push(current.trim().to_string());
+    }
+    chunks
+}
+
+/// Whole sentences from the end of `chunk`, up to about CHUNK_OVERLAP_CHARS.
+fn overlap_context(chunk: &str) -> String {
+    let mut context = Vec::new();
+    let mut len = 0;
+    for sentence in split_sentences(chunk).into_iter().rev() {
+        len += sentence.chars().count();
+        if len > CHUNK_OVERLAP_CHARS && !context.is_empty() {
+            break;
+        }
+        context.push(sentence);
+    }
+    context.reverse();
+    context.concat().trim().to_string()
+}
+
+/// Run one provider, splitting text over its max_input_chars into chunks.
+/// Each chunk after the first is sent with the end of the previous one as
+/// read-only context so sentences spanning the cut are formatted consistently.
+/// Any failing chunk fails the whole text so the next provider can retry it.
+async fn format_chunked(text: &str, settings: &AiSettings) -> Result<String, String> {
+    let max = settings.max_input_chars;
+    // Rules have no input limit, and chunking would only add paragraph breaks
+    if max == 0 || settings.provider == AiProvider::Rules || text.chars().count() <= max {
+        return format_with_provider(text, settings).await;
+    }
+
+    let chunks = chunk_text(text, max);
+    log::info!("Text exceeds {} chars, formatting in {} chunks", max, chunks.len());
+
+    let mut formatted = Vec::with_capacity(chunks.len());
+    for (i, chunk) in chunks.iter().enumerate() {
+        let result = if i == 0 {
+            format_with_provider(chunk, settings).await
+        } else {
+            let mut chunk_settings = settings.clone();
+            chunk_settings.prompt = format!(
+                "{}\n\nThis text continues a longer dictation. It follows on from: \"{}\"\n\
+                 Use that only as context; do not repeat it in your output.",
+                settings.prompt,
+                overlap_context(&chunks[i - 1])
+            );
+            format_with_provider(chunk, &chunk_settings).await
+        };
+        formatted.push(result.map_err(|e| format!("chunk {}/{}: {}", i + 1, chunks.len(), e))?);
+    }
+    Ok(formatted.join("\n\n"))
+}
+
+/// Run a single provider.
+async fn format_with_provider(text: &str, settings: &AiSettings) -> Result<String, String> {
+    match settings.provider {
+        AiProvider::OpenAi => format_with_openai(text, settings).await,
+        AiProvider::Claude => format_with_claude(text, settings).await,
+        AiProvider::AzureOpenAi => format_with_azure(text, settings).await,
+        AiProvider::Rules => Ok(crate::postprocess::rules::format(text)),
+        AiProvider::None => Ok(text.to_string()),
+    }
+}
+
+/// Chat Completions "messages": the prompt as a system message, or folded
+/// into the user message when `prompt_in_user_message` is set.
+fn chat_messages(text: &str, settings: &AiSettings) -> serde_json::Value {
+    if settings.prompt_in_user_message {
+        serde_json::json!([
+            { "role": "user", "content": format!("{}\n\n---\n\n{}", settings.prompt, text) }
+        ])
+    } else {
+        serde_json::json!([
+            { "role": "system", "content": settings.prompt },
+            { "role": "user", "content": text }
+        ])
+    }
 }
 
 /// OpenAI Chat Completions API
@@ -97,17 +550,16 @@ async fn format_with_openai(text: &str, settings: &AiSettings) -> Result<String,
 
     let body = serde_json::json!({
         "model": settings.openai_model,
-        "messages": [
-            { "role": "system", "content": settings.prompt },
-            { "role": "user", "content": text }
-        ],
+        "messages": chat_messages(text, settings),
         "temperature": 0.1
     });
 
+    let headers = settings.header_map()?;
     let client = Client::new();
     let resp = client
         .post("https://api.openai.com/v1/chat/completions")
         .header("Authorization", format!("Bearer {}", settings.api_key))
+        .headers(headers)
         .json(&body)
         .timeout(std::time::Duration::from_secs(30))
         .send()
@@ -131,6 +583,59 @@ async fn format_with_openai(text: &str, settings: &AiSettings) -> Result<String,
         .ok_or_else(|| "No content in OpenAI response".to_string())
 }
 
+/// Azure OpenAI Chat Completions API (deployment-based URL, api-key header)
+async fn format_with_azure(text: &str, settings: &AiSettings) -> Result<String, String> {
+    if settings.api_key.is_empty() {
+        return Err("Azure OpenAI API key not set".to_string());
+    }
+
+    let api_version = if settings.azure_api_version.trim().is_empty() {
+        default_azure_api_version()
+    } else {
+        settings.azure_api_version.trim().to_string()
+    };
+    let url = format!(
+        "https://{}.openai.azure.com/openai/deployments/{}/chat/completions",
+        settings.azure_resource.trim(),
+        settings.azure_deployment.trim()
+    );
+
+    // The deployment determines the model, so no "model" field is sent
+    let body = serde_json::json!({
+        "messages": chat_messages(text, settings),
+        "temperature": 0.1
+    });
+
+    let headers = settings.header_map()?;
+    let client = Client::new();
+    let resp = client
+        .post(&url)
+        .query(&[("api-version", api_version.as_str())])
+        .header("api-key", &settings.api_key)
+        .headers(headers)
+        .json(&body)
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| format!("Azure OpenAI request failed: {}", e))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Azure OpenAI error {}: {}", status, body));
+    }
+
+    let json: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Azure OpenAI response: {}", e))?;
+
+    json["choices"][0]["message"]["content"]
+        .as_str()
+        .map(|s| s.trim().to_string())
+        .ok_or_else(|| "No content in Azure OpenAI response".to_string())
+}
+
 /// Anthropic Messages API
 async fn format_with_claude(text: &str, settings: &AiSettings) -> Result<String, String> {
     if settings.api_key.is_empty() {
@@ -147,12 +652,14 @@ async fn format_with_claude(text: &str, settings: &AiSettings) -> Result<String,
         "temperature": 0.1
     });
 
+    let headers = settings.header_map()?;
     let client = Client::new();
     let resp = client
         .post("https://api.anthropic.com/v1/messages")
         .header("x-api-key", &settings.api_key)
         .header("anthropic-version", "2023-06-01")
         .header("content-type", "application/json")
+        .headers(headers)
         .json(&body)
         .timeout(std::time::Duration::from_secs(30))
         .send()
@@ -175,3 +682,115 @@ async fn format_with_claude(text: &str, settings: &AiSettings) -> Result<String,
         .map(|s| s.trim().to_string())
         .ok_or_else(|| "No content in Claude response".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checked(raw: &str, formatted: &str) -> Result<(), String> {
+        check_output(raw, formatted, &AiSettings::default())
+    }
+
+    #[test]
+    fn output_within_ratios_passes() {
+        assert!(checked("hello world", "Hello, world.").is_ok());
+        // Exactly at either limit still passes
+        assert!(checked("abcd", "abcdabcd").is_ok());
+        assert!(checked("abcd", "ab").is_ok());
+    }
+
+    #[test]
+    fn output_outside_ratios_is_rejected() {
+        assert!(checked("abcd", "abcdabcda").is_err());
+        assert!(checked("abcd", "a").is_err());
+        assert!(checked("hello world", "").is_err());
+    }
+
+    #[test]
+    fn zero_ratios_disable_the_length_checks() {
+        let settings = AiSettings {
+            max_length_ratio: 0.0,
+            min_length_ratio: 0.0,
+            ..AiSettings::default()
+        };
+        assert!(check_output("hi", &"hi ".repeat(50), &settings).is_ok());
+        assert!(check_output("hello world", "", &settings).is_ok());
+    }
+
+    #[test]
+    fn commentary_is_rejected_unless_dictated() {
+        assert!(checked("the meeting is at noon", "Here is the meeting at noon.").is_err());
+        assert!(checked("the meeting is at noon", "The meeting is at noon.\nLet me know").is_err());
+        assert!(checked("here is the plan", "Here is the plan.").is_ok());
+    }
+
+    #[test]
+    fn checks_are_skipped_without_raw_fallback_or_for_rules() {
+        let off = AiSettings {
+            raw_fallback: false,
+            ..AiSettings::default()
+        };
+        assert!(check_output("abcd", "a", &off).is_ok());
+        let rules = AiSettings {
+            provider: AiProvider::Rules,
+            ..AiSettings::default()
+        };
+        assert!(check_output("abcd", "a", &rules).is_ok());
+    }
+
+    #[test]
+    fn markdown_wrapper_fence_is_removed() {
+        assert_eq!(validate_markdown("```markdown\n# Title\n- item\n```"), "# Title\n- item");
+        assert_eq!(validate_markdown("# Title\n```\ncode\n```"), "# Title\n```\ncode\n```");
+    }
+
+    #[test]
+    fn unclosed_fence_is_closed_with_its_own_marker() {
+        assert_eq!(
+            validate_markdown("Intro\n```rust\nfn main()"),
+            "Intro\n```rust\nfn main()\n```"
+        );
+        assert_eq!(validate_markdown("~~~\ncode"), "~~~\ncode\n~~~");
+        assert_eq!(validate_markdown("````\ncode\n```"), "````\ncode\n```\n````");
+    }
+
+    #[test]
+    fn other_fence_inside_a_block_is_content() {
+        let closed = "```\n~~~\n```";
+        assert_eq!(validate_markdown(closed), closed);
+        assert_eq!(validate_markdown("```\n~~~"), "```\n~~~\n```");
+        // A fence line with an info string doesn't close a block
+        assert_eq!(validate_markdown("```\n```js"), "```\n```js\n```");
+    }
+
+    #[test]
+    fn sentences_keep_their_trailing_whitespace() {
+        assert_eq!(split_sentences("Hi. Ok?  Yes"), vec!["Hi. ", "Ok?  ", "Yes"]);
+        // No whitespace after the dot, so no sentence end
+        assert_eq!(split_sentences("Version 1.5 ships."), vec!["Version 1.5 ships."]);
+    }
+
+    #[test]
+    fn short_text_is_one_chunk() {
+        assert_eq!(chunk_text("One. Two.", 100), vec!["One. Two."]);
+        assert_eq!(chunk_text("One. Two. Three.", 10), vec!["One. Two.", "Three."]);
+    }
+
+    #[test]
+    fn long_sentence_is_split_between_words() {
+        let chunks = chunk_text("alpha beta gamma delta.", 11);
+        assert_eq!(chunks, vec!["alpha beta", "gamma", "delta."]);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 11));
+    }
+
+    #[test]
+    fn overlap_is_whole_sentences_from_the_chunk_end() {
+        assert_eq!(overlap_context("One. Two."), "One. Two.");
+        let first = format!("{}. ", "a".repeat(200));
+        let last = format!("{}.", "b".repeat(200));
+        assert_eq!(overlap_context(&format!("{}{}", first, last)), last);
+        // The last sentence is kept even when it alone is over the limit
+        let long = format!("{}.", "c".repeat(CHUNK_OVERLAP_CHARS + 100));
+        assert_eq!(overlap_context(&format!("Short. {}", long)), long);
+    }
+}
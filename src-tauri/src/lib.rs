@@ -2,25 +2,29 @@ pub mod audio;
 pub mod commands;
 pub mod config;
 pub mod formatting;
+pub mod logging;
+pub mod postprocess;
 pub mod settings;
 pub mod state;
 pub mod system;
 pub mod transcription;
 
+use std::path::PathBuf;
 use std::sync::Mutex;
 use tauri::{Emitter, Listener, Manager};
 
 use audio::buffer::AudioBuffer;
 use audio::capture::AudioCapture;
+use audio::TARGET_SAMPLE_RATE;
 use config::AppConfig;
-use settings::Settings;
-use state::{AppState, AppStatus};
+use settings::{RecordingMode, SaveScheduler, Settings};
+use state::{AppState, AppStatus, TranscriptionPair};
 use system::sounds::SoundPlayer;
 use transcription::engine::WhisperEngine;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    env_logger::init();
+    logging::init();
 
     tauri::Builder::default()
         .plugin(
@@ -29,14 +33,8 @@ pub fn run() {
                     use tauri_plugin_global_shortcut::ShortcutState;
                     log::info!("Hotkey event: {:?} state={:?}", shortcut, event.state);
                     match event.state {
-                        ShortcutState::Pressed => {
-                            log::info!("Hotkey PRESSED - starting recording");
-                            let _ = app.emit("hotkey-start-recording", ());
-                        }
-                        ShortcutState::Released => {
-                            log::info!("Hotkey RELEASED - stopping recording");
-                            let _ = app.emit("hotkey-stop-recording", ());
-                        }
+                        ShortcutState::Pressed => on_hotkey_pressed(app, shortcut.id()),
+                        ShortcutState::Released => on_hotkey_released(app),
                     }
                 })
                 .build(),
@@ -47,42 +45,44 @@ pub fn run() {
             // Initialize configuration
             let config = AppConfig::new();
             config.ensure_dirs().expect("Failed to create app directories");
+            log::info!(
+                "Data dir: {:?}, models dir: {:?}",
+                config.data_dir,
+                config.models_dir
+            );
 
             // Initialize audio pipeline
             let buffer = AudioBuffer::new();
-            let capture = AudioCapture::new(buffer.clone());
-
-            // Initialize Whisper engine and try loading model
-            let mut engine = WhisperEngine::new();
-            let model_filename = "ggml-medium.bin";
-            let model_path = config.model_path(model_filename);
-
-            let mut initial_state = AppState::default();
-
-            if model_path.exists() {
-                match engine.load_model(&model_path) {
-                    Ok(_) => {
-                        log::info!("Model loaded from {:?}", model_path);
-                        initial_state.model_loaded = true;
-                    }
-                    Err(e) => log::error!("Failed to load model: {}", e),
-                }
-            } else {
-                log::warn!(
-                    "Model not found at {:?}. Download it to enable transcription.",
-                    model_path
-                );
-            }
+            let mut capture = AudioCapture::new(buffer.clone());
 
             // Load settings
             let user_settings = Settings::load(&config.data_dir);
+            logging::apply_saved_level(&user_settings.log_level);
             log::info!("Loaded hotkey setting: {}", user_settings.hotkey);
+            capture.set_high_pass(user_settings.high_pass_cutoff());
+            capture.set_device(user_settings.input_device.clone());
+            if let Some(name) = &user_settings.input_device {
+                // Not an error: recording falls back to the default device.
+                // The page isn't listening yet; it asks get_input_device
+                if audio::devices::find_input_device(name).is_none() {
+                    log::warn!("Saved input device {:?} isn't connected, using the default", name);
+                }
+            }
+
+            // Initialize Whisper engine and try loading model
+            let mut engine = WhisperEngine::new();
+            engine.set_settings(user_settings.active_transcription());
+            engine.set_context_settings(user_settings.model_context.clone());
+            let model_path = config.model_path(&user_settings.model_filename);
 
-            // Initialize sound player (persistent output stream) with settings
+            let initial_state = AppState::default();
+
+            // Initialize sound player with settings
             let sound_player = SoundPlayer::new(
                 user_settings.start_sound.clone(),
                 user_settings.stop_sound.clone(),
                 user_settings.sound_volume,
+                user_settings.keep_sound_output_open,
             );
 
             // Register state
@@ -94,6 +94,26 @@ pub fn run() {
             app.manage(sound_player);
             app.manage(Mutex::new(user_settings.clone()));
 
+            let app_handle = app.handle().clone();
+            app.manage(SaveScheduler::new(move || {
+                let settings = app_handle.state::<Mutex<Settings>>();
+                let saved = settings.lock().unwrap().save(&app_handle.state::<AppConfig>().data_dir);
+                if let Err(e) = saved {
+                    log::error!("Failed to save settings: {}", e);
+                }
+            }));
+
+            // Load the model in the background so the window appears right away;
+            // the UI enables recording on "model-loaded"
+            if model_path.exists() {
+                spawn_model_load(app.handle().clone(), model_path);
+            } else {
+                log::warn!(
+                    "Model not found at {:?}. Download it to enable transcription.",
+                    model_path
+                );
+            }
+
             // Setup system tray
             system::tray::setup_tray(app.handle())?;
 
@@ -104,25 +124,64 @@ pub fn run() {
                     .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
                 app.global_shortcut().register(shortcut)?;
                 log::info!("Global hotkey registered: {} (hold to dictate)", user_settings.hotkey);
+
+                // A bad binding shouldn't stop the app from starting
+                for binding in &user_settings.hotkey_bindings {
+                    match commands::parse_hotkey(&binding.hotkey) {
+                        Ok(sc) => match app.global_shortcut().register(sc) {
+                            Ok(_) => log::info!(
+                                "Preset hotkey registered: {} -> {:?}",
+                                binding.hotkey,
+                                binding.preset
+                            ),
+                            Err(e) => log::warn!("Failed to register {}: {}", binding.hotkey, e),
+                        },
+                        Err(e) => log::warn!("Invalid preset hotkey {}: {}", binding.hotkey, e),
+                    }
+                }
             }
 
+            let double_tap = system::double_tap::DoubleTap::default();
+            double_tap.configure(
+                app.handle(),
+                &user_settings.double_tap_modifier,
+                user_settings.double_tap_window_ms,
+            );
+            app.manage(double_tap);
+
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                idle_unload_loop(app_handle).await;
+            });
+
             // Make close button hide the window instead of destroying it
             if let Some(window) = app.get_webview_window("main") {
                 let w = window.clone();
-                window.on_window_event(move |event| {
-                    if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                window.on_window_event(move |event| match event {
+                    tauri::WindowEvent::CloseRequested { api, .. } => {
                         api.prevent_close();
                         let _ = w.hide();
                     }
+                    // A hotkey release can be missed while focus moves between
+                    // windows, so re-check the physical key state on refocus
+                    tauri::WindowEvent::Focused(true) => resync_hotkey_state(w.app_handle()),
+                    tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) => {
+                        let app = w.app_handle().clone();
+                        let paths = paths.clone();
+                        tauri::async_runtime::spawn(transcribe_dropped_files(app, paths));
+                    }
+                    _ => {}
                 });
             }
 
             // Handle start recording (from hotkey or tray)
             let app_handle = app.handle().clone();
-            app.listen("hotkey-start-recording", move |_event| {
+            app.listen("hotkey-start-recording", move |event| {
                 let app = app_handle.clone();
+                let shortcut_id: Option<u32> = serde_json::from_str(event.payload()).ok();
                 tauri::async_runtime::spawn(async move {
-                    start_recording_flow(&app);
+                    let (hotkey, format) = resolve_hotkey(&app, shortcut_id);
+                    start_recording_flow(&app, Some(hotkey), format);
                 });
             });
 
@@ -130,7 +189,7 @@ pub fn run() {
             app.listen("tray-start-recording", move |_event| {
                 let app = app_handle.clone();
                 tauri::async_runtime::spawn(async move {
-                    start_recording_flow(&app);
+                    start_recording_flow(&app, None, formatting::FormatChoice::Configured);
                 });
             });
 
@@ -139,7 +198,8 @@ pub fn run() {
             app.listen("hotkey-stop-recording", move |_event| {
                 let app = app_handle.clone();
                 tauri::async_runtime::spawn(async move {
-                    stop_and_transcribe_flow(&app).await;
+                    let format = session_format(&app);
+                    stop_and_transcribe_flow(&app, format).await;
                 });
             });
 
@@ -147,52 +207,997 @@ pub fn run() {
             app.listen("tray-stop-recording", move |_event| {
                 let app = app_handle.clone();
                 tauri::async_runtime::spawn(async move {
-                    stop_and_transcribe_flow(&app).await;
+                    let format = session_format(&app);
+                    stop_and_transcribe_flow(&app, format).await;
                 });
             });
 
+            // Follow the status with the on-screen recording indicator
+            let app_handle = app.handle().clone();
+            app.listen("status-changed", move |_event| {
+                system::indicator::update(&app_handle);
+            });
+
+            // The window is created hidden; show it last, once everything
+            // above is wired up, unless the app should stay in the tray
+            if user_settings.start_minimized {
+                log::info!("Starting minimized to the tray");
+            } else if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::start_recording,
             commands::stop_recording_and_transcribe,
+            commands::pause_recording,
+            commands::resume_recording,
+            commands::get_post_command,
+            commands::set_post_command,
             commands::get_status,
+            commands::get_recording_duration,
+            commands::get_diagnostics,
+            commands::test_injection,
+            commands::inject,
+            commands::get_target_window,
+            commands::set_target_window,
+            commands::get_auto_advance,
+            commands::set_auto_advance,
+            commands::get_paste_shortcut,
+            commands::set_paste_shortcut,
+            commands::get_injection_method,
+            commands::set_injection_method,
+            commands::get_clipboard_restore,
+            commands::set_clipboard_restore,
             commands::is_model_loaded,
             commands::get_last_transcription,
+            commands::get_last_transcription_pair,
+            commands::copy_last_transcription,
+            commands::format_preview,
+            commands::reformat_last,
             commands::get_models_dir,
+            commands::get_log_level,
+            commands::set_log_level,
             commands::get_hotkey,
             commands::set_hotkey,
+            commands::get_recording_limits,
+            commands::set_recording_limits,
+            commands::get_idle_unload_minutes,
+            commands::set_idle_unload_minutes,
+            commands::get_recording_mode,
+            commands::set_recording_mode,
+            commands::get_stutter_tolerance,
+            commands::set_stutter_tolerance,
+            commands::get_start_minimized,
+            commands::set_start_minimized,
+            commands::get_tap_to_reinject,
+            commands::set_tap_to_reinject,
+            commands::get_undo_injection,
+            commands::set_undo_injection,
+            commands::undo_last_injection,
+            commands::get_double_tap,
+            commands::set_double_tap,
             commands::get_sound_settings,
             commands::set_sound_settings,
+            commands::get_keep_sound_output_open,
+            commands::set_keep_sound_output_open,
             commands::test_sound,
+            commands::get_empty_feedback,
+            commands::set_empty_feedback,
+            commands::test_mic,
+            commands::calibrate_noise,
+            commands::get_denoise,
+            commands::set_denoise,
+            commands::get_device_configs,
+            commands::get_high_pass,
+            commands::set_high_pass,
+            commands::list_audio_devices,
+            commands::get_input_device,
+            commands::set_input_device,
+            commands::get_edge_fade,
+            commands::set_edge_fade,
             commands::get_ai_settings,
             commands::set_ai_settings,
+            commands::get_ai_enabled,
+            commands::set_ai_enabled,
+            commands::toggle_ai_enabled,
+            commands::get_ai_fallbacks,
+            commands::set_ai_fallbacks,
+            commands::get_prompt_presets,
+            commands::get_builtin_presets,
+            commands::set_prompt_presets,
+            commands::get_hotkey_bindings,
+            commands::set_hotkey_bindings,
+            commands::get_transcription_settings,
+            commands::set_transcription_settings,
+            commands::get_best_of,
+            commands::set_best_of,
+            commands::get_no_speech_threshold,
+            commands::get_token_suppression,
+            commands::set_token_suppression,
+            commands::get_recent_transcriptions,
+            commands::get_chunk_seconds,
+            commands::set_chunk_seconds,
+            commands::get_registered_hotkeys,
+            commands::check_hotkey_conflict,
+            commands::get_indicator_settings,
+            commands::set_indicator_settings,
+            commands::list_monitors,
+            commands::get_output_settings,
+            commands::set_output_settings,
+            commands::get_model_context_settings,
+            commands::set_model_context_settings,
+            commands::get_auto_stop_silence,
+            commands::set_auto_stop_silence,
+            commands::reinject_history,
+            commands::set_no_speech_threshold,
+            commands::get_transcription_mode,
+            commands::set_transcription_mode,
+            commands::get_language_models,
+            commands::set_language_models,
+            commands::get_model_overrides,
+            commands::set_model_overrides,
+            commands::set_language,
+            commands::get_postprocess_settings,
+            commands::set_postprocess_settings,
+            commands::list_models,
+            commands::download_model,
+            commands::transcribe_file,
+            commands::benchmark_models,
+            commands::estimate_transcription_time,
+            commands::get_model_capabilities,
+            commands::check_model_memory,
+            commands::get_model_mirror,
+            commands::set_model_mirror,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Don't lose a change still waiting in the save debounce
+            if let tauri::RunEvent::Exit = event {
+                app.state::<SaveScheduler>().flush();
+            }
+        });
+}
+
+/// Tries per model load. Antivirus scanners often lock a freshly downloaded
+/// model for a moment, so the first open can fail transiently.
+const MODEL_LOAD_ATTEMPTS: u32 = 3;
+/// Wait before retry n is n times this.
+const MODEL_LOAD_BACKOFF: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Load a Whisper model on a blocking thread. "model-loading" (payload: model
+/// path) fires right away, then "model-loaded" (payload: model path) or
+/// "model-load-failed" (payload: error).
+pub(crate) fn spawn_model_load(app: tauri::AppHandle, model_path: PathBuf) {
+    // Set before the thread starts, so a transcription that comes right
+    // after this call already waits for the load
+    app.state::<Mutex<AppState>>().lock().unwrap().model_loading = true;
+    let _ = app.emit("model-loading", model_path.to_string_lossy().to_string());
+    tauri::async_runtime::spawn_blocking(move || {
+        let name = model_path.file_name().unwrap_or_default().to_string_lossy();
+        // What this model remembered, swapped in before anything is
+        // transcribed with it
+        let model_settings = app
+            .state::<Mutex<Settings>>()
+            .lock()
+            .unwrap()
+            .transcription_for(&name);
+        if let Ok(meta) = std::fs::metadata(&model_path) {
+            let advisory = transcription::models::memory_advisory(
+                &name,
+                meta.len(),
+                system::memory::available_memory_bytes(),
+            );
+            if advisory.fit == transcription::models::MemoryFit::Insufficient {
+                log::warn!("{}", advisory.message);
+                let _ = app.emit("model-memory-warning", &advisory);
+            }
+        }
+
+        let mut attempt = 1;
+        let (result, loaded) = loop {
+            let (result, loaded) = {
+                let engine = app.state::<Mutex<WhisperEngine>>();
+                let mut eng = engine.lock().unwrap();
+                let result = eng.load_model(&model_path);
+                if result.is_ok() {
+                    eng.set_settings(model_settings.clone());
+                }
+                (result, eng.is_loaded())
+            };
+            match result {
+                Err(e) if attempt < MODEL_LOAD_ATTEMPTS => {
+                    log::warn!(
+                        "Model load attempt {}/{} failed: {}",
+                        attempt,
+                        MODEL_LOAD_ATTEMPTS,
+                        e
+                    );
+                    // The engine lock is released while we back off
+                    std::thread::sleep(MODEL_LOAD_BACKOFF * attempt);
+                    attempt += 1;
+                }
+                Err(e) if attempt > 1 => {
+                    break (Err(format!("{} (gave up after {} attempts)", e, attempt)), loaded)
+                }
+                result => break (result, loaded),
+            }
+        };
+        {
+            let state = app.state::<Mutex<AppState>>();
+            let mut s = state.lock().unwrap();
+            s.model_loaded = loaded;
+            s.model_loading = false;
+            if result.is_ok() {
+                s.model_idle_unloaded = false;
+                s.model_last_used = std::time::Instant::now();
+            }
+            // A crash is shown like any other app error, unless it would
+            // cut into a dictation that is under way
+            if let Err(e) = &result {
+                if transcription::engine::is_model_crash(e) && s.status == AppStatus::Idle {
+                    s.status = AppStatus::Error(e.clone());
+                    let _ = app.emit("status-changed", "Error");
+                }
+            }
+        }
+
+        match result {
+            Ok(_) => {
+                log::info!("Model loaded from {:?}", model_path);
+                let _ = app.emit("model-loaded", model_path.to_string_lossy().to_string());
+                check_model_language(&app);
+            }
+            Err(e) => {
+                log::error!("Failed to load model: {}", e);
+                let _ = app.emit("model-load-failed", e);
+            }
+        }
+    });
+}
+
+/// How often the idle unloader checks the model's last use.
+const IDLE_UNLOAD_CHECK: std::time::Duration = std::time::Duration::from_secs(30);
+/// Longest a transcription waits for a model load before going ahead anyway.
+const MODEL_LOAD_WAIT: std::time::Duration = std::time::Duration::from_secs(120);
+
+/// Unload the model once it has gone idle_unload_minutes without being used,
+/// emitting "model-unloaded". It comes back through `reload_idle_model`.
+async fn idle_unload_loop(app: tauri::AppHandle) {
+    loop {
+        tokio::time::sleep(IDLE_UNLOAD_CHECK).await;
+        let minutes = app.state::<Mutex<Settings>>().lock().unwrap().idle_unload_minutes;
+        if minutes == 0 {
+            continue;
+        }
+        let state = app.state::<Mutex<AppState>>();
+        let mut s = state.lock().unwrap();
+        if s.status != AppStatus::Idle
+            || !s.model_loaded
+            || s.model_loading
+            || s.model_last_used.elapsed().as_secs() < minutes * 60
+        {
+            continue;
+        }
+        // The state lock is held throughout, so no recording can start
+        // between the check and the unload. A busy engine (a benchmark,
+        // a file transcription) just means another try next time.
+        let engine = app.state::<Mutex<WhisperEngine>>();
+        let Ok(mut eng) = engine.try_lock() else {
+            continue;
+        };
+        eng.unload();
+        drop(eng);
+        s.model_loaded = false;
+        s.model_idle_unloaded = true;
+        drop(s);
+        log::info!("Model unloaded after {} idle minutes", minutes);
+        let _ = app.emit("model-unloaded", minutes);
+    }
+}
+
+/// Start loading the configured model again if it was unloaded for being
+/// idle. Anything transcribed meanwhile waits for it.
+fn reload_idle_model(app: &tauri::AppHandle) {
+    let reload = std::mem::take(
+        &mut app.state::<Mutex<AppState>>().lock().unwrap().model_idle_unloaded,
+    );
+    if !reload {
+        return;
+    }
+    let model_filename = app.state::<Mutex<Settings>>().lock().unwrap().model_filename.clone();
+    log::info!("Reloading {} after idle unload", model_filename);
+    spawn_model_load(app.clone(), app.state::<AppConfig>().model_path(&model_filename));
+}
+
+/// Wait (up to MODEL_LOAD_WAIT) for a model load in progress to finish.
+async fn wait_for_model_load(app: &tauri::AppHandle) {
+    let started = std::time::Instant::now();
+    while app.state::<Mutex<AppState>>().lock().unwrap().model_loading {
+        if started.elapsed() >= MODEL_LOAD_WAIT {
+            log::warn!("Model still loading after {}s", MODEL_LOAD_WAIT.as_secs());
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+}
+
+/// A dictation only continues the previous one if it follows soon after;
+/// otherwise the user has likely moved on to another field.
+const CONTINUATION_WINDOW_SECS: u64 = 120;
+
+/// End of the previous injected text, if recent enough to continue from.
+fn continuation_tail(app: &tauri::AppHandle) -> Option<String> {
+    let state = app.state::<Mutex<AppState>>();
+    let s = state.lock().unwrap();
+    let (tail, at) = s.last_injected_tail.as_ref()?;
+    (at.elapsed().as_secs() < CONTINUATION_WINDOW_SECS).then(|| tail.clone())
+}
+
+/// End of the previous dictation when `mode` says the next one should be
+/// spaced from it. Where the focused window can't be determined, the same-field
+/// check falls back to timing alone.
+fn auto_space_tail(app: &tauri::AppHandle, mode: postprocess::AutoSpace) -> Option<String> {
+    let state = app.state::<Mutex<AppState>>();
+    let s = state.lock().unwrap();
+    let (tail, at) = s.last_injected_tail.as_ref()?;
+    match mode {
+        postprocess::AutoSpace::Off => None,
+        postprocess::AutoSpace::Always => Some(tail.clone()),
+        postprocess::AutoSpace::SameField => {
+            let recent = at.elapsed().as_secs() < CONTINUATION_WINDOW_SECS;
+            let same_window = match (s.last_injected_window, system::window::foreground_window()) {
+                (Some(last), Some(current)) => last == current,
+                _ => true,
+            };
+            (recent && same_window).then(|| tail.clone())
+        }
+    }
+}
+
+/// Emit "model-language-mismatch" when the configured language isn't English
+/// but the model is English-only, and return the warning.
+pub(crate) fn check_model_language(app: &tauri::AppHandle) -> Option<String> {
+    let (model, language) = {
+        let settings = app.state::<Mutex<Settings>>();
+        let s = settings.lock().unwrap();
+        (s.model_filename.clone(), s.active_transcription().language)
+    };
+    let multilingual = app
+        .state::<Mutex<WhisperEngine>>()
+        .lock()
+        .unwrap()
+        .is_multilingual()
+        .unwrap_or_else(|| !transcription::models::is_english_only(&model));
+    let warning =
+        transcription::models::language_mismatch(&model, multilingual, language.as_deref())?;
+    log::warn!("{}", warning);
+    let _ = app.emit("model-language-mismatch", &warning);
+    Some(warning)
+}
+
+/// Tell the user a recording produced no text, per the empty_feedback setting.
+/// "transcription-empty" is emitted either way for the window.
+fn report_empty_transcription(app: &tauri::AppHandle) {
+    let _ = app.emit("transcription-empty", ());
+    let feedback = app.state::<Mutex<Settings>>().lock().unwrap().empty_feedback;
+    if feedback.plays_sound() {
+        app.state::<SoundPlayer>().play_empty();
+    }
+    if feedback.notifies() {
+        use tauri_plugin_notification::NotificationExt;
+        if let Err(e) = app
+            .notification()
+            .builder()
+            .title("Wispr Local")
+            .body("No speech detected. Check the microphone or speak a little louder.")
+            .show()
+        {
+            log::warn!("Failed to show notification: {}", e);
+        }
+    }
+}
+
+/// Paste shortcut and injection method from the settings.
+pub(crate) fn injection_options(
+    app: &tauri::AppHandle,
+) -> system::text_injection::InjectionOptions {
+    let settings = app.state::<Mutex<Settings>>();
+    let s = settings.lock().unwrap();
+    system::text_injection::InjectionOptions {
+        paste: system::text_injection::PasteShortcut::from_setting(&s.paste_shortcut),
+        method: s.injection_method,
+        type_max_chars: s.type_max_chars,
+        clipboard_restore: s.clipboard_restore,
+    }
+}
+
+/// Tell the user their clipboard couldn't be restored after a paste, when
+/// clipboard_restore is RestoreOrWarn. Emits "clipboard-restore-failed".
+pub(crate) fn report_injection(app: &tauri::AppHandle, injected: &system::text_injection::Injected) {
+    if injected.restore_error.is_none() {
+        return;
+    }
+    let policy = app.state::<Mutex<Settings>>().lock().unwrap().clipboard_restore;
+    if policy == settings::ClipboardRestore::RestoreOrWarn {
+        let _ = app.emit(
+            "clipboard-restore-failed",
+            "Your clipboard couldn't be restored after pasting. It still holds the dictation.",
+        );
+    }
+}
+
+/// Paste a recent dictation again (0 = newest). A no-op while the history
+/// is empty; refused while a dictation is in progress. The status reads
+/// Injecting during the paste. Blocks while pasting, so call it off the
+/// async runtime.
+pub(crate) fn reinject_history(app: &tauri::AppHandle, index: usize) -> Result<(), String> {
+    let state = app.state::<Mutex<AppState>>();
+    let text = {
+        // Checked and claimed under one lock, so a hotkey press can't start
+        // a recording in between
+        let mut s = state.lock().map_err(|e| e.to_string())?;
+        if s.history.is_empty() {
+            log::info!("Transcription history is empty, nothing to re-inject");
+            return Ok(());
+        }
+        let text = match s.history.get(index) {
+            Some(entry) => entry.text.clone(),
+            None => {
+                return Err(format!(
+                    "No history entry {} (only {} kept)",
+                    index,
+                    s.history.len()
+                ))
+            }
+        };
+        if !s.claim_from_idle(AppStatus::Injecting) {
+            return Err("Busy — wait for the current dictation to finish".to_string());
+        }
+        text
+    };
+    let _ = app.emit("status-changed", "Injecting");
+
+    let options = injection_options(app);
+    let window = system::window::foreground_window();
+    let injected = system::text_injection::inject_text(&text, &options);
+
+    let mut s = state.lock().map_err(|e| e.to_string())?;
+    s.status = AppStatus::Idle;
+    if injected.is_ok() {
+        s.last_injected_window = window;
+        s.last_injected_chars = Some(text.chars().count());
+    }
+    drop(s);
+    let _ = app.emit("status-changed", "Idle");
+
+    let injected = injected.map_err(|e| e.user_message())?;
+    report_injection(app, &injected);
+    log::info!("Re-injected history entry {}", index);
+    Ok(())
+}
+
+/// Copy the last dictation to the clipboard for pasting by hand, a safe
+/// alternative to re-injecting when it's unclear where focus is.
+pub(crate) fn copy_last_transcription(app: &tauri::AppHandle) -> Result<(), String> {
+    let text = app.state::<Mutex<AppState>>().lock().unwrap().last_transcription.clone();
+    if text.is_empty() {
+        return Err("Nothing has been dictated yet".to_string());
+    }
+    system::text_injection::copy_to_clipboard(&text).map_err(|e| {
+        log::warn!("Copying the last dictation failed: {}", e);
+        e.user_message()
+    })?;
+    log::info!("Copied the last dictation ({} chars)", text.chars().count());
+    Ok(())
+}
+
+/// Switch AI formatting of dictations on or off, keeping the provider
+/// settings. Shared by the settings window and the tray.
+pub(crate) fn set_ai_enabled(app: &tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    {
+        let settings = app.state::<Mutex<Settings>>();
+        let mut s = settings.lock().map_err(|e| e.to_string())?;
+        s.ai_enabled = enabled;
+    }
+    app.state::<SaveScheduler>().schedule();
+    system::tray::sync_ai_enabled(app, enabled);
+    let _ = app.emit("ai-enabled-changed", enabled);
+    log::info!("AI formatting {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+/// Delete the last pasted text again (see text_injection::undo_injection).
+/// Only once per paste, and only with the undo_injection setting on.
+pub(crate) fn undo_last_injection(app: &tauri::AppHandle) -> Result<(), String> {
+    let enabled = app.state::<Mutex<Settings>>().lock().unwrap().undo_injection;
+    if !enabled {
+        return Err("Undo of the last paste is turned off in settings".to_string());
+    }
+    let (chars, window) = {
+        let state = app.state::<Mutex<AppState>>();
+        let mut s = state.lock().map_err(|e| e.to_string())?;
+        if s.status != AppStatus::Idle {
+            return Err("Busy — wait for the current dictation to finish".to_string());
+        }
+        let Some(chars) = s.last_injected_chars.take() else {
+            return Err("Nothing to undo".to_string());
+        };
+        // Whatever ends the field now, it isn't the undone text
+        s.last_injected_tail = None;
+        (chars, s.last_injected_window)
+    };
+    system::text_injection::undo_injection(chars, window).map_err(|e| e.user_message())?;
+    log::info!("Undid the last paste ({} characters)", chars);
+    Ok(())
+}
+
+/// Run Whisper on a blocking thread: decoding is CPU-bound and would otherwise
+/// stall the async workers that deliver events and run the streaming preview.
+/// `language` is forced for the decode (None = auto-detect per window).
+pub(crate) async fn transcribe_blocking(
+    app: &tauri::AppHandle,
+    samples: Vec<f32>,
+    language: Option<String>,
+) -> Result<String, String> {
+    wait_for_model_load(app).await;
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let engine = app.state::<Mutex<WhisperEngine>>();
+        let eng = engine.lock().map_err(|e| e.to_string())?;
+        let result = eng.transcribe_as(&samples, language.as_deref());
+        drop(eng);
+        app.state::<Mutex<AppState>>().lock().unwrap().model_last_used = std::time::Instant::now();
+        result
+    })
+    .await
+    .map_err(|e| format!("Transcription task failed: {}", e))?
+}
+
+/// Transcribe a long recording in chunks split at pauses (per chunk_seconds),
+/// emitting "transcription-progress" before each one. Short recordings, or
+/// chunking turned off, go to Whisper in one piece.
+async fn transcribe_in_chunks(
+    app: &tauri::AppHandle,
+    samples: Vec<f32>,
+    language: Option<String>,
+) -> Result<String, String> {
+    // Not counted toward the measured speed
+    wait_for_model_load(app).await;
+    let started = std::time::Instant::now();
+    let audio_secs = audio::samples_to_secs(samples.len());
+    let text = transcribe_chunks(app, samples, language).await?;
+    record_transcription_speed(app, audio_secs, started.elapsed());
+    Ok(text)
+}
+
+async fn transcribe_chunks(
+    app: &tauri::AppHandle,
+    samples: Vec<f32>,
+    language: Option<String>,
+) -> Result<String, String> {
+    let (chunk_seconds, threshold, separator) = {
+        let settings = app.state::<Mutex<Settings>>();
+        let s = settings.lock().unwrap();
+        (
+            s.chunk_seconds,
+            audio::level::speech_threshold(s.silence_threshold, s.noise_floor_rms),
+            s.transcription.segment_separator,
+        )
+    };
+    let max_len = chunk_seconds as usize * TARGET_SAMPLE_RATE as usize;
+    if max_len == 0 || samples.len() <= max_len {
+        return transcribe_blocking(app, samples, language).await;
+    }
+
+    let chunks = audio::level::split_at_pauses(&samples, threshold, max_len);
+    log::info!("Transcribing in {} chunks split at pauses", chunks.len());
+    let mut texts = Vec::with_capacity(chunks.len());
+    for (i, range) in chunks.iter().enumerate() {
+        let _ = app.emit(
+            "transcription-progress",
+            state::TranscriptionProgress {
+                chunk: i + 1,
+                total: chunks.len(),
+            },
+        );
+        let text = transcribe_blocking(app, samples[range.clone()].to_vec(), language.clone())
+            .await
+            .map_err(|e| format!("chunk {}/{}: {}", i + 1, chunks.len(), e))?;
+        texts.push(text);
+    }
+    Ok(transcription::engine::join_texts(
+        texts.iter().map(String::as_str),
+        separator,
+    ))
+}
+
+/// The language a whole recording is transcribed in (see
+/// WhisperEngine::recording_language), detected on a blocking thread.
+async fn recording_language(app: &tauri::AppHandle, samples: &[f32]) -> Option<String> {
+    wait_for_model_load(app).await;
+    let head = detection_head(samples).to_vec();
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let engine = app.state::<Mutex<WhisperEngine>>();
+        let eng = engine.lock().ok()?;
+        eng.recording_language(&head)
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// recording_language for the current recording session: settled by
+/// whichever of the streaming preview and the final transcription needs it
+/// first, and reused by the other.
+pub(crate) async fn session_language(app: &tauri::AppHandle, samples: &[f32]) -> Option<String> {
+    let (session, settled) = settled_session_language(app);
+    if let Some(language) = settled {
+        return language;
+    }
+    let language = recording_language(app, samples).await;
+    settle_session_language(app, session, language.clone());
+    language
+}
+
+/// The current recording session and its language, if already settled.
+fn settled_session_language(app: &tauri::AppHandle) -> (u64, Option<Option<String>>) {
+    let state = app.state::<Mutex<AppState>>();
+    let s = state.lock().unwrap();
+    let settled = match &s.recording_language {
+        Some((session, language)) if *session == s.recording_session => Some(language.clone()),
+        _ => None,
+    };
+    (s.recording_session, settled)
+}
+
+fn settle_session_language(app: &tauri::AppHandle, session: u64, language: Option<String>) {
+    app.state::<Mutex<AppState>>().lock().unwrap().recording_language = Some((session, language));
+}
+
+/// The start of a recording that auto_lock detects the language from.
+fn detection_head(samples: &[f32]) -> &[f32] {
+    let len = transcription::engine::LANGUAGE_DETECT_SECS * TARGET_SAMPLE_RATE as usize;
+    &samples[..samples.len().min(len)]
+}
+
+/// Shorter transcriptions don't update the measured speed: Whisper pads
+/// every window to 30s, so short clips overstate the real-time factor.
+pub(crate) const MIN_SPEED_SAMPLE_SECS: f32 = 5.0;
+
+/// Update the configured model's measured real-time factor, used by
+/// estimate_transcription_time.
+fn record_transcription_speed(app: &tauri::AppHandle, audio_secs: f32, elapsed: std::time::Duration) {
+    if audio_secs < MIN_SPEED_SAMPLE_SECS {
+        return;
+    }
+    let rtf = elapsed.as_secs_f32() / audio_secs;
+    {
+        let settings = app.state::<Mutex<Settings>>();
+        let mut s = settings.lock().unwrap();
+        let model = s.model_filename.clone();
+        let threads = s.active_transcription().threads;
+        s.record_model_speed(&model, rtf, threads);
+        log::debug!("{} ran at RTF {:.2} ({} threads)", model, rtf, threads);
+    }
+    app.state::<SaveScheduler>().schedule();
+}
+
+/// Decode an audio file and transcribe it like a long dictation, chunked at
+/// pauses. The raw text is returned; nothing is formatted or pasted.
+pub(crate) async fn transcribe_file(
+    app: &tauri::AppHandle,
+    path: &std::path::Path,
+) -> Result<String, String> {
+    {
+        let state = app.state::<Mutex<AppState>>();
+        let mut s = state.lock().unwrap();
+        if !s.model_loaded && !s.model_idle_unloaded {
+            return Err("No model loaded".to_string());
+        }
+        // Keeps the hotkey from starting a dictation that would queue
+        // behind this file on the engine lock
+        if !s.claim_from_idle(AppStatus::Transcribing) {
+            return Err("Busy — wait for the current dictation to finish".to_string());
+        }
+    }
+    let _ = app.emit("status-changed", "Transcribing");
+    reload_idle_model(app);
+
+    let result = decode_and_transcribe_file(app, path).await;
+    match &result {
+        Err(e) if transcription::engine::is_model_crash(e) => {
+            let state = app.state::<Mutex<AppState>>();
+            state.lock().unwrap().status = AppStatus::Error(e.clone());
+            let _ = app.emit("status-changed", "Error");
+            let _ = app.emit("app-error", e.clone());
+        }
+        _ => {
+            let state = app.state::<Mutex<AppState>>();
+            state.lock().unwrap().status = AppStatus::Idle;
+            let _ = app.emit("status-changed", "Idle");
+        }
+    }
+    result
+}
+
+async fn decode_and_transcribe_file(
+    app: &tauri::AppHandle,
+    path: &std::path::Path,
+) -> Result<String, String> {
+    let owned = path.to_path_buf();
+    let samples = tauri::async_runtime::spawn_blocking(move || {
+        audio::file::load_audio_file(&owned)
+    })
+    .await
+    .map_err(|e| format!("Decoding task failed: {}", e))??;
+    if samples.is_empty() {
+        return Err(format!("{} contains no audio", path.display()));
+    }
+    log::info!(
+        "Transcribing {} ({:.1}s)",
+        path.display(),
+        audio::samples_to_secs(samples.len())
+    );
+    // The file is its own recording: its language is settled once, apart
+    // from any dictation session
+    let language = recording_language(app, &samples).await;
+    transcribe_in_chunks(app, samples, language).await
+}
+
+/// Transcribe files dropped on the main window in order, reporting each one
+/// through "file-transcription". A file that fails doesn't stop the rest,
+/// unless Whisper crashed on it.
+async fn transcribe_dropped_files(app: tauri::AppHandle, paths: Vec<PathBuf>) {
+    let total = paths.len();
+    for (i, path) in paths.iter().enumerate() {
+        let report = |text: Option<String>, error: Option<String>| state::FileTranscription {
+            path: path.display().to_string(),
+            index: i + 1,
+            total,
+            text,
+            error,
+        };
+        let _ = app.emit("file-transcription", report(None, None));
+        let (event, crashed) = match transcribe_file(&app, path).await {
+            Ok(text) => (report(Some(text), None), false),
+            Err(e) => {
+                log::warn!("Failed to transcribe {}: {}", path.display(), e);
+                let crashed = transcription::engine::is_model_crash(&e);
+                (report(None, Some(e)), crashed)
+            }
+        };
+        let _ = app.emit("file-transcription", event);
+        // The rest would only be refused until the model is reloaded
+        if crashed {
+            break;
+        }
+    }
+}
+
+/// In hybrid mode, holding the hotkey at least this long means hold-to-talk;
+/// a shorter tap latches the recording on until the next press. In hold mode
+/// with tap_to_reinject, a shorter tap pastes the last dictation instead.
+const HYBRID_HOLD_MS: u128 = 400;
+
+/// Wait after a tap before re-injecting, so the paste keystroke doesn't mix
+/// with the hotkey's modifiers still coming up.
+const TAP_REINJECT_DELAY_MS: u64 = 150;
+
+/// The recording mode is read on every event, so switching it takes effect
+/// without re-registering the shortcut.
+fn on_hotkey_pressed(app: &tauri::AppHandle, shortcut_id: u32) {
+    let mode = app.state::<Mutex<Settings>>().lock().unwrap().recording_mode;
+    let state = app.state::<Mutex<AppState>>();
+    let mut s = state.lock().unwrap();
+    if s.hotkey_down {
+        // Key repeat while held
+        return;
+    }
+    s.hotkey_down = true;
+
+    let recording = s.status.in_recording();
+    if let Some((pressed_at, _)) = s.pending_release.take() {
+        if recording {
+            log::info!("Hotkey PRESSED again right after release - continuing recording");
+            s.hotkey_pressed_at = Some(pressed_at);
+            return;
+        }
+    }
+    let stop = recording && (mode == RecordingMode::Toggle || s.recording_latched);
+    if stop {
+        log::info!("Hotkey PRESSED - stopping latched recording");
+        s.hotkey_pressed_at = None;
+        drop(s);
+        let _ = app.emit("hotkey-stop-recording", ());
+    } else if !recording && !s.status.allows(&AppStatus::Recording) {
+        let status = s.status.clone();
+        drop(s);
+        reject_recording_start(app, &status);
+    } else if !recording {
+        log::info!("Hotkey PRESSED - starting recording ({:?} mode)", mode);
+        s.hotkey_pressed_at = Some(std::time::Instant::now());
+        s.recording_latched = mode == RecordingMode::Toggle;
+        drop(s);
+        // Payload identifies which dictation hotkey fired
+        let _ = app.emit("hotkey-start-recording", shortcut_id);
+    }
+}
+
+fn on_hotkey_released(app: &tauri::AppHandle) {
+    let (mode, tolerance_ms, tap_to_reinject) = {
+        let settings = app.state::<Mutex<Settings>>();
+        let s = settings.lock().unwrap();
+        (s.recording_mode, s.stutter_tolerance_ms, s.tap_to_reinject)
+    };
+    let state = app.state::<Mutex<AppState>>();
+    let mut s = state.lock().unwrap();
+    s.hotkey_down = false;
+
+    // Only the release that follows a start press matters
+    let Some(pressed_at) = s.hotkey_pressed_at.take() else {
+        return;
+    };
+    let tapped = pressed_at.elapsed().as_millis() < HYBRID_HOLD_MS;
+    if mode == RecordingMode::Hold && tap_to_reinject && tapped {
+        log::info!("Hotkey TAPPED - pasting the last dictation again");
+        let session = s.recording_session;
+        drop(s);
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            // Let the hotkey's modifiers come up before pasting
+            tokio::time::sleep(std::time::Duration::from_millis(TAP_REINJECT_DELAY_MS)).await;
+            discard_recording(&app, session);
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = reinject_history(&app, 0) {
+                    log::warn!("Re-inject on tap failed: {}", e);
+                    let _ = app.emit("app-error", e);
+                }
+            })
+            .await;
+        });
+        return;
+    }
+    let stop = match mode {
+        RecordingMode::Hold => true,
+        RecordingMode::Toggle => false,
+        RecordingMode::Hybrid => pressed_at.elapsed().as_millis() >= HYBRID_HOLD_MS,
+    };
+    if stop && tolerance_ms > 0 {
+        let released_at = std::time::Instant::now();
+        s.pending_release = Some((pressed_at, released_at));
+        let session = s.recording_session;
+        drop(s);
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(tolerance_ms)).await;
+            stop_after_release(&app, session, released_at);
+        });
+    } else if stop {
+        log::info!("Hotkey RELEASED - stopping recording");
+        drop(s);
+        let _ = app.emit("hotkey-stop-recording", ());
+    } else {
+        log::info!("Hotkey RELEASED - recording continues until the next press");
+        s.recording_latched = true;
+    }
+}
+
+/// A start request arrived while the previous dictation is still in flight.
+/// It is dropped rather than queued, and "recording-rejected" says why.
+pub(crate) fn reject_recording_start(app: &tauri::AppHandle, status: &AppStatus) {
+    log::info!("Not starting a recording while {:?}", status);
+    let _ = app.emit(
+        "recording-rejected",
+        "Busy — wait for the current dictation to finish",
+    );
+}
+
+/// Throw away recording `session` without transcribing it.
+fn discard_recording(app: &tauri::AppHandle, session: u64) {
+    {
+        let state = app.state::<Mutex<AppState>>();
+        let mut s = state.lock().unwrap();
+        if !s.status.in_recording() || s.recording_session != session {
+            return;
+        }
+        s.status = AppStatus::Idle;
+    }
+    app.state::<Mutex<AudioCapture>>().lock().unwrap().stop();
+    app.state::<AudioBuffer>().clear();
+    let _ = app.emit("status-changed", "Idle");
 }
 
-fn start_recording_flow(app: &tauri::AppHandle) {
+/// Stop for a release held back by stutter tolerance, unless the key was
+/// pressed again (or the recording ended some other way) in the meantime.
+fn stop_after_release(app: &tauri::AppHandle, session: u64, released_at: std::time::Instant) {
+    let state = app.state::<Mutex<AppState>>();
+    let mut s = state.lock().unwrap();
+    if s.pending_release.is_none_or(|(_, at)| at != released_at) {
+        return;
+    }
+    s.pending_release = None;
+    if !s.status.in_recording() || s.recording_session != session {
+        return;
+    }
+    log::info!("Hotkey RELEASED - stopping recording");
+    drop(s);
+    let _ = app.emit("hotkey-stop-recording", ());
+}
+
+/// Map a fired shortcut to its hotkey string and formatting choice.
+/// Unknown ids fall back to the main dictation hotkey.
+fn resolve_hotkey(app: &tauri::AppHandle, shortcut_id: Option<u32>) -> (String, formatting::FormatChoice) {
+    let settings = app.state::<Mutex<Settings>>();
+    let s = settings.lock().unwrap();
+    let binding = shortcut_id.and_then(|id| {
+        s.hotkey_bindings.iter().find(|b| {
+            commands::parse_hotkey(&b.hotkey).is_ok_and(|sc| sc.id() == id)
+        })
+    });
+    match binding {
+        Some(b) => (b.hotkey.clone(), b.format_choice()),
+        None => (s.hotkey.clone(), formatting::FormatChoice::Configured),
+    }
+}
+
+/// Formatting chosen when the current recording started.
+fn session_format(app: &tauri::AppHandle) -> formatting::FormatChoice {
+    app.state::<Mutex<AppState>>().lock().unwrap().recording_format.clone()
+}
+
+fn start_recording_flow(
+    app: &tauri::AppHandle,
+    hotkey: Option<String>,
+    format: formatting::FormatChoice,
+) {
     log::info!("start_recording_flow called");
     let state = app.state::<Mutex<AppState>>();
     let capture = app.state::<Mutex<AudioCapture>>();
     let buffer = app.state::<AudioBuffer>();
 
-    {
+    let session = {
         let mut s = state.lock().unwrap();
-        if s.status == AppStatus::Recording {
+        if s.status.in_recording() {
+            return;
+        }
+        if !s.status.allows(&AppStatus::Recording) {
+            let status = s.status.clone();
+            drop(s);
+            reject_recording_start(app, &status);
             return;
         }
         buffer.clear();
         s.status = AppStatus::Recording;
-    }
+        s.recording_session += 1;
+        s.pending_release = None;
+        if hotkey.is_none() {
+            // Not started by a press, so no hotkey press/release state applies
+            s.hotkey_pressed_at = None;
+            s.recording_latched = false;
+        }
+        s.recording_hotkey = hotkey;
+        s.recording_format = format;
+        s.recording_session
+    };
 
     let _ = app.emit("status-changed", "Recording");
     app.state::<SoundPlayer>().play_start();
+    // Loads while the user speaks; the transcription waits for it
+    reload_idle_model(app);
 
     let mut cap = capture.lock().unwrap();
     match cap.start() {
-        Ok(rate) => log::info!("Recording started at {} Hz", rate),
+        Ok(rate) => {
+            log::info!("Recording started at {} Hz", rate);
+            if let Some(name) = cap.missing_device() {
+                let _ = app.emit("input-device-missing", name);
+            }
+        }
         Err(e) => {
             log::error!("Failed to start recording: {}", e);
             state.lock().unwrap().status = AppStatus::Error(e);
@@ -201,158 +1206,416 @@ fn start_recording_flow(app: &tauri::AppHandle) {
         }
     }
 
+    spawn_recording_monitors(app, session, &cap);
+}
+
+/// Spawn the tasks that run alongside a recording session: the streaming
+/// preview, the length watchdog, and the clipping, silence and device
+/// monitors. Each ends on its own once `session` stops recording.
+pub(crate) fn spawn_recording_monitors(app: &tauri::AppHandle, session: u64, cap: &AudioCapture) {
     // Spawn streaming preview: transcribe every ~2s while recording
     let app_clone = app.clone();
     tauri::async_runtime::spawn(async move {
         streaming_preview_loop(app_clone).await;
     });
+
+    let app_clone = app.clone();
+    tauri::async_runtime::spawn(async move {
+        recording_watchdog(app_clone, session).await;
+    });
+
+    let clip_monitor = cap.clip_monitor();
+    let app_clone = app.clone();
+    tauri::async_runtime::spawn(async move {
+        clipping_monitor_loop(app_clone, session, clip_monitor).await;
+    });
+
+    let app_clone = app.clone();
+    tauri::async_runtime::spawn(async move {
+        silence_auto_stop_loop(app_clone, session).await;
+    });
+
+    let stream_error = cap.stream_error();
+    let app_clone = app.clone();
+    tauri::async_runtime::spawn(async move {
+        device_monitor_loop(app_clone, session, stream_error).await;
+    });
 }
 
-async fn streaming_preview_loop(app: tauri::AppHandle) {
+/// End the recording early when the input device goes away (e.g. a Bluetooth
+/// headset disconnects): cpal reports a stream error, or no audio arrives for
+/// a while. What was captured is still transcribed, and "device-disconnected"
+/// tells the user why the recording stopped.
+async fn device_monitor_loop(
+    app: tauri::AppHandle,
+    session: u64,
+    stream_error: audio::capture::StreamErrorFlag,
+) {
     use std::time::Duration;
 
-    // Max audio to transcribe in preview mode (10s at 16kHz) — keeps preview fast
-    const MAX_PREVIEW_SAMPLES: usize = 16000 * 10;
+    const TICK: Duration = Duration::from_millis(250);
+    // Capture callbacks arrive every ~10-100ms, so this is clearly a dead stream
+    const STALL: Duration = Duration::from_secs(2);
 
-    // Wait 1.5s before first preview (need enough audio)
-    for _ in 0..15 {
-        tokio::time::sleep(Duration::from_millis(100)).await;
+    let mut last_len = app.state::<AudioBuffer>().len();
+    let mut stalled = Duration::ZERO;
+
+    loop {
+        tokio::time::sleep(TICK).await;
+        let paused = {
+            let state = app.state::<Mutex<AppState>>();
+            let s = state.lock().unwrap();
+            if !s.status.in_recording() || s.recording_session != session {
+                return;
+            }
+            s.status == AppStatus::Paused
+        };
+
+        let len = app.state::<AudioBuffer>().len();
+        if paused || len != last_len {
+            last_len = len;
+            stalled = Duration::ZERO;
+        } else {
+            stalled += TICK;
+        }
+
+        let reason = match stream_error.get() {
+            Some(e) => format!("Microphone error ({})", e),
+            None if stalled >= STALL => "Microphone stopped sending audio".to_string(),
+            None => continue,
+        };
+        let message = format!("{}; transcribing what was recorded so far", reason);
+        log::warn!("{}", message);
+        let _ = app.emit("device-disconnected", &message);
+        let format = session_format(&app);
+        stop_and_transcribe_flow(&app, format).await;
+        return;
+    }
+}
+
+/// Stop and transcribe once the speaker has gone quiet for auto_stop_silence_ms.
+/// Silence only counts after some speech was heard, and never while the
+/// hotkey is being held (hold mode ends on release anyway).
+async fn silence_auto_stop_loop(app: tauri::AppHandle, session: u64) {
+    use std::time::Duration;
+
+    const TICK: Duration = Duration::from_millis(100);
+    const TICK_SAMPLES: usize = TARGET_SAMPLE_RATE as usize / 10;
+
+    let (silence_ms, threshold) = {
+        let settings = app.state::<Mutex<Settings>>();
+        let s = settings.lock().unwrap();
+        (
+            s.auto_stop_silence_ms,
+            audio::level::speech_threshold(s.silence_threshold, s.noise_floor_rms),
+        )
+    };
+    if silence_ms == 0 {
+        return;
+    }
+    let hangover = Duration::from_millis(silence_ms);
+    let mut heard_speech = false;
+    let mut silent = Duration::ZERO;
+
+    loop {
+        tokio::time::sleep(TICK).await;
+        {
+            let state = app.state::<Mutex<AppState>>();
+            let s = state.lock().unwrap();
+            if !s.status.in_recording() || s.recording_session != session {
+                return;
+            }
+            let held = s.recording_hotkey.is_some() && !s.recording_latched;
+            if s.status == AppStatus::Paused || held {
+                silent = Duration::ZERO;
+                continue;
+            }
+        }
+
+        let level = audio::level::rms(&app.state::<AudioBuffer>().tail(TICK_SAMPLES));
+        if level >= threshold {
+            heard_speech = true;
+            silent = Duration::ZERO;
+        } else if heard_speech {
+            silent += TICK;
+        }
+
+        if silent >= hangover {
+            log::info!("{}ms of silence, stopping automatically", silence_ms);
+            let format = session_format(&app);
+            stop_and_transcribe_flow(&app, format).await;
+            return;
+        }
+    }
+}
+
+/// Emit "clipping" while the gain stage is clamping too many samples,
+/// at most once every few seconds so the UI isn't flooded.
+async fn clipping_monitor_loop(
+    app: tauri::AppHandle,
+    session: u64,
+    monitor: audio::clipping::ClipMonitor,
+) {
+    use std::time::{Duration, Instant};
+
+    const WINDOW: Duration = Duration::from_millis(500);
+    const COOLDOWN: Duration = Duration::from_secs(5);
+
+    // Discard anything counted before this recording started
+    monitor.take_ratio();
+    let mut last_warning: Option<Instant> = None;
+
+    loop {
+        tokio::time::sleep(WINDOW).await;
+        {
+            let state = app.state::<Mutex<AppState>>();
+            let s = state.lock().unwrap();
+            if !s.status.in_recording() || s.recording_session != session {
+                return;
+            }
+        }
+
+        if let Some(warning) = monitor.check() {
+            if last_warning.is_none_or(|t| t.elapsed() >= COOLDOWN) {
+                log::warn!("Input clipping: {:.1}% of samples", warning.ratio * 100.0);
+                let _ = app.emit("clipping", &warning);
+                last_warning = Some(Instant::now());
+            }
+        }
+    }
+}
+
+/// Hand the finished dictation to the user's post_command in the background,
+/// so a slow script doesn't hold up pasting.
+fn spawn_post_command(app: &tauri::AppHandle, command: String, text: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        match system::post_command::run(&command, &text) {
+            Ok(output) if output.is_empty() => log::info!("Post-dictation command succeeded"),
+            Ok(output) => log::info!("Post-dictation command succeeded: {}", output),
+            Err(e) => {
+                log::error!("Post-dictation command failed: {}", e);
+                let _ = app.emit("app-error", format!("Post-dictation command failed: {}", e));
+            }
+        }
+    });
+}
+
+/// Auto-stop a recording that runs past max_recording_seconds, so a missed
+/// hotkey release can't leave the mic open forever.
+async fn recording_watchdog(app: tauri::AppHandle, session: u64) {
+    use std::time::Duration;
+
+    let (max_secs, warning_secs, warning_sound) = {
+        let settings = app.state::<Mutex<Settings>>();
+        let s = settings.lock().unwrap();
+        (s.max_recording_seconds, s.recording_warning_seconds, s.recording_warning_sound)
+    };
+    if max_secs == 0 {
+        return;
+    }
+    const TICK: Duration = Duration::from_millis(250);
+    let limit = Duration::from_secs(max_secs);
+    // Only time spent capturing counts toward the limit, not pauses
+    let mut recorded = Duration::ZERO;
+    // Seconds left at the last countdown event
+    let mut last_warned: Option<u64> = None;
+
+    loop {
+        tokio::time::sleep(TICK).await;
+        {
+            let state = app.state::<Mutex<AppState>>();
+            let s = state.lock().unwrap();
+            if !s.status.in_recording() || s.recording_session != session {
+                return;
+            }
+            if s.status == AppStatus::Recording {
+                recorded += TICK;
+            }
+        }
+        let remaining = (limit.saturating_sub(recorded).as_millis() as u64).div_ceil(1000);
+        if remaining > 0 && remaining <= warning_secs && last_warned != Some(remaining) {
+            if last_warned.is_none() {
+                log::info!("Recording stops automatically in {}s", remaining);
+                if warning_sound {
+                    app.state::<SoundPlayer>().play_warning();
+                }
+            }
+            last_warned = Some(remaining);
+            let _ = app.emit("recording-time-warning", remaining);
+            system::indicator::countdown(&app, remaining);
+        }
+        if recorded >= limit {
+            log::warn!("Recording hit the {}s limit, stopping automatically", max_secs);
+            let format = session_format(&app);
+            stop_and_transcribe_flow(&app, format).await;
+            return;
+        }
+    }
+}
+
+/// Stop a hotkey-held recording if the hotkey turns out to no longer be down
+/// (its release event was missed).
+fn resync_hotkey_state(app: &tauri::AppHandle) {
+    let hotkey = {
         let state = app.state::<Mutex<AppState>>();
-        let still_recording = state.lock().unwrap().status == AppStatus::Recording;
-        if !still_recording {
+        let s = state.lock().unwrap();
+        if s.status != AppStatus::Recording || s.recording_latched {
             return;
         }
+        match &s.recording_hotkey {
+            Some(hotkey) => hotkey.clone(),
+            None => return,
+        }
+    };
+
+    if system::keyboard::is_hotkey_held(&hotkey) == Some(false) {
+        log::warn!("Hotkey no longer held but still recording, stopping");
+        let _ = app.emit("hotkey-stop-recording", ());
+    }
+}
+
+/// Audio clean-up stages enabled in the settings, shared by the preview and
+/// the final transcription so both hear the same audio.
+fn preprocess_options(app: &tauri::AppHandle) -> audio::preprocess::Preprocess {
+    let settings = app.state::<Mutex<Settings>>();
+    let s = settings.lock().unwrap();
+    audio::preprocess::Preprocess {
+        noise_profile: if s.denoise { s.noise_profile.clone() } else { Vec::new() },
+        silence_threshold: s.silence_threshold,
+        fade_ms: s.fade_ms,
+        fade_in: s.fade_in,
+    }
+}
+
+async fn streaming_preview_loop(app: tauri::AppHandle) {
+    // Max audio to transcribe in preview mode (10s) — keeps preview fast
+    const MAX_PREVIEW_SAMPLES: usize = TARGET_SAMPLE_RATE as usize * 10;
+
+    // Wait 1.5s before first preview (need enough audio)
+    if !wait_while_recording(&app, 15).await {
+        return;
     }
 
     loop {
         let buffer = app.state::<AudioBuffer>();
         let full_samples = buffer.snapshot();
 
-        if full_samples.len() >= 16000 {
+        if full_samples.len() >= TARGET_SAMPLE_RATE as usize {
             // Only transcribe the last 10s for speed; show full context on final
             let samples = if full_samples.len() > MAX_PREVIEW_SAMPLES {
-                &full_samples[full_samples.len() - MAX_PREVIEW_SAMPLES..]
+                full_samples[full_samples.len() - MAX_PREVIEW_SAMPLES..].to_vec()
             } else {
-                &full_samples
+                full_samples
             };
 
             // Check if still recording right before locking the engine
-            {
-                let state = app.state::<Mutex<AppState>>();
-                if state.lock().unwrap().status != AppStatus::Recording {
-                    return;
-                }
+            let status = app.state::<Mutex<AppState>>().lock().unwrap().status.clone();
+            if !status.in_recording() {
+                return;
             }
 
-            // Try non-blocking lock — skip if final transcription holds it
-            let engine = app.state::<Mutex<WhisperEngine>>();
-            let lock_result = engine.try_lock();
-            if let Ok(eng) = lock_result {
-                let duration = samples.len() as f32 / 16000.0;
-                log::info!("Streaming preview: transcribing {:.1}s", duration);
-                match eng.transcribe(samples) {
-                    Ok(text) if !text.is_empty() => {
+            // While paused there's no new audio to preview, and a window of
+            // silence is skipped rather than sent to Whisper to hallucinate on
+            let threshold = {
+                let settings = app.state::<Mutex<Settings>>();
+                let s = settings.lock().unwrap();
+                audio::level::speech_threshold(s.silence_threshold, s.noise_floor_rms)
+            };
+            let speaking = audio::level::has_speech(&samples, threshold);
+            let samples = if status == AppStatus::Recording && speaking {
+                audio::preprocess::run(samples, &preprocess_options(&app))
+            } else {
+                Vec::new()
+            };
+            if !samples.is_empty() {
+                let duration = audio::samples_to_secs(samples.len());
+                let (session, settled) = settled_session_language(&app);
+                let app_clone = app.clone();
+                // Decode on a blocking thread. Try non-blocking lock — skip if
+                // final transcription holds it
+                let result = tauri::async_runtime::spawn_blocking(move || {
+                    let engine = app_clone.state::<Mutex<WhisperEngine>>();
+                    let eng = engine.try_lock().ok()?;
+                    // The first preview settles the language for the session;
+                    // the final transcription reuses it
+                    let (language, detected) = match settled {
+                        Some(language) => (language, false),
+                        None if eng.is_loaded() => {
+                            (eng.recording_language(detection_head(&samples)), true)
+                        }
+                        None => (None, false),
+                    };
+                    log::info!("Streaming preview: transcribing {:.1}s", duration);
+                    let text = eng.transcribe_as(&samples, language.as_deref());
+                    Some((text, detected.then_some(language)))
+                })
+                .await;
+                if let Ok(Some((_, Some(language)))) = &result {
+                    settle_session_language(&app, session, language.clone());
+                }
+                match result.map(|r| r.map(|(text, _)| text)) {
+                    Ok(Some(Ok(text))) if !text.is_empty() => {
+                        let text = {
+                            let settings = app.state::<Mutex<Settings>>();
+                            let s = settings.lock().unwrap();
+                            postprocess::redact_raw(&text, &s.postprocess)
+                        };
                         log::info!("Preview: {}", text);
                         let _ = app.emit("streaming-preview", &text);
                     }
+                    Ok(None) => log::info!("Streaming preview: engine locked, skipping"),
                     _ => {}
                 }
-            } else {
-                log::info!("Streaming preview: engine locked, skipping");
             }
         }
 
         // Wait 2s before next preview, checking every 100ms if still recording
-        for _ in 0..20 {
-            tokio::time::sleep(Duration::from_millis(100)).await;
-            let state = app.state::<Mutex<AppState>>();
-            let still_recording = state.lock().unwrap().status == AppStatus::Recording;
-            if !still_recording {
-                return;
-            }
+        if !wait_while_recording(&app, 20).await {
+            return;
         }
     }
 }
 
-/// Remove common filler words from transcription (Russian + English)
-fn remove_fillers(text: &str) -> String {
-    // Regex-free approach: split by words, filter fillers, rejoin
-    let fillers_ru = [
-        "ну", "эм", "э", "ээ", "эээ", "ам", "хм", "ммм", "мм",
-        "типа", "короче", "как бы", "это самое", "в общем", "так сказать",
-        "слушай", "значит", "ну вот",
-    ];
-    let fillers_en = [
-        "um", "uh", "uh", "uhh", "umm", "hmm", "er", "ah", "like",
-        "you know", "i mean", "so", "well", "basically",
-    ];
-
-    let mut result = text.to_string();
-
-    // Remove multi-word fillers first (longer patterns first)
-    for filler in fillers_ru.iter().chain(fillers_en.iter()) {
-        if filler.contains(' ') {
-            // Case-insensitive removal of multi-word fillers
-            let lower = result.to_lowercase();
-            let filler_lower = filler.to_lowercase();
-            while let Some(pos) = lower.find(&filler_lower) {
-                // Remove filler and any trailing comma/space
-                let end = pos + filler.len();
-                let end = if result[end..].starts_with(", ") {
-                    end + 2
-                } else if result[end..].starts_with(' ') {
-                    end + 1
-                } else {
-                    end
-                };
-                result = format!("{}{}", &result[..pos], &result[end..]);
-                break; // re-check from start since indices changed
-            }
+/// Sleep for `ticks` x 100ms of capture time; paused time doesn't count.
+/// Returns false as soon as the recording ends.
+async fn wait_while_recording(app: &tauri::AppHandle, ticks: u32) -> bool {
+    use std::time::Duration;
+
+    let mut waited = 0;
+    while waited < ticks {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        match app.state::<Mutex<AppState>>().lock().unwrap().status {
+            AppStatus::Recording => waited += 1,
+            AppStatus::Paused => {}
+            _ => return false,
         }
     }
-
-    // Remove single-word fillers
-    let words: Vec<&str> = result.split_whitespace().collect();
-    let cleaned: Vec<&str> = words
-        .into_iter()
-        .filter(|w| {
-            let lower = w.to_lowercase();
-            let stripped = lower.trim_matches(|c: char| c == ',' || c == '.' || c == '!' || c == '?');
-            !fillers_ru.contains(&stripped)
-                && !fillers_en.contains(&stripped)
-        })
-        .collect();
-
-    let result = cleaned.join(" ");
-    // Clean up double spaces and trim
-    result.trim().to_string()
+    true
 }
 
-async fn stop_and_transcribe_flow(app: &tauri::AppHandle) {
+async fn stop_and_transcribe_flow(app: &tauri::AppHandle, format: formatting::FormatChoice) {
     log::info!("stop_and_transcribe_flow called");
     let state = app.state::<Mutex<AppState>>();
     let capture = app.state::<Mutex<AudioCapture>>();
     let buffer = app.state::<AudioBuffer>();
-    let engine = app.state::<Mutex<WhisperEngine>>();
 
-    // Only stop if we're actually recording
+    // Only stop if we're actually recording (a paused recording is transcribed too).
+    // Leaving the recording state under the same lock makes this the only
+    // caller to stop it when the hotkey and an auto-stop race
     {
-        let s = state.lock().unwrap();
-        if s.status != AppStatus::Recording {
+        let mut s = state.lock().unwrap();
+        if !s.status.in_recording() {
             return;
         }
+        s.status = AppStatus::Transcribing;
     }
+    let _ = app.emit("status-changed", "Transcribing");
 
     // Stop capture
     {
         capture.lock().unwrap().stop();
     }
-    app.state::<SoundPlayer>().play_stop();
-
-    {
-        state.lock().unwrap().status = AppStatus::Transcribing;
-    }
-    let _ = app.emit("status-changed", "Transcribing");
 
     let samples = buffer.take_samples();
     if samples.is_empty() {
@@ -362,21 +1625,44 @@ async fn stop_and_transcribe_flow(app: &tauri::AppHandle) {
         return;
     }
 
+    // An accidental tap of the hotkey: skip transcription, silently
+    let min_ms = app.state::<Mutex<Settings>>().lock().unwrap().min_recording_ms;
+    let min_samples = (min_ms * TARGET_SAMPLE_RATE as u64 / 1000) as usize;
+    if samples.len() < min_samples {
+        log::info!(
+            "Recording too short ({:.2}s < {}ms), skipping transcription",
+            audio::samples_to_secs(samples.len()),
+            min_ms
+        );
+        state.lock().unwrap().status = AppStatus::Idle;
+        let _ = app.emit("status-changed", "Idle");
+        return;
+    }
+
+    app.state::<SoundPlayer>().play_stop();
+
+    let samples = audio::preprocess::run(samples, &preprocess_options(app));
+
     log::info!(
         "Transcribing {:.1}s of audio",
-        samples.len() as f32 / 16000.0
+        audio::samples_to_secs(samples.len())
     );
 
-    let text = {
-        let eng = engine.lock().unwrap();
-        match eng.transcribe(&samples) {
-            Ok(t) => t,
-            Err(e) => {
-                log::error!("Transcription failed: {}", e);
-                state.lock().unwrap().status = AppStatus::Idle;
-                let _ = app.emit("status-changed", "Idle");
-                return;
-            }
+    let language = session_language(app, &samples).await;
+    let text = match transcribe_in_chunks(app, samples, language.clone()).await {
+        Ok(t) => t,
+        Err(e) if transcription::engine::is_model_crash(&e) => {
+            log::error!("Transcription failed: {}", e);
+            state.lock().unwrap().status = AppStatus::Error(e.clone());
+            let _ = app.emit("status-changed", "Error");
+            let _ = app.emit("app-error", e);
+            return;
+        }
+        Err(e) => {
+            log::error!("Transcription failed: {}", e);
+            state.lock().unwrap().status = AppStatus::Idle;
+            let _ = app.emit("status-changed", "Idle");
+            return;
         }
     };
 
@@ -384,32 +1670,87 @@ async fn stop_and_transcribe_flow(app: &tauri::AppHandle) {
         log::warn!("No speech detected");
         state.lock().unwrap().status = AppStatus::Idle;
         let _ = app.emit("status-changed", "Idle");
+        report_empty_transcription(app);
         return;
     }
 
-    let text = remove_fillers(&text);
+    let raw_settings = app.state::<Mutex<Settings>>().lock().unwrap().postprocess.clone();
+    let filler_removal = raw_settings.filler_removal;
+    // Masked before the raw text is logged, formatted, shown or kept
+    let text = postprocess::redact_raw(&text, &raw_settings);
+    let text = if filler_removal == postprocess::FillerRemoval::BeforeFormatting {
+        // By line, so segment_separator's line breaks survive
+        postprocess::remove_fillers_by_line(&text).trim().to_string()
+    } else {
+        text
+    };
     log::info!("Transcription (cleaned): {}", text);
 
     if text.is_empty() {
         log::warn!("No speech after filler removal");
         state.lock().unwrap().status = AppStatus::Idle;
         let _ = app.emit("status-changed", "Idle");
+        report_empty_transcription(app);
         return;
     }
 
     // AI formatting step
-    let ai_settings = {
+    let (ai_chain, postprocess_settings, markdown) = {
         let settings = app.state::<Mutex<Settings>>();
         let guard = settings.lock().unwrap();
-        guard.ai.clone()
+        let markdown = match &format {
+            formatting::FormatChoice::Preset(name) => {
+                guard.find_preset(name).is_some_and(|p| p.markdown)
+            }
+            _ => false,
+        };
+        (guard.ai_chain_for(&format), guard.postprocess.clone(), markdown)
     };
 
-    let text = if ai_settings.provider != formatting::AiProvider::None {
+    let raw_text = text;
+    let text = if !ai_chain.is_empty() {
         {
             state.lock().unwrap().status = AppStatus::Formatting;
         }
         let _ = app.emit("status-changed", "Formatting");
-        formatting::format_text(&text, &ai_settings).await
+        let outcome = formatting::format_text_detailed(&raw_text, &ai_chain).await;
+        if let Some(reason) = &outcome.rejected {
+            log::warn!("AI output rejected ({}), keeping the raw transcription", reason);
+            let _ = app.emit(
+                "formatting-rejected",
+                format!("AI formatting was discarded ({}), using the raw text", reason),
+            );
+        }
+        let formatted = outcome.text;
+        if markdown {
+            formatting::validate_markdown(&formatted)
+        } else {
+            formatted
+        }
+    } else {
+        raw_text.clone()
+    };
+    let text = if filler_removal == postprocess::FillerRemoval::AfterFormatting {
+        postprocess::remove_fillers_by_line(&text)
+    } else {
+        text
+    };
+    let text = postprocess::process(&text, &postprocess_settings, language.as_deref());
+    let text = if postprocess_settings.continue_sentences {
+        match continuation_tail(app) {
+            Some(tail) => postprocess::continue_sentence(&text, &tail),
+            None => text,
+        }
+    } else {
+        text
+    };
+    let text = postprocess::template::apply_affixes(
+        &text,
+        &postprocess_settings.prefix_text,
+        &postprocess_settings.suffix_text,
+    );
+    let text = if postprocess_settings.include_timestamp {
+        postprocess::template::prepend_timestamp(&text, &postprocess_settings.timestamp_format)
     } else {
         text
     };
@@ -419,16 +1760,126 @@ async fn stop_and_transcribe_flow(app: &tauri::AppHandle) {
     }
     let _ = app.emit("status-changed", "Injecting");
 
-    match system::text_injection::inject_text(&text) {
-        Ok(_) => log::info!("Text injected successfully"),
-        Err(e) => log::error!("Text injection failed: {}", e),
+    let (output_mode, output_file, post_command, target_window, restore_focus, auto_advance) = {
+        let settings = app.state::<Mutex<Settings>>();
+        let s = settings.lock().unwrap();
+        (
+            s.output_mode,
+            s.output_file.clone(),
+            s.post_command.clone(),
+            s.target_window.trim().to_string(),
+            s.restore_focus,
+            s.auto_advance,
+        )
+    };
+
+    if output_mode.writes_file() {
+        match system::file_output::append_dictation(&output_file, &text) {
+            Ok(path) => log::info!("Dictation appended to {:?}", path),
+            Err(e) => {
+                log::error!("Appending dictation failed: {}", e);
+                let _ = app.emit("app-error", e);
+            }
+        }
+    }
+
+    if !post_command.is_empty() {
+        spawn_post_command(app, post_command, text.clone());
+    }
+
+    if output_mode.injects() {
+        // Decided before pasting, while the target field still has focus.
+        // The space only goes into the paste; history keeps the clean text
+        let pasted = match auto_space_tail(app, postprocess_settings.auto_space) {
+            Some(tail) if postprocess::needs_leading_space(&text, &tail) => format!(" {}", text),
+            _ => text.clone(),
+        };
+        // Clipboard checks, restore delays and focus waits sleep, so the
+        // paste runs off the async runtime
+        let handle = app.clone();
+        let paste = tauri::async_runtime::spawn_blocking(move || {
+            paste_dictation(&handle, &pasted, &target_window, restore_focus, auto_advance)
+        })
+        .await;
+        if let Err(e) = paste {
+            log::error!("Paste task failed: {}", e);
+        }
     }
 
     {
         let mut s = state.lock().unwrap();
         s.last_transcription = text.clone();
+        s.last_raw_transcription = raw_text.clone();
+        s.push_history(text.clone(), raw_text.clone());
         s.status = AppStatus::Idle;
     }
+    system::tray::sync_recent(app);
     let _ = app.emit("status-changed", "Idle");
-    let _ = app.emit("transcription-complete", text);
+    let _ = app.emit(
+        "transcription-complete",
+        TranscriptionPair {
+            raw: raw_text,
+            formatted: text,
+        },
+    );
+}
+
+/// Paste a finished dictation into the focused app or `target_window`,
+/// then send the auto-advance key. Blocks while pasting, so call it off
+/// the async runtime.
+fn paste_dictation(
+    app: &tauri::AppHandle,
+    text: &str,
+    target_window: &str,
+    restore_focus: bool,
+    auto_advance: settings::AutoAdvance,
+) {
+    let state = app.state::<Mutex<AppState>>();
+    let window = if target_window.is_empty() {
+        system::window::foreground_window()
+    } else {
+        system::window::find_window(target_window).ok().flatten()
+    };
+    let options = injection_options(app);
+    let injected = if target_window.is_empty() {
+        system::text_injection::inject_text(text, &options)
+    } else {
+        system::text_injection::inject_text_into_window(text, target_window, restore_focus, &options)
+    };
+
+    match injected {
+        Ok(injected) => {
+            log::info!("Text injected successfully");
+            report_injection(app, &injected);
+            // A few characters are enough to see how the text ended
+            let start = text.char_indices().rev().nth(15).map_or(0, |(i, _)| i);
+            let tail = text[start..].to_string();
+            let mut s = state.lock().unwrap();
+            s.last_injected_tail = Some((tail, std::time::Instant::now()));
+            s.last_injected_window = window;
+            s.last_injected_chars = Some(text.chars().count());
+            drop(s);
+            // With focus handed back elsewhere, the key would land in the wrong window
+            let refocused = !target_window.is_empty() && restore_focus;
+            if auto_advance != settings::AutoAdvance::Off && !refocused {
+                match system::text_injection::send_advance_key(auto_advance) {
+                    // Backspacing would now hit the next field
+                    Ok(()) if auto_advance == settings::AutoAdvance::Tab => {
+                        state.lock().unwrap().last_injected_chars = None;
+                    }
+                    Ok(()) => {
+                        let mut s = state.lock().unwrap();
+                        if let Some(chars) = s.last_injected_chars.as_mut() {
+                            *chars += 1;
+                        }
+                    }
+                    Err(e) => log::warn!("Auto-advance failed: {}", e),
+                }
+            }
+        }
+        Err(e) => {
+            log::error!("Text injection failed: {}", e);
+            let _ = app.emit("app-error", e.user_message());
+        }
+    }
 }
@@ -0,0 +1,42 @@
+use log::LevelFilter;
+use std::str::FromStr;
+
+/// Env var that pins the log filter; when set it wins over the saved setting.
+const RUST_LOG_ENV: &str = "RUST_LOG";
+
+/// Install the logger. env_logger lets everything from this crate through
+/// and the global max level does the filtering, so the level can change at
+/// runtime. Dependencies (tao, wry, reqwest, ...) stay at warnings unless
+/// RUST_LOG asks for more.
+pub fn init() {
+    env_logger::Builder::new()
+        .filter_level(LevelFilter::Warn)
+        .filter_module(env!("CARGO_CRATE_NAME"), LevelFilter::Trace)
+        .parse_default_env()
+        .init();
+    log::set_max_level(LevelFilter::Info);
+}
+
+/// Parse "off", "error", "warn", "info", "debug" or "trace".
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(level.trim()).map_err(|_| format!("Unknown log level: {}", level))
+}
+
+/// Apply the saved level at startup unless RUST_LOG is set.
+pub fn apply_saved_level(level: &str) {
+    if std::env::var_os(RUST_LOG_ENV).is_some() {
+        log::info!("{} is set, ignoring saved log level", RUST_LOG_ENV);
+        log::set_max_level(LevelFilter::Trace);
+        return;
+    }
+    match parse_level(level) {
+        Ok(filter) => log::set_max_level(filter),
+        Err(e) => log::warn!("{}, using info", e),
+    }
+}
+
+/// Change the active level immediately.
+pub fn set_level(filter: LevelFilter) {
+    log::set_max_level(filter);
+    log::info!("Log level set to {}", filter);
+}
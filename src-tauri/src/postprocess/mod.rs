@@ -0,0 +1,390 @@
+use serde::{Deserialize, Serialize};
+
+pub mod numbers;
+pub mod redact;
+pub mod rules;
+pub mod template;
+
+/// Which non-text characters to drop from the final transcription.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SymbolFilter {
+    #[default]
+    #[serde(rename = "keep")]
+    Keep,
+    /// Drop emoji and pictographs only
+    #[serde(rename = "emoji")]
+    StripEmoji,
+    /// Drop emoji plus pictographic symbols (™, ★, ♪, box drawing, ...)
+    #[serde(rename = "symbols")]
+    StripSymbols,
+}
+
+/// When to put a space between a dictation and the previous one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoSpace {
+    #[default]
+    Off,
+    /// Only when the dictation follows soon after the previous one and lands
+    /// in the same window
+    SameField,
+    /// After every injected dictation, wherever it goes
+    Always,
+}
+
+/// When the built-in filler-word removal runs relative to AI formatting.
+/// Without a formatting provider the first two are the same.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FillerRemoval {
+    /// Strip fillers from the raw transcription. Best with no formatting or
+    /// the Rules provider, and cheaper for AI providers (shorter input).
+    #[default]
+    BeforeFormatting,
+    /// Strip whatever fillers survive AI formatting. Recommended for AI
+    /// providers: the model sees the full sentence, so removal leaves fewer
+    /// awkward gaps.
+    AfterFormatting,
+    /// Keep fillers, or leave them to the AI prompt.
+    Off,
+}
+
+/// Text clean-up steps applied to the final text before injection.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PostProcessSettings {
+    #[serde(default)]
+    pub symbol_filter: SymbolFilter,
+    #[serde(default)]
+    pub filler_removal: FillerRemoval,
+    /// Convert spoken English numbers to digits ("twenty five" -> "25").
+    /// Skipped for dictations in another language.
+    #[serde(default)]
+    pub spoken_numbers: bool,
+    /// Lowercase the first letter of a dictation that continues an unfinished
+    /// sentence from the previous one.
+    #[serde(default)]
+    pub continue_sentences: bool,
+    /// Inserted before every dictation; supports {date}, {time} and {weekday}.
+    #[serde(default)]
+    pub prefix_text: String,
+    /// Inserted after every dictation; same placeholders as `prefix_text`.
+    #[serde(default)]
+    pub suffix_text: String,
+    /// Start every dictation with the local time, e.g. "[14:32] ".
+    #[serde(default)]
+    pub include_timestamp: bool,
+    /// chrono strftime format for the timestamp (empty = "[%H:%M] ").
+    #[serde(default)]
+    pub timestamp_format: String,
+    /// Separate consecutive dictations with a space so they don't run together.
+    #[serde(default)]
+    pub auto_space: AutoSpace,
+    /// Mask long digit runs and `redact_words` before the text is pasted.
+    /// The raw transcription is masked as soon as it is decoded, so the
+    /// preview, history, logs and AI formatting never see it unmasked.
+    #[serde(default)]
+    pub redact: bool,
+    /// Words or phrases to mask, matched whole-word and case-insensitively.
+    #[serde(default)]
+    pub redact_words: Vec<String>,
+    /// Replacement for redacted text (empty = "[redacted]").
+    #[serde(default)]
+    pub redact_mask: String,
+}
+
+/// Run the enabled post-processing steps over the final text, dictated in
+/// `language` (None = not known).
+pub fn process(text: &str, settings: &PostProcessSettings, language: Option<&str>) -> String {
+    let mut text = text.to_string();
+    if settings.spoken_numbers {
+        text = numbers::spoken_numbers_to_digits(&text, language);
+    }
+    if settings.symbol_filter != SymbolFilter::Keep {
+        text = strip_symbols(&text, settings.symbol_filter);
+    }
+    // Last, so digits produced by spoken_numbers are caught too
+    if settings.redact {
+        text = redact::redact(&text, &settings.redact_words, &settings.redact_mask);
+    }
+    text
+}
+
+/// Mask the raw transcription when redaction is on. process() masks again
+/// at the end, catching digits that spoken_numbers only produces there.
+pub fn redact_raw(text: &str, settings: &PostProcessSettings) -> String {
+    if !settings.redact {
+        return text.to_string();
+    }
+    redact::redact(text, &settings.redact_words, &settings.redact_mask)
+}
+
+/// Lowercase the first letter of `text` when `previous` (the end of the last
+/// dictation) doesn't finish a sentence. "I" and acronyms are left alone.
+pub fn continue_sentence(text: &str, previous: &str) -> String {
+    let ends_sentence = match previous.trim_end().chars().last() {
+        None => true,
+        Some(c) => matches!(c, '.' | '!' | '?' | '…' | ':') || previous.ends_with('\n'),
+    };
+    if ends_sentence {
+        return text.to_string();
+    }
+
+    let lead = text.len() - text.trim_start().len();
+    let first_word = text[lead..]
+        .split(|c: char| c.is_whitespace() || c == '\'' || c == '’')
+        .next()
+        .unwrap_or("");
+    let is_i = first_word == "I";
+    let is_acronym = first_word.chars().filter(|c| c.is_alphabetic()).count() > 1
+        && !first_word.chars().any(|c| c.is_lowercase());
+    if is_i || is_acronym {
+        return text.to_string();
+    }
+
+    let mut chars = text[lead..].chars();
+    match chars.next() {
+        Some(first) if first.is_uppercase() => {
+            format!("{}{}{}", &text[..lead], first.to_lowercase(), chars.as_str())
+        }
+        _ => text.to_string(),
+    }
+}
+
+/// Whether `text` needs a leading space to follow `previous` (the end of the
+/// last dictation): not when either side already has whitespace, or when
+/// `text` starts with punctuation that attaches to the preceding word.
+pub fn needs_leading_space(text: &str, previous: &str) -> bool {
+    let Some(last) = previous.chars().last() else {
+        return false;
+    };
+    match text.chars().next() {
+        None => false,
+        Some(first) => {
+            !last.is_whitespace()
+                && !first.is_whitespace()
+                && !matches!(first, '.' | ',' | '!' | '?' | ';' | ':' | '…' | ')' | ']' | '}')
+        }
+    }
+}
+
+/// Remove common filler words from transcription (Russian + English)
+pub fn remove_fillers(text: &str) -> String {
+    // Regex-free approach: split by words, filter fillers, rejoin
+    let fillers_ru = [
+        "ну", "эм", "э", "ээ", "эээ", "ам", "хм", "ммм", "мм",
+        "типа", "короче", "как бы", "это самое", "в общем", "так сказать",
+        "слушай", "значит", "ну вот",
+    ];
+    let fillers_en = [
+        "um", "uh", "uh", "uhh", "umm", "hmm", "er", "ah", "like",
+        "you know", "i mean", "so", "well", "basically",
+    ];
+
+    let mut result = text.to_string();
+
+    // Remove multi-word fillers first (longer patterns first)
+    for filler in fillers_ru.iter().chain(fillers_en.iter()) {
+        if filler.contains(' ') {
+            result = remove_phrase(&result, filler);
+        }
+    }
+
+    // Remove single-word fillers
+    let words: Vec<&str> = result.split_whitespace().collect();
+    let cleaned: Vec<&str> = words
+        .into_iter()
+        .filter(|w| {
+            let lower = w.to_lowercase();
+            let stripped = lower.trim_matches(|c: char| c == ',' || c == '.' || c == '!' || c == '?');
+            !fillers_ru.contains(&stripped)
+                && !fillers_en.contains(&stripped)
+        })
+        .collect();
+
+    let result = cleaned.join(" ");
+    // Clean up double spaces and trim
+    result.trim().to_string()
+}
+
+/// `remove_fillers` for AI-formatted text: each line is cleaned on its own,
+/// so line breaks and Markdown indentation survive.
+pub fn remove_fillers_by_line(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            let cleaned = remove_fillers(line);
+            if cleaned.is_empty() {
+                cleaned
+            } else {
+                format!("{}{}", indent, cleaned)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Remove every whole-word, case-insensitive occurrence of `phrase` along with
+/// a trailing ", " or " ".
+fn remove_phrase(text: &str, phrase: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = phrase.chars().collect();
+
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let Some(end) = match_phrase_at(&chars, i, &pattern) else {
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        };
+
+        i = match (chars.get(end), chars.get(end + 1)) {
+            (Some(','), Some(' ')) => end + 2,
+            (Some(' '), _) => end + 1,
+            _ => end,
+        };
+    }
+    out
+}
+
+/// If `pattern` occurs at `chars[i..]` as whole words, ignoring case, return
+/// the index just past it. "ass" doesn't match inside "class". Works on chars
+/// rather than byte offsets, since lowercasing can change a string's byte
+/// length (and Cyrillic is multi-byte).
+pub(crate) fn match_phrase_at(chars: &[char], i: usize, pattern: &[char]) -> Option<usize> {
+    let end = i + pattern.len();
+    let is_match = !pattern.is_empty()
+        && (i == 0 || !chars[i - 1].is_alphanumeric())
+        && end <= chars.len()
+        && chars[i..end]
+            .iter()
+            .zip(pattern)
+            .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+        && chars.get(end).is_none_or(|c| !c.is_alphanumeric());
+    is_match.then_some(end)
+}
+
+/// Remove emoji (and optionally other symbols) and tidy the spaces they leave.
+pub fn strip_symbols(text: &str, filter: SymbolFilter) -> String {
+    let drop = |c: char| match filter {
+        SymbolFilter::Keep => false,
+        SymbolFilter::StripEmoji => is_emoji(c),
+        SymbolFilter::StripSymbols => is_emoji(c) || is_symbol(c),
+    };
+
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars().filter(|&c| !drop(c)) {
+        // Collapse the double spaces left where a symbol was removed
+        if c == ' ' && (out.is_empty() || out.ends_with(' ') || out.ends_with('\n')) {
+            continue;
+        }
+        out.push(c);
+    }
+    out.trim().to_string()
+}
+
+/// Emoji, pictographs and the invisible characters used to compose them.
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF   // mahjong .. symbols & pictographs ext-A (incl. flags, skin tones)
+        | 0x2600..=0x27BF   // misc symbols, dingbats
+        | 0x2B00..=0x2BFF   // misc symbols and arrows (⭐, ⬆)
+        | 0xFE00..=0xFE0F   // variation selectors
+        | 0x200D            // zero-width joiner
+        | 0x20E3            // combining keycap
+        | 0xE0020..=0xE007F // tag characters
+    )
+}
+
+/// Pictographic and modifier symbols (Unicode So and Sk) outside the emoji
+/// ranges. Currency and math signs, combining marks, °, ℃ and № are text.
+fn is_symbol(c: char) -> bool {
+    matches!(c as u32,
+        0xA6 | 0xA8 | 0xA9 | 0xAE | 0xAF | 0xB4 | 0xB8 // ¦ ¨ © ® ¯ ´ ¸
+        | 0x02C2..=0x02C5 | 0x02D2..=0x02DF   // modifier letter symbols
+        | 0x02E5..=0x02EB | 0x02ED | 0x02EF..=0x02FF
+        | 0x2100..=0x2102 | 0x2104..=0x2108   // letterlike symbols (™, ℗) ..
+        | 0x210A..=0x2115 | 0x2117..=0x214F   // .. except ℃, ℉ and №
+        | 0x2300..=0x23FF   // misc technical (⌘, ⏰)
+        | 0x2400..=0x24FF   // control pictures, enclosed alphanumerics
+        | 0x2500..=0x25FF   // box drawing, blocks, geometric shapes
+        | 0x2800..=0x28FF   // braille
+        | 0x3200..=0x33FF   // enclosed CJK letters, CJK compatibility
+        | 0x1D000..=0x1D24F // musical symbols
+    ) && !c.is_alphanumeric()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_emoji_from_mixed_text() {
+        assert_eq!(
+            strip_symbols("Ship it 🚀 today 👍🏽", SymbolFilter::StripEmoji),
+            "Ship it today"
+        );
+        assert_eq!(
+            strip_symbols("Family 👨‍👩‍👧 dinner ❤️ at 7", SymbolFilter::StripEmoji),
+            "Family dinner at 7"
+        );
+    }
+
+    #[test]
+    fn strip_emoji_keeps_other_symbols_and_text() {
+        assert_eq!(
+            strip_symbols("Brand™ «quote» — Привет ⭐", SymbolFilter::StripEmoji),
+            "Brand™ «quote» — Привет"
+        );
+    }
+
+    #[test]
+    fn strip_symbols_drops_symbols_but_keeps_typography() {
+        assert_eq!(
+            strip_symbols("Brand™ ♪ «quote» — done…", SymbolFilter::StripSymbols),
+            "Brand «quote» — done…"
+        );
+    }
+
+    #[test]
+    fn strip_symbols_keeps_prices_temperatures_and_accents() {
+        let filter = SymbolFilter::StripSymbols;
+        assert_eq!(strip_symbols("€5, £3 or ¥200 ₽", filter), "€5, £3 or ¥200 ₽");
+        assert_eq!(strip_symbols("20°C ±2, § 4 × 3 ÷ 2", filter), "20°C ±2, § 4 × 3 ÷ 2");
+        // "é" written as e plus a combining acute accent
+        assert_eq!(strip_symbols("cafe\u{301} №5", filter), "cafe\u{301} №5");
+    }
+
+    #[test]
+    fn keep_leaves_text_unchanged() {
+        assert_eq!(strip_symbols("Hi 👋", SymbolFilter::Keep), "Hi 👋");
+    }
+
+    #[test]
+    fn redact_raw_only_masks_when_enabled() {
+        let mut settings = PostProcessSettings {
+            redact_words: vec!["Project Falcon".to_string()],
+            ..Default::default()
+        };
+        let raw = "Project Falcon card 4111 1111 1111 1111";
+        assert_eq!(redact_raw(raw, &settings), raw);
+        settings.redact = true;
+        assert_eq!(redact_raw(raw, &settings), "[redacted] card [redacted]");
+    }
+
+    #[test]
+    fn remove_fillers_drops_single_and_multi_word_fillers() {
+        assert_eq!(remove_fillers("Um, I think, you know, it works"), "I think, it works");
+        assert_eq!(remove_fillers("Ну, это работает"), "это работает");
+    }
+
+    #[test]
+    fn remove_phrase_handles_cyrillic_next_to_cyrillic_words() {
+        assert_eq!(remove_phrase("Мы, как бы, начали", "как бы"), "Мы, начали");
+        assert_eq!(remove_phrase("В общем всё готово", "в общем"), "всё готово");
+        assert_eq!(remove_phrase("ЭТО САМОЕ, доклад", "это самое"), "доклад");
+        // Only whole words: "общемто" is not "общем"
+        assert_eq!(remove_phrase("В общемто да", "в общем"), "В общемто да");
+        assert_eq!(remove_phrase("Такак бы нет", "как бы"), "Такак бы нет");
+    }
+}
@@ -0,0 +1,442 @@
+//! Spoken English numbers to digits: "twenty twenty-three" -> "2023",
+//! "five dollars and fifty cents" -> "$5.50", "twenty first" -> "21st".
+//!
+//! Deliberately conservative: a lone small number ("one of a kind",
+//! "first") is left as a word, like most style guides do.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Unit,
+    Teen,
+    Tens,
+    Hundred,
+    Scale,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct NumWord {
+    value: u64,
+    kind: Kind,
+    ordinal: bool,
+}
+
+fn classify(word: &str) -> Option<NumWord> {
+    use Kind::*;
+    let (value, kind, ordinal) = match word {
+        "zero" => (0, Unit, false),
+        "one" => (1, Unit, false),
+        "two" => (2, Unit, false),
+        "three" => (3, Unit, false),
+        "four" => (4, Unit, false),
+        "five" => (5, Unit, false),
+        "six" => (6, Unit, false),
+        "seven" => (7, Unit, false),
+        "eight" => (8, Unit, false),
+        "nine" => (9, Unit, false),
+        // "second" is left out: "a twenty second delay" is far more common than "22nd"
+        "first" => (1, Unit, true),
+        "third" => (3, Unit, true),
+        "fourth" => (4, Unit, true),
+        "fifth" => (5, Unit, true),
+        "sixth" => (6, Unit, true),
+        "seventh" => (7, Unit, true),
+        "eighth" => (8, Unit, true),
+        "ninth" => (9, Unit, true),
+        "ten" => (10, Teen, false),
+        "eleven" => (11, Teen, false),
+        "twelve" => (12, Teen, false),
+        "thirteen" => (13, Teen, false),
+        "fourteen" => (14, Teen, false),
+        "fifteen" => (15, Teen, false),
+        "sixteen" => (16, Teen, false),
+        "seventeen" => (17, Teen, false),
+        "eighteen" => (18, Teen, false),
+        "nineteen" => (19, Teen, false),
+        "tenth" => (10, Teen, true),
+        "eleventh" => (11, Teen, true),
+        "twelfth" => (12, Teen, true),
+        "thirteenth" => (13, Teen, true),
+        "fourteenth" => (14, Teen, true),
+        "fifteenth" => (15, Teen, true),
+        "sixteenth" => (16, Teen, true),
+        "seventeenth" => (17, Teen, true),
+        "eighteenth" => (18, Teen, true),
+        "nineteenth" => (19, Teen, true),
+        "twenty" => (20, Tens, false),
+        "thirty" => (30, Tens, false),
+        "forty" => (40, Tens, false),
+        "fifty" => (50, Tens, false),
+        "sixty" => (60, Tens, false),
+        "seventy" => (70, Tens, false),
+        "eighty" => (80, Tens, false),
+        "ninety" => (90, Tens, false),
+        "twentieth" => (20, Tens, true),
+        "thirtieth" => (30, Tens, true),
+        "fortieth" => (40, Tens, true),
+        "fiftieth" => (50, Tens, true),
+        "sixtieth" => (60, Tens, true),
+        "seventieth" => (70, Tens, true),
+        "eightieth" => (80, Tens, true),
+        "ninetieth" => (90, Tens, true),
+        "hundred" => (100, Hundred, false),
+        "hundredth" => (100, Hundred, true),
+        "thousand" => (1_000, Scale, false),
+        "thousandth" => (1_000, Scale, true),
+        "million" => (1_000_000, Scale, false),
+        "millionth" => (1_000_000, Scale, true),
+        "billion" => (1_000_000_000, Scale, false),
+        "billionth" => (1_000_000_000, Scale, true),
+        _ => return None,
+    };
+    Some(NumWord {
+        value,
+        kind,
+        ordinal,
+    })
+}
+
+/// A word with the whitespace before it and any trailing punctuation split off.
+struct Word<'a> {
+    ws: &'a str,
+    raw: &'a str,
+    core: String,
+    trail: &'a str,
+}
+
+impl Word<'_> {
+    /// Trailing punctuation ends a number phrase ("twenty, three" is two numbers).
+    fn has_trail(&self) -> bool {
+        !self.trail.is_empty()
+    }
+}
+
+/// Split text into words, keeping the exact whitespace so it can be rebuilt.
+/// Also returns any trailing whitespace.
+fn split_words(text: &str) -> (Vec<Word<'_>>, &str) {
+    let mut words = Vec::new();
+    let mut rest = text;
+    loop {
+        let ws_len = rest.len() - rest.trim_start().len();
+        let (ws, after) = rest.split_at(ws_len);
+        if after.is_empty() {
+            return (words, ws);
+        }
+        let word_len = after.find(char::is_whitespace).unwrap_or(after.len());
+        let (raw, next) = after.split_at(word_len);
+        let body = raw.trim_end_matches(|c: char| {
+            matches!(c, ',' | '.' | '!' | '?' | ';' | ':' | ')' | '"' | '\'')
+        });
+        words.push(Word {
+            ws,
+            raw,
+            core: body.to_lowercase(),
+            trail: &raw[body.len()..],
+        });
+        rest = next;
+    }
+}
+
+struct Parsed {
+    value: u64,
+    words: usize,
+    ordinal: bool,
+    last_kind: Kind,
+}
+
+/// Parse the longest well-formed cardinal/ordinal at the start of `words`.
+fn parse_cardinal(words: &[Word]) -> Option<Parsed> {
+    let mut total = 0u64;
+    let mut current = 0u64;
+    let mut max_scale = u64::MAX;
+    let mut last: Option<Kind> = None;
+    let mut consumed = 0;
+    let mut ordinal = false;
+
+    let mut i = 0;
+    while i < words.len() {
+        let w = &words[i];
+
+        // "one hundred and five": "and" only joins a hundred/scale to a smaller number
+        if w.core == "and" {
+            let joins = matches!(last, Some(Kind::Hundred) | Some(Kind::Scale))
+                && !w.has_trail()
+                && words.get(i + 1).and_then(|n| classify(&n.core)).is_some_and(|n| {
+                    matches!(n.kind, Kind::Unit | Kind::Teen | Kind::Tens)
+                });
+            if joins {
+                i += 1;
+                continue;
+            }
+            break;
+        }
+
+        let Some(n) = classify(&w.core) else { break };
+        let fits = match n.kind {
+            Kind::Unit => matches!(last, None | Some(Kind::Tens | Kind::Hundred | Kind::Scale)),
+            Kind::Teen | Kind::Tens => matches!(last, None | Some(Kind::Hundred | Kind::Scale)),
+            Kind::Hundred => matches!(last, Some(Kind::Unit | Kind::Teen | Kind::Tens)) && current < 100,
+            Kind::Scale => current > 0 && n.value < max_scale,
+        };
+        if !fits {
+            break;
+        }
+
+        match n.kind {
+            Kind::Unit | Kind::Teen | Kind::Tens => current += n.value,
+            Kind::Hundred => current *= 100,
+            Kind::Scale => {
+                total += current * n.value;
+                current = 0;
+                max_scale = n.value;
+            }
+        }
+        last = Some(n.kind);
+        i += 1;
+        consumed = i;
+
+        if n.ordinal || w.has_trail() {
+            ordinal = n.ordinal;
+            break;
+        }
+    }
+
+    Some(Parsed {
+        value: total + current,
+        words: consumed,
+        ordinal,
+        last_kind: last?,
+    })
+}
+
+/// "nineteen eighty four" -> 1984, "twenty twenty three" -> 2023, "nineteen oh five" -> 1905.
+/// `first` is the already-parsed leading group; returns the year and words used after it.
+fn parse_year_tail(first: u64, rest: &[Word]) -> Option<(u64, usize)> {
+    if !(11..=20).contains(&first) {
+        return None;
+    }
+    if rest.first()?.core == "oh" {
+        let n = classify(&rest.get(1)?.core)?;
+        if rest[0].has_trail() || n.kind != Kind::Unit || n.ordinal || n.value == 0 {
+            return None;
+        }
+        return Some((first * 100 + n.value, 2));
+    }
+    let q = parse_cardinal(rest)?;
+    if q.ordinal || !(10..=99).contains(&q.value) {
+        return None;
+    }
+    Some((first * 100 + q.value, q.words))
+}
+
+/// Three or more single digits in a row ("five five five one two") are read
+/// digit by digit, as in phone numbers.
+fn parse_digit_run(words: &[Word]) -> Option<(String, usize)> {
+    let mut digits = String::new();
+    for w in words {
+        match classify(&w.core) {
+            Some(d) if d.kind == Kind::Unit && !d.ordinal => {
+                digits.push_str(&d.value.to_string());
+                if w.has_trail() {
+                    break;
+                }
+            }
+            _ => break,
+        }
+    }
+    let n = digits.len();
+    (n >= 3).then_some((digits, n))
+}
+
+/// "five dollars", "five dollars and fifty cents", "twenty percent".
+fn parse_unit_suffix(value: u64, rest: &[Word]) -> Option<(String, usize)> {
+    let w = rest.first()?;
+    let symbol = match w.core.as_str() {
+        "dollar" | "dollars" | "bucks" => "$",
+        "euro" | "euros" => "€",
+        "percent" => return Some((format!("{}%", value), 1)),
+        _ => return None,
+    };
+
+    if !w.has_trail() && rest.get(1).is_some_and(|a| a.core == "and" && !a.has_trail()) {
+        if let Some(c) = parse_cardinal(&rest[2..]) {
+            let cents_word = rest.get(2 + c.words);
+            if !c.ordinal
+                && c.value < 100
+                && !rest[1 + c.words].has_trail()
+                && cents_word.is_some_and(|u| u.core == "cent" || u.core == "cents")
+            {
+                return Some((format!("{}{}.{:02}", symbol, value, c.value), 3 + c.words));
+            }
+        }
+    }
+    Some((format!("{}{}", symbol, value), 1))
+}
+
+fn ordinal_suffix(n: u64) -> &'static str {
+    if (11..=13).contains(&(n % 100)) {
+        return "th";
+    }
+    match n % 10 {
+        1 => "st",
+        2 => "nd",
+        3 => "rd",
+        _ => "th",
+    }
+}
+
+/// Try to convert a number phrase starting at `words[0]`.
+/// Returns the replacement and how many words it covers.
+fn convert_at(words: &[Word]) -> Option<(String, usize)> {
+    let p = parse_cardinal(words)?;
+    let last = &words[p.words - 1];
+    let rest = &words[p.words..];
+
+    if !p.ordinal && !last.has_trail() {
+        if p.words == 1 {
+            if let Some((year, n)) = parse_year_tail(p.value, rest) {
+                return Some((year.to_string(), 1 + n));
+            }
+        }
+        if let Some((s, n)) = parse_unit_suffix(p.value, rest) {
+            return Some((s, p.words + n));
+        }
+    }
+    if !p.ordinal && p.words == 1 && p.last_kind == Kind::Unit {
+        if let Some(run) = parse_digit_run(words) {
+            return Some(run);
+        }
+    }
+
+    // Leave lone small numbers as words
+    if p.words == 1 && p.value < 10 {
+        return None;
+    }
+    // A bare "hundred"/"thousand" without a count isn't a number phrase
+    if p.words == 1 && matches!(p.last_kind, Kind::Hundred | Kind::Scale) {
+        return None;
+    }
+
+    let s = if p.ordinal {
+        format!("{}{}", p.value, ordinal_suffix(p.value))
+    } else {
+        p.value.to_string()
+    };
+    Some((s, p.words))
+}
+
+/// "twenty-three" -> "twenty three" so hyphenated numbers parse like spaced ones.
+fn split_number_hyphens(text: &str) -> String {
+    let (words, tail) = split_words(text);
+    let mut out = String::with_capacity(text.len());
+    for w in &words {
+        out.push_str(w.ws);
+        if w.core.contains('-') && w.core.split('-').all(|p| classify(p).is_some()) {
+            out.push_str(&w.raw.replace('-', " "));
+        } else {
+            out.push_str(w.raw);
+        }
+    }
+    out.push_str(tail);
+    out
+}
+
+/// Whisper codes of the languages with a number word table.
+const LANGUAGES: &[&str] = &["en"];
+
+/// Replace spoken numbers with digits. Text in a `language` with no word
+/// table is left alone; None (not known) is taken as English.
+pub fn spoken_numbers_to_digits(text: &str, language: Option<&str>) -> String {
+    if language.is_some_and(|language| !LANGUAGES.contains(&language)) {
+        return text.to_string();
+    }
+    english_to_digits(text)
+}
+
+fn english_to_digits(text: &str) -> String {
+    let text = split_number_hyphens(text);
+    let (words, tail) = split_words(&text);
+
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < words.len() {
+        match convert_at(&words[i..]) {
+            Some((digits, n)) => {
+                out.push_str(words[i].ws);
+                out.push_str(&digits);
+                out.push_str(words[i + n - 1].trail);
+                i += n;
+            }
+            None => {
+                out.push_str(words[i].ws);
+                out.push_str(words[i].raw);
+                i += 1;
+            }
+        }
+    }
+    out.push_str(tail);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::spoken_numbers_to_digits;
+
+    fn digits(text: &str) -> String {
+        spoken_numbers_to_digits(text, Some("en"))
+    }
+
+    #[test]
+    fn only_languages_with_a_word_table_are_converted() {
+        assert_eq!(spoken_numbers_to_digits("twenty five apples", None), "25 apples");
+        assert_eq!(spoken_numbers_to_digits("ich habe five", Some("de")), "ich habe five");
+        assert_eq!(spoken_numbers_to_digits("двадцать пять", Some("ru")), "двадцать пять");
+    }
+
+    #[test]
+    fn cardinals() {
+        assert_eq!(digits("I have twenty five apples"), "I have 25 apples");
+        assert_eq!(digits("twenty-three people"), "23 people");
+        assert_eq!(digits("one hundred and five"), "105");
+        assert_eq!(digits("two thousand three hundred forty"), "2340");
+        assert_eq!(digits("three million"), "3000000");
+        assert_eq!(digits("eleven, twelve."), "11, 12.");
+    }
+
+    #[test]
+    fn ordinals() {
+        assert_eq!(digits("the twenty first of May"), "the 21st of May");
+        assert_eq!(digits("his thirty third birthday"), "his 33rd birthday");
+        assert_eq!(digits("the eleventh hour"), "the 11th hour");
+        assert_eq!(digits("one hundredth time"), "100th time");
+    }
+
+    #[test]
+    fn years() {
+        assert_eq!(digits("in twenty twenty-three"), "in 2023");
+        assert_eq!(digits("since nineteen eighty four"), "since 1984");
+        assert_eq!(digits("built in nineteen oh five"), "built in 1905");
+    }
+
+    #[test]
+    fn currency_and_percent() {
+        assert_eq!(digits("five dollars"), "$5");
+        assert_eq!(digits("five dollars and fifty cents"), "$5.50");
+        assert_eq!(digits("twenty euros"), "€20");
+        assert_eq!(digits("twenty percent off"), "20% off");
+    }
+
+    #[test]
+    fn phone_style_digit_runs() {
+        assert_eq!(digits("call five five five one two"), "call 55512");
+        assert_eq!(digits("one two, three"), "one two, three");
+    }
+
+    #[test]
+    fn conservative_with_words() {
+        assert_eq!(digits("one of a kind"), "one of a kind");
+        assert_eq!(digits("first things first"), "first things first");
+        assert_eq!(digits("a twenty second delay"), "a 20 second delay");
+        assert_eq!(digits("hundreds of thousands"), "hundreds of thousands");
+        assert_eq!(digits("no numbers here"), "no numbers here");
+    }
+}
@@ -0,0 +1,127 @@
+//! Mask sensitive text before it is pasted, e.g. while screen sharing:
+//! card- and phone-like digit runs and a user-defined word list.
+
+use super::match_phrase_at;
+
+/// Replacement used when no mask is configured.
+pub const DEFAULT_MASK: &str = "[redacted]";
+
+/// Digit runs at least this long are masked. Long enough to leave years,
+/// times and dates alone but catch card, account and phone numbers.
+const MIN_DIGIT_RUN: usize = 10;
+
+/// Group sizes that a single space may join into one run.
+const SPACED_GROUP: std::ops::RangeInclusive<usize> = 3..=4;
+
+/// Apply both detectors. `mask` falls back to "[redacted]" when empty.
+pub fn redact(text: &str, words: &[String], mask: &str) -> String {
+    let mask = if mask.is_empty() { DEFAULT_MASK } else { mask };
+    let mut text = redact_digit_runs(text, mask);
+    for word in words.iter().map(|w| w.trim()).filter(|w| !w.is_empty()) {
+        text = redact_phrase(&text, word, mask);
+    }
+    text
+}
+
+/// Replace runs of at least MIN_DIGIT_RUN digits with `mask`. Digits may be
+/// grouped by dashes ("555-123-4567") or by single spaces between groups of
+/// three or four digits, the way cards and phones are written ("4111 1111
+/// 1111 1111"); the separators inside the run are masked too. Other numbers
+/// that merely follow each other ("12345 67890") stay separate.
+pub fn redact_digit_runs(text: &str, mask: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let group_len = |start: usize| chars[start..].iter().take_while(|c| c.is_ascii_digit()).count();
+
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if !chars[i].is_ascii_digit() {
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        // Extend over the following groups for as long as the separator
+        // between them joins them
+        let mut len = group_len(i);
+        let mut end = i + len;
+        let mut digits = len;
+        while let (Some(&sep), Some(next)) = (chars.get(end), chars.get(end + 1)) {
+            if !next.is_ascii_digit() {
+                break;
+            }
+            let next_len = group_len(end + 1);
+            let joins = match sep {
+                '-' => true,
+                ' ' => SPACED_GROUP.contains(&len) && SPACED_GROUP.contains(&next_len),
+                _ => false,
+            };
+            if !joins {
+                break;
+            }
+            end += 1 + next_len;
+            digits += next_len;
+            len = next_len;
+        }
+
+        if digits >= MIN_DIGIT_RUN {
+            out.push_str(mask);
+        } else {
+            out.extend(&chars[i..end]);
+        }
+        i = end;
+    }
+    out
+}
+
+/// Replace whole-word, case-insensitive occurrences of `phrase` with `mask`.
+/// "ass" doesn't match inside "class", and multi-word phrases work too.
+pub fn redact_phrase(text: &str, phrase: &str, mask: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = phrase.chars().collect();
+
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if let Some(end) = match_phrase_at(&chars, i, &pattern) {
+            out.push_str(mask);
+            i = end;
+        } else {
+            out.push(chars[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digit_runs_mask_cards_and_phone_numbers() {
+        assert_eq!(redact_digit_runs("card 4111 1111 1111 1111.", "#"), "card #.");
+        assert_eq!(redact_digit_runs("call 555-123-4567 now", "#"), "call # now");
+        assert_eq!(redact_digit_runs("call 555 123 4567 now", "#"), "call # now");
+        assert_eq!(redact_digit_runs("acct 12345678901", "#"), "acct #");
+    }
+
+    #[test]
+    fn digit_runs_leave_short_and_separate_numbers() {
+        assert_eq!(redact_digit_runs("at 10:30 on 2024-03-05", "#"), "at 10:30 on 2024-03-05");
+        assert_eq!(redact_digit_runs("rooms 12345 67890", "#"), "rooms 12345 67890");
+        assert_eq!(redact_digit_runs("1 2 3 4 5 6 7 8 9 10", "#"), "1 2 3 4 5 6 7 8 9 10");
+        assert_eq!(redact_digit_runs("ids 123456, 7890123", "#"), "ids 123456, 7890123");
+    }
+
+    #[test]
+    fn blocklist_masks_whole_words_only() {
+        let words = vec!["Acme".to_string(), "project x".to_string(), " ".to_string()];
+        assert_eq!(
+            redact("acme and PROJECT X ship; Acmeville stays", &words, ""),
+            "[redacted] and [redacted] ship; Acmeville stays"
+        );
+        assert_eq!(redact_phrase("first class ass", "ass", "*"), "first class *");
+        assert_eq!(redact_phrase("Пароль: пароль123", "пароль", "*"), "*: пароль123");
+    }
+}
@@ -0,0 +1,246 @@
+//! Deterministic clean-up used as the "Rules" formatting provider: basic
+//! formatting with no network call or local LLM.
+
+use super::match_phrase_at;
+
+/// Spoken commands and what they become. A phrase must come before any
+/// other phrase it starts with. Russian "точка" is left out, since it is far
+/// more often "point" than a dictated period.
+const COMMANDS: &[(&str, &str)] = &[
+    ("new paragraph", "\n\n"),
+    ("new line", "\n"),
+    ("period", "."),
+    ("full stop", "."),
+    ("comma", ","),
+    ("question mark", "?"),
+    ("exclamation mark", "!"),
+    ("exclamation point", "!"),
+    ("semicolon", ";"),
+    ("colon", ":"),
+    ("новый абзац", "\n\n"),
+    ("новая строка", "\n"),
+    ("запятая", ","),
+    ("вопросительный знак", "?"),
+    ("восклицательный знак", "!"),
+    ("точка с запятой", ";"),
+    ("двоеточие", ":"),
+];
+
+/// Commands that are also everyday words ("the trial period", "colon
+/// cancer"), only taken as commands when set off by pauses on both sides.
+const WORD_COMMANDS: &[&str] = &["period", "full stop", "comma", "colon"];
+
+/// Punctuation Whisper writes at a pause.
+fn is_pause(c: char) -> bool {
+    matches!(c, ',' | '.' | '!' | '?' | ';' | ':' | '\n')
+}
+
+/// Apply every rule in order.
+pub fn format(text: &str) -> String {
+    let text = apply_commands(text);
+    let text = collapse_whitespace(&text);
+    let text = capitalize_i(&text);
+    let text = capitalize_sentences(&text);
+    ensure_terminal_punctuation(&text)
+}
+
+/// Replace spoken commands (case-insensitive, whole words) with line breaks
+/// or punctuation: "new line", "new paragraph", "comma", "period", "question
+/// mark" and so on. The comma or period Whisper tends to put around a command
+/// word is dropped, so "Hello, comma, world" becomes "Hello, world".
+/// WORD_COMMANDS need a pause or the text's edge on both sides, so "Hello,
+/// comma, world" converts but "the trial period ends" is left alone.
+pub fn apply_commands(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    'outer: while i < chars.len() {
+        for (phrase, replacement) in COMMANDS {
+            let pattern: Vec<char> = phrase.chars().collect();
+            let Some(end) = match_phrase_at(&chars, i, &pattern) else {
+                continue;
+            };
+            if WORD_COMMANDS.contains(phrase) {
+                let before = out.trim_end_matches(' ').chars().last();
+                let after = chars[end..].iter().find(|&&c| c != ' ');
+                if !before.is_none_or(is_pause) || !after.is_none_or(|&c| is_pause(c)) {
+                    continue;
+                }
+            }
+            let line_break = replacement.starts_with('\n');
+            // Drop the spaces before the command, and a mark attaches in
+            // place of Whisper's own comma or period
+            while out.ends_with(' ') {
+                out.pop();
+            }
+            if !line_break && out.ends_with([',', '.']) {
+                out.pop();
+            }
+            out.push_str(replacement);
+            i = end;
+            // Drop Whisper's punctuation after it, and after a line break
+            // the space before the next word too
+            let skip = |c: char| matches!(c, ',' | '.') || line_break && c == ' ';
+            while chars.get(i).is_some_and(|&c| skip(c)) {
+                i += 1;
+            }
+            continue 'outer;
+        }
+        out.push(chars[i]);
+        i += 1;
+    }
+    out
+}
+
+/// Squeeze runs of spaces and tabs into one space, drop spaces at line
+/// edges and before punctuation, and trim the text. Line breaks are kept.
+pub fn collapse_whitespace(text: &str) -> String {
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            let mut out = String::with_capacity(line.len());
+            for word in line.split_whitespace() {
+                let attaches = word.starts_with(['.', ',', '!', '?', ';', ':']);
+                if !out.is_empty() && !attaches {
+                    out.push(' ');
+                }
+                out.push_str(word);
+            }
+            out
+        })
+        .collect();
+    lines.join("\n").trim().to_string()
+}
+
+/// Capitalize the English pronoun "I" and its contractions (i'm, i'll, ...).
+/// Latin "i" only, so Cyrillic "и" is unaffected.
+pub fn capitalize_i(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    for (idx, &c) in chars.iter().enumerate() {
+        let word_start = idx == 0 || !chars[idx - 1].is_alphanumeric();
+        let after = chars.get(idx + 2);
+        let pronoun = c == 'i'
+            && word_start
+            && match chars.get(idx + 1) {
+                None => true,
+                // "i.e." is an abbreviation, "so do i." is the pronoun
+                Some('.') => !after.is_some_and(|n| n.is_alphabetic()),
+                Some('-') => false,
+                Some(next) => !next.is_alphanumeric(),
+            };
+        out.push(if pronoun { 'I' } else { c });
+    }
+    out
+}
+
+/// Uppercase the first letter of the text, of every line and of every
+/// sentence after ".", "!" or "?" plus whitespace ("e.g." is left alone).
+pub fn capitalize_sentences(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut at_start = true;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if at_start && c.is_alphabetic() {
+            out.extend(c.to_uppercase());
+            at_start = false;
+            continue;
+        }
+        if c == '\n' {
+            at_start = true;
+        } else if matches!(c, '.' | '!' | '?' | '…') {
+            at_start = chars.peek().is_none_or(|n| n.is_whitespace());
+        } else if !c.is_whitespace() && !matches!(c, '"' | '\'' | '«' | '“' | '(') {
+            // Digits and other symbols start a sentence too
+            at_start = false;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// End every line that finishes on a letter or digit with a period.
+pub fn ensure_terminal_punctuation(text: &str) -> String {
+    text.split('\n')
+        .map(|line| match line.chars().last() {
+            Some(c) if c.is_alphanumeric() => format!("{}.", line),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_become_line_breaks() {
+        assert_eq!(apply_commands("Dear Anna, new line. Thanks"), "Dear Anna,\nThanks");
+        assert_eq!(apply_commands("One. New paragraph two"), "One.\n\ntwo");
+        assert_eq!(apply_commands("Привет новая строка пока"), "Привет\nпока");
+    }
+
+    #[test]
+    fn commands_become_punctuation() {
+        assert_eq!(
+            apply_commands("Hello, comma, how are you question mark"),
+            "Hello, how are you?"
+        );
+        assert_eq!(apply_commands("Hello, comma, world. Period."), "Hello, world.");
+        assert_eq!(apply_commands("Note, colon, done exclamation point"), "Note: done!");
+        assert_eq!(apply_commands("Wait semicolon go"), "Wait; go");
+        assert_eq!(apply_commands("Да запятая конечно"), "Да, конечно");
+        // Whole words only
+        assert_eq!(apply_commands("periodic commas"), "periodic commas");
+    }
+
+    #[test]
+    fn command_words_in_a_sentence_are_kept() {
+        assert_eq!(apply_commands("the trial period ends"), "the trial period ends");
+        assert_eq!(apply_commands("colon cancer screening"), "colon cancer screening");
+        assert_eq!(apply_commands("a trial period, then"), "a trial period, then");
+        assert_eq!(apply_commands("add a comma here"), "add a comma here");
+        assert_eq!(apply_commands("came to a full stop."), "came to a full stop.");
+    }
+
+    #[test]
+    fn whitespace_is_collapsed() {
+        assert_eq!(
+            collapse_whitespace("  one   two\t three , four \n five "),
+            "one two three, four\nfive"
+        );
+    }
+
+    #[test]
+    fn pronoun_i_is_capitalized() {
+        assert_eq!(capitalize_i("i think i'm right, so do i."), "I think I'm right, so do I.");
+        assert_eq!(capitalize_i("i.e. the i-th iPhone и"), "i.e. the i-th iPhone и");
+    }
+
+    #[test]
+    fn sentence_starts_are_capitalized() {
+        assert_eq!(
+            capitalize_sentences("one. two? \"three\"! four\nfive"),
+            "One. Two? \"Three\"! Four\nFive"
+        );
+        assert_eq!(capitalize_sentences("version 1.5 ships"), "Version 1.5 ships");
+        assert_eq!(capitalize_sentences("привет. как дела"), "Привет. Как дела");
+    }
+
+    #[test]
+    fn terminal_punctuation_is_added() {
+        assert_eq!(
+            ensure_terminal_punctuation("Done\nReally?\nItem 2\n"),
+            "Done.\nReally?\nItem 2.\n"
+        );
+    }
+
+    #[test]
+    fn format_applies_every_rule() {
+        assert_eq!(
+            format("so  i said hi, comma, new line how are you question mark fine"),
+            "So I said hi,\nHow are you? Fine."
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! Prefix/suffix boilerplate with a few placeholders, e.g. a ticket header
+//! like "Reported {date} {time}:\n", and optional per-dictation timestamps.
+
+use chrono::{DateTime, Local};
+use std::fmt::Write;
+
+/// Timestamp format used when none is configured.
+pub const DEFAULT_TIMESTAMP_FORMAT: &str = "[%H:%M] ";
+
+/// Expand {date}, {time} and {weekday} in `template`. Unknown braces are kept as-is.
+pub fn expand_placeholders(template: &str, now: &DateTime<Local>) -> String {
+    template
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &now.format("%H:%M").to_string())
+        .replace("{weekday}", &now.format("%A").to_string())
+}
+
+/// `expand_placeholders` for file paths: {time} becomes "HH-MM", since a
+/// colon isn't allowed in Windows file names.
+pub fn expand_path_placeholders(template: &str, now: &DateTime<Local>) -> String {
+    let template = template.replace("{time}", &now.format("%H-%M").to_string());
+    expand_placeholders(&template, now)
+}
+
+/// Wrap `text` in the expanded prefix and suffix. Empty templates are skipped.
+pub fn apply_affixes(text: &str, prefix: &str, suffix: &str) -> String {
+    if prefix.is_empty() && suffix.is_empty() {
+        return text.to_string();
+    }
+    let now = Local::now();
+    format!(
+        "{}{}{}",
+        expand_placeholders(prefix, &now),
+        text,
+        expand_placeholders(suffix, &now)
+    )
+}
+
+/// Render `now` with a strftime `format`, reporting bad specifiers instead of
+/// panicking the way `to_string()` would.
+pub fn format_timestamp(format: &str, now: &DateTime<Local>) -> Result<String, String> {
+    let format = if format.is_empty() {
+        DEFAULT_TIMESTAMP_FORMAT
+    } else {
+        format
+    };
+    let mut out = String::new();
+    write!(out, "{}", now.format(format))
+        .map_err(|_| format!("Invalid timestamp format: {:?}", format))?;
+    Ok(out)
+}
+
+/// Put the current local time in front of `text`. A bad format (which the
+/// settings command rejects anyway) leaves the text unchanged.
+pub fn prepend_timestamp(text: &str, format: &str) -> String {
+    match format_timestamp(format, &Local::now()) {
+        Ok(stamp) => format!("{}{}", stamp, text),
+        Err(e) => {
+            log::warn!("{}", e);
+            text.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn path_placeholders_avoid_colons() {
+        let now = Local.with_ymd_and_hms(2024, 3, 5, 14, 32, 0).unwrap();
+        assert_eq!(
+            expand_path_placeholders("C:\\Notes\\{date} {time} {weekday}.md", &now),
+            "C:\\Notes\\2024-03-05 14-32 Tuesday.md"
+        );
+        assert_eq!(expand_placeholders("{time}", &now), "14:32");
+    }
+}
@@ -1,37 +1,675 @@
-use crate::formatting::AiSettings;
+use crate::formatting::{self, AiProvider, AiSettings, FormatChoice, PromptPreset};
+use crate::postprocess::PostProcessSettings;
+use crate::transcription::engine::{
+    ContextSettings, ModelOverrides, TranscriptionSettings, MULTILINGUAL_PROMPT,
+};
+use crate::transcription::models::ModelSpeed;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
     pub hotkey: String,
+    /// Launch with the window hidden; it opens from the tray.
+    #[serde(default)]
+    pub start_minimized: bool,
+    #[serde(default)]
+    pub recording_mode: RecordingMode,
+    /// A hold-to-talk release followed by a press within this many ms keeps
+    /// the same recording going (0 = stop on every release).
+    #[serde(default = "default_stutter_tolerance_ms")]
+    pub stutter_tolerance_ms: u64,
+    /// In hold mode, a quick tap of the hotkey pastes the last dictation
+    /// again instead of starting a new one.
+    #[serde(default)]
+    pub tap_to_reinject: bool,
+    /// Modifier ("ctrl", "shift", "alt" or "super") whose double tap toggles
+    /// dictation, alongside the hotkey (empty = off). Windows only.
+    #[serde(default)]
+    pub double_tap_modifier: String,
+    /// Most time between the two taps' presses.
+    #[serde(default = "default_double_tap_window_ms")]
+    pub double_tap_window_ms: u64,
     #[serde(default)]
     pub start_sound: String,
     #[serde(default)]
     pub stop_sound: String,
     #[serde(default = "default_volume")]
     pub sound_volume: f32,
+    /// Keep the sound output stream open all the time for the quickest cues.
+    /// Off opens it per sound, so the speakers aren't held "in use" and can
+    /// power down.
+    #[serde(default = "default_true")]
+    pub keep_sound_output_open: bool,
+    /// How to tell the user a recording came back without speech.
+    #[serde(default)]
+    pub empty_feedback: EmptyFeedback,
+    /// Master switch for AI formatting of dictations. Off keeps the provider
+    /// configuration but pastes the raw transcription.
+    #[serde(default = "default_true")]
+    pub ai_enabled: bool,
     #[serde(default)]
     pub ai: AiSettings,
+    /// Providers tried in order when the primary `ai` provider fails.
+    #[serde(default)]
+    pub ai_fallbacks: Vec<AiSettings>,
+    /// Named prompts that hotkey bindings can select.
+    #[serde(default)]
+    pub prompt_presets: Vec<PromptPreset>,
+    /// Extra dictation hotkeys, each with its own formatting.
+    #[serde(default)]
+    pub hotkey_bindings: Vec<HotkeyBinding>,
+    #[serde(default)]
+    pub transcription: TranscriptionSettings,
+    /// Load-time model options (flash attention, GPU, DTW).
+    #[serde(default)]
+    pub model_context: ContextSettings,
+    /// Model file in the models directory loaded at startup.
+    #[serde(default = "default_model_filename")]
+    pub model_filename: String,
+    /// Last preset applied with set_transcription_mode.
+    #[serde(default)]
+    pub transcription_mode: TranscriptionMode,
+    /// Model to switch to when a language is selected, e.g. "en" ->
+    /// "ggml-small.en.bin", "ru" -> "ggml-medium.bin". "auto" is used when
+    /// the language is left to auto-detection.
+    #[serde(default)]
+    pub language_models: BTreeMap<String, String>,
+    /// Per-model language, bias prompt and thread count, keyed by model
+    /// file. The language is also remembered here whenever one is selected,
+    /// so each model comes back with the language it was last used with.
+    #[serde(default)]
+    pub model_overrides: BTreeMap<String, ModelOverrides>,
+    /// Unload the model after this many minutes without dictation to free
+    /// its memory; it is loaded again when the next recording starts
+    /// (0 = keep it loaded).
+    #[serde(default)]
+    pub idle_unload_minutes: u64,
+    /// Transcription speed measured per model file, for time estimates.
+    #[serde(default)]
+    pub model_speeds: BTreeMap<String, ModelSpeed>,
+    #[serde(default)]
+    pub postprocess: PostProcessSettings,
+    /// Auto-stop a recording after this many seconds (0 = no limit).
+    /// Guards against a missed hotkey release leaving the mic open.
+    #[serde(default = "default_max_recording_seconds")]
+    pub max_recording_seconds: u64,
+    /// Start counting down this many seconds before the auto-stop
+    /// (0 = no warning).
+    #[serde(default = "default_recording_warning_seconds")]
+    pub recording_warning_seconds: u64,
+    /// Play a soft tick when the countdown starts.
+    #[serde(default)]
+    pub recording_warning_sound: bool,
+    /// Recordings shorter than this are dropped without transcribing (0 = keep all).
+    #[serde(default = "default_min_recording_ms")]
+    pub min_recording_ms: u64,
+    /// Recordings longer than this many seconds are split at pauses into
+    /// chunks of about this length and transcribed one by one (0 = never).
+    #[serde(default)]
+    pub chunk_seconds: u64,
+    /// Stop a latched or tray-started recording after this much silence
+    /// following speech (0 = off).
+    #[serde(default)]
+    pub auto_stop_silence_ms: u64,
+    /// Alternative host or base URL for model downloads (empty = HuggingFace).
+    #[serde(default)]
+    pub model_mirror_url: String,
+    /// Ambient RMS measured by calibrate_noise (0 = never calibrated).
+    #[serde(default)]
+    pub noise_floor_rms: f32,
+    /// RMS below which leading/trailing audio is trimmed as silence (0 = off).
+    #[serde(default)]
+    pub silence_threshold: f32,
+    /// Show a small always-on-top status pill while dictating.
+    #[serde(default)]
+    pub show_indicator: bool,
+    #[serde(default)]
+    pub indicator_position: IndicatorPosition,
+    /// Display the indicator appears on.
+    #[serde(default)]
+    pub indicator_monitor: IndicatorMonitor,
+    /// Where finished dictations go: pasted, appended to `output_file`, both,
+    /// or nowhere but the app window.
+    #[serde(default)]
+    pub output_mode: OutputMode,
+    /// File dictations are appended to; {date}, {time} (as HH-MM) and
+    /// {weekday} expand, so "journal-{date}.md" starts a new file each day.
+    #[serde(default)]
+    pub output_file: String,
+    /// Command run with every finished dictation, which it gets on stdin or
+    /// in place of "{text}" in its arguments (empty = off). It runs with the
+    /// user's permissions on whatever was dictated, so only point it at
+    /// programs you trust.
+    #[serde(default)]
+    pub post_command: String,
+    /// Always paste into the window whose title contains this text,
+    /// regardless of focus (empty = paste into the focused app).
+    #[serde(default)]
+    pub target_window: String,
+    /// After pasting into `target_window`, give focus back to the previous window.
+    #[serde(default = "default_true")]
+    pub restore_focus: bool,
+    /// Keys that paste in the target app, e.g. "Ctrl+Shift+V" for terminals
+    /// (empty = Cmd+V on macOS, Ctrl+V elsewhere).
+    #[serde(default)]
+    pub paste_shortcut: String,
+    #[serde(default)]
+    pub injection_method: InjectionMethod,
+    /// Longest text the Auto injection method types instead of pasting.
+    #[serde(default = "default_type_max_chars")]
+    pub type_max_chars: usize,
+    /// What happens to the user's clipboard after a paste.
+    #[serde(default)]
+    pub clipboard_restore: ClipboardRestore,
+    /// Key pressed after each pasted dictation to move to the next form field.
+    #[serde(default)]
+    pub auto_advance: AutoAdvance,
+    /// Allow undo_last_injection to delete the last paste with simulated
+    /// keystrokes.
+    #[serde(default)]
+    pub undo_injection: bool,
+    /// Microphone to record from, by device name (None = system default).
+    /// A device that isn't connected falls back to the default.
+    #[serde(default)]
+    pub input_device: Option<String>,
+    /// Average noise magnitude spectrum measured by calibrate_noise.
+    #[serde(default)]
+    pub noise_profile: Vec<f32>,
+    /// Run spectral subtraction with `noise_profile` before transcribing.
+    #[serde(default)]
+    pub denoise: bool,
+    /// Filter out rumble below `high_pass_hz` while capturing, before the gain.
+    #[serde(default)]
+    pub high_pass: bool,
+    #[serde(default = "default_high_pass_hz")]
+    pub high_pass_hz: f32,
+    /// Fade out the last this many milliseconds of a recording before
+    /// transcribing, against clicks where it was cut off (0 = off).
+    #[serde(default = "default_fade_ms")]
+    pub fade_ms: u32,
+    /// Fade in the start of the recording over `fade_ms` as well.
+    #[serde(default)]
+    pub fade_in: bool,
+    /// off / error / warn / info / debug / trace. RUST_LOG overrides it.
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+}
+
+/// How the dictation hotkey starts and stops a recording.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingMode {
+    /// Record while the hotkey is held, stop on release.
+    #[default]
+    Hold,
+    /// First press starts, next press stops.
+    Toggle,
+    /// Hold to talk, or tap to start and tap again to stop.
+    Hybrid,
+}
+
+impl RecordingMode {
+    pub const ALL: [RecordingMode; 3] = [
+        RecordingMode::Hold,
+        RecordingMode::Toggle,
+        RecordingMode::Hybrid,
+    ];
+
+    pub fn parse(mode: &str) -> Result<Self, String> {
+        match mode.trim().to_lowercase().as_str() {
+            "hold" => Ok(RecordingMode::Hold),
+            "toggle" => Ok(RecordingMode::Toggle),
+            "hybrid" => Ok(RecordingMode::Hybrid),
+            other => Err(format!(
+                "Unknown recording mode: {} (expected hold, toggle or hybrid)",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RecordingMode::Hold => "hold",
+            RecordingMode::Toggle => "toggle",
+            RecordingMode::Hybrid => "hybrid",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RecordingMode::Hold => "Hold to Talk",
+            RecordingMode::Toggle => "Press to Toggle",
+            RecordingMode::Hybrid => "Hold or Tap",
+        }
+    }
+}
+
+/// Screen corner for the recording indicator.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndicatorPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+/// Display the recording indicator is shown on. Whatever can't be resolved
+/// (no focused window, a disconnected display) falls back to the display
+/// under the mouse cursor, then to the primary display.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndicatorMonitor {
+    /// The display holding the focused window, i.e. where the user types.
+    #[default]
+    ActiveWindow,
+    /// The display under the mouse cursor.
+    Cursor,
+    Primary,
+    /// A display by the name list_monitors reports.
+    Named(String),
+}
+
+/// Feedback when a recording is transcribed but no speech comes out, so it
+/// isn't mistaken for a hotkey that didn't work.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyFeedback {
+    /// Log only.
+    Silent,
+    /// A short low double beep.
+    #[default]
+    Sound,
+    /// A system notification.
+    Notification,
+    /// Beep and notify.
+    Both,
+}
+
+impl EmptyFeedback {
+    pub fn plays_sound(self) -> bool {
+        matches!(self, EmptyFeedback::Sound | EmptyFeedback::Both)
+    }
+
+    pub fn notifies(self) -> bool {
+        matches!(self, EmptyFeedback::Notification | EmptyFeedback::Both)
+    }
+}
+
+/// Destination for the final dictation text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    /// Paste into the focused (or target) window.
+    #[default]
+    Inject,
+    /// Append to the output file only.
+    AppendToFile,
+    /// Paste and append to the file.
+    Both,
+    /// Don't send the text anywhere: it is only shown in the app and kept
+    /// in the history, for manual copying or debugging the pipeline.
+    DisplayOnly,
+}
+
+impl OutputMode {
+    pub fn injects(self) -> bool {
+        matches!(self, OutputMode::Inject | OutputMode::Both)
+    }
+
+    pub fn writes_file(self) -> bool {
+        matches!(self, OutputMode::AppendToFile | OutputMode::Both)
+    }
+}
+
+/// How dictated text gets into the target app.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectionMethod {
+    /// Through the clipboard and the paste shortcut. Fast for any length.
+    #[default]
+    Paste,
+    /// As simulated keystrokes. Leaves the clipboard alone, but slow for
+    /// long text.
+    Type,
+    /// Type text up to `type_max_chars`, paste anything longer.
+    Auto,
+}
+
+/// Whether the clipboard gets its old contents back after a paste.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardRestore {
+    /// Put the old contents back; a failure is only logged.
+    #[default]
+    Always,
+    /// Leave the dictation on the clipboard for pasting again elsewhere.
+    Never,
+    /// Put the old contents back and tell the user when that fails.
+    RestoreOrWarn,
+}
+
+/// Navigation key sent after a dictation is pasted, for filling in forms.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoAdvance {
+    #[default]
+    Off,
+    Tab,
+    Enter,
+}
+
+/// An additional dictation hotkey tied to a prompt preset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeyBinding {
+    pub hotkey: String,
+    /// Name of a prompt preset (None = paste the raw dictation, no AI formatting).
+    #[serde(default)]
+    pub preset: Option<String>,
+}
+
+impl HotkeyBinding {
+    pub fn format_choice(&self) -> FormatChoice {
+        match &self.preset {
+            Some(name) => FormatChoice::Preset(name.clone()),
+            None => FormatChoice::Raw,
+        }
+    }
+}
+
+/// Bundled model + language + prompt presets for quick switching.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptionMode {
+    /// Small English-only model with the language pinned: fast and accurate for English.
+    English,
+    /// Multilingual model with auto-detection biased toward Russian and English.
+    #[default]
+    Multilingual,
+}
+
+impl TranscriptionMode {
+    pub fn parse(mode: &str) -> Result<Self, String> {
+        match mode.trim().to_lowercase().as_str() {
+            "english" => Ok(TranscriptionMode::English),
+            "multilingual" => Ok(TranscriptionMode::Multilingual),
+            other => Err(format!(
+                "Unknown transcription mode: {} (expected \"english\" or \"multilingual\")",
+                other
+            )),
+        }
+    }
+
+    pub fn model_filename(self) -> &'static str {
+        match self {
+            TranscriptionMode::English => "ggml-small.en.bin",
+            TranscriptionMode::Multilingual => "ggml-medium.bin",
+        }
+    }
+
+    pub fn language(self) -> Option<String> {
+        match self {
+            TranscriptionMode::English => Some("en".to_string()),
+            TranscriptionMode::Multilingual => None,
+        }
+    }
+
+    pub fn initial_prompt(self) -> String {
+        match self {
+            TranscriptionMode::English => String::new(),
+            TranscriptionMode::Multilingual => MULTILINGUAL_PROMPT.to_string(),
+        }
+    }
+}
+
+fn default_model_filename() -> String {
+    TranscriptionMode::default().model_filename().to_string()
+}
+
+/// serde default for flags that start out on, shared by every settings
+/// struct.
+pub(crate) fn default_true() -> bool {
+    true
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
 }
 
 fn default_volume() -> f32 {
     0.5
 }
 
+fn default_max_recording_seconds() -> u64 {
+    300
+}
+
+fn default_type_max_chars() -> usize {
+    20
+}
+
+fn default_recording_warning_seconds() -> u64 {
+    5
+}
+
+fn default_min_recording_ms() -> u64 {
+    300
+}
+
+fn default_stutter_tolerance_ms() -> u64 {
+    150
+}
+
+fn default_double_tap_window_ms() -> u64 {
+    400
+}
+
+fn default_high_pass_hz() -> f32 {
+    crate::audio::filter::DEFAULT_HIGH_PASS_HZ
+}
+
+fn default_fade_ms() -> u32 {
+    crate::audio::preprocess::DEFAULT_FADE_MS
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
             hotkey: "Ctrl+Shift+Space".to_string(),
+            start_minimized: false,
+            recording_mode: RecordingMode::default(),
+            stutter_tolerance_ms: default_stutter_tolerance_ms(),
+            tap_to_reinject: false,
+            double_tap_modifier: String::new(),
+            double_tap_window_ms: default_double_tap_window_ms(),
             start_sound: String::new(),
             stop_sound: String::new(),
             sound_volume: default_volume(),
+            keep_sound_output_open: true,
+            empty_feedback: EmptyFeedback::default(),
+            ai_enabled: true,
             ai: AiSettings::default(),
+            ai_fallbacks: Vec::new(),
+            prompt_presets: Vec::new(),
+            hotkey_bindings: Vec::new(),
+            transcription: TranscriptionSettings::default(),
+            model_context: ContextSettings::default(),
+            model_filename: default_model_filename(),
+            transcription_mode: TranscriptionMode::default(),
+            language_models: BTreeMap::new(),
+            model_overrides: BTreeMap::new(),
+            idle_unload_minutes: 0,
+            model_speeds: BTreeMap::new(),
+            postprocess: PostProcessSettings::default(),
+            max_recording_seconds: default_max_recording_seconds(),
+            recording_warning_seconds: default_recording_warning_seconds(),
+            recording_warning_sound: false,
+            min_recording_ms: default_min_recording_ms(),
+            chunk_seconds: 0,
+            auto_stop_silence_ms: 0,
+            model_mirror_url: String::new(),
+            noise_floor_rms: 0.0,
+            silence_threshold: 0.0,
+            show_indicator: false,
+            indicator_position: IndicatorPosition::default(),
+            indicator_monitor: IndicatorMonitor::default(),
+            output_mode: OutputMode::default(),
+            output_file: String::new(),
+            post_command: String::new(),
+            target_window: String::new(),
+            restore_focus: true,
+            paste_shortcut: String::new(),
+            injection_method: InjectionMethod::default(),
+            type_max_chars: default_type_max_chars(),
+            clipboard_restore: ClipboardRestore::default(),
+            auto_advance: AutoAdvance::default(),
+            undo_injection: false,
+            input_device: None,
+            noise_profile: Vec::new(),
+            denoise: false,
+            high_pass: false,
+            high_pass_hz: default_high_pass_hz(),
+            fade_ms: default_fade_ms(),
+            fade_in: false,
+            log_level: default_log_level(),
         }
     }
 }
 
 impl Settings {
+    /// Point the model, language and prompt at a mode's preset together.
+    pub fn apply_transcription_mode(&mut self, mode: TranscriptionMode) {
+        self.transcription_mode = mode;
+        self.model_filename = mode.model_filename().to_string();
+        self.transcription.language = mode.language();
+        self.transcription.initial_prompt = mode.initial_prompt();
+        // The preset wins over what the model remembered
+        let model = self.model_filename.clone();
+        self.remember_language(&model, mode.language().as_deref());
+        if let Some(overrides) = self.model_overrides.get_mut(&model) {
+            if overrides.initial_prompt.is_some() {
+                overrides.initial_prompt = Some(mode.initial_prompt());
+            }
+        }
+    }
+
+    /// Transcription settings in effect while `model_filename` is loaded.
+    pub fn transcription_for(&self, model_filename: &str) -> TranscriptionSettings {
+        match self.model_overrides.get(model_filename) {
+            Some(overrides) => self.transcription.with_overrides(overrides),
+            None => self.transcription.clone(),
+        }
+    }
+
+    /// Transcription settings in effect for the configured model.
+    pub fn active_transcription(&self) -> TranscriptionSettings {
+        self.transcription_for(&self.model_filename)
+    }
+
+    /// Store settings edited on top of `active_transcription`. A changed
+    /// language is remembered for the configured model, and a bias prompt or
+    /// thread count the model overrides updates the override, so the global
+    /// values don't pick up one model's choices.
+    pub fn set_active_transcription(&mut self, mut transcription: TranscriptionSettings) {
+        let model = self.model_filename.clone();
+        if transcription.language != self.active_transcription().language {
+            self.remember_language(&model, transcription.language.as_deref());
+        }
+        if let Some(overrides) = self.model_overrides.get_mut(&model) {
+            if let Some(prompt) = overrides.initial_prompt.as_mut() {
+                *prompt = std::mem::replace(
+                    &mut transcription.initial_prompt,
+                    self.transcription.initial_prompt.clone(),
+                );
+            }
+            if let Some(threads) = overrides.threads.as_mut() {
+                *threads = std::mem::replace(&mut transcription.threads, self.transcription.threads);
+            }
+        }
+        self.transcription = transcription;
+    }
+
+    /// Fold a measured real-time factor into `model_filename`'s speed.
+    pub fn record_model_speed(&mut self, model_filename: &str, rtf: f32, threads: i32) {
+        match self.model_speeds.get_mut(model_filename) {
+            Some(speed) => speed.record(rtf, threads),
+            None => {
+                self.model_speeds
+                    .insert(model_filename.to_string(), ModelSpeed::new(rtf, threads));
+            }
+        }
+    }
+
+    /// Remember `language` (None = auto-detect) as the one last used with
+    /// `model_filename`.
+    pub fn remember_language(&mut self, model_filename: &str, language: Option<&str>) {
+        self.model_overrides
+            .entry(model_filename.to_string())
+            .or_default()
+            .language = Some(language.unwrap_or("auto").to_string());
+    }
+
+    /// Model mapped to `language` (None = auto-detect), if any.
+    pub fn model_for_language(&self, language: Option<&str>) -> Option<&str> {
+        self.language_models
+            .get(language.unwrap_or("auto"))
+            .map(String::as_str)
+    }
+
+    /// High-pass cutoff for the capture path, if the filter is on.
+    pub fn high_pass_cutoff(&self) -> Option<f32> {
+        self.high_pass.then_some(self.high_pass_hz)
+    }
+
+    /// Primary AI provider followed by the fallbacks, skipping unconfigured entries.
+    pub fn ai_chain(&self) -> Vec<AiSettings> {
+        std::iter::once(&self.ai)
+            .chain(self.ai_fallbacks.iter())
+            .filter(|a| a.provider != AiProvider::None)
+            .cloned()
+            .collect()
+    }
+
+    /// User preset or built-in preset by name.
+    pub fn find_preset(&self, name: &str) -> Option<PromptPreset> {
+        self.prompt_presets
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .or_else(|| formatting::builtin_presets().into_iter().find(|p| p.name == name))
+    }
+
+    /// Provider chain for one session's formatting choice. A preset keeps the
+    /// configured providers but swaps in its prompt. Empty while `ai_enabled`
+    /// is off.
+    pub fn ai_chain_for(&self, choice: &FormatChoice) -> Vec<AiSettings> {
+        if !self.ai_enabled {
+            return Vec::new();
+        }
+        match choice {
+            FormatChoice::Configured => self.ai_chain(),
+            FormatChoice::Raw => Vec::new(),
+            FormatChoice::Preset(name) => {
+                let Some(preset) = self.find_preset(name) else {
+                    log::warn!("Prompt preset {:?} not found, using the default prompt", name);
+                    return self.ai_chain();
+                };
+                self.ai_chain()
+                    .into_iter()
+                    .map(|mut ai| {
+                        ai.prompt = preset.prompt.clone();
+                        ai
+                    })
+                    .collect()
+            }
+        }
+    }
+
     pub fn file_path(data_dir: &PathBuf) -> PathBuf {
         data_dir.join("settings.json")
     }
@@ -57,3 +695,153 @@ impl Settings {
         Ok(())
     }
 }
+
+/// Quiet time after the last change before a scheduled save is written.
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+/// Longest a change waits while changes keep coming (a slider being dragged).
+const SAVE_MAX_DELAY: Duration = Duration::from_secs(2);
+/// How long `flush` waits for the write to finish.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+enum SaveRequest {
+    Save,
+    Flush(mpsc::Sender<()>),
+}
+
+/// Coalesces settings saves from rapid changes (a dragged volume slider,
+/// typing in a text field) into one write. A save is written SAVE_DEBOUNCE
+/// after the last change, or SAVE_MAX_DELAY after the first unsaved one,
+/// whichever comes first. `write` saves the current settings, so the last
+/// value always lands on disk. Every settings change is saved through here;
+/// a pending save is flushed on exit.
+pub struct SaveScheduler {
+    sender: Mutex<mpsc::Sender<SaveRequest>>,
+}
+
+impl SaveScheduler {
+    pub fn new(write: impl Fn() + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            // First and last change since the last write
+            let mut pending: Option<(Instant, Instant)> = None;
+            loop {
+                let request = match pending {
+                    Some((first, last)) => {
+                        let due = (last + SAVE_DEBOUNCE).min(first + SAVE_MAX_DELAY);
+                        rx.recv_timeout(due.saturating_duration_since(Instant::now()))
+                    }
+                    None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+                };
+                match request {
+                    Ok(SaveRequest::Save) => {
+                        let now = Instant::now();
+                        pending = Some((pending.map_or(now, |(first, _)| first), now));
+                    }
+                    Ok(SaveRequest::Flush(done)) => {
+                        if pending.take().is_some() {
+                            write();
+                        }
+                        let _ = done.send(());
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        pending = None;
+                        write();
+                    }
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        if pending.is_some() {
+                            write();
+                        }
+                        break;
+                    }
+                }
+            }
+        });
+
+        Self {
+            sender: Mutex::new(tx),
+        }
+    }
+
+    /// Save the settings soon, together with any other changes made meanwhile.
+    pub fn schedule(&self) {
+        let _ = self.sender.lock().unwrap().send(SaveRequest::Save);
+    }
+
+    /// Write a pending save now and wait for it. Called on exit.
+    pub fn flush(&self) {
+        let (done_tx, done_rx) = mpsc::channel();
+        if self.sender.lock().unwrap().send(SaveRequest::Flush(done_tx)).is_ok()
+            && done_rx.recv_timeout(FLUSH_TIMEOUT).is_err()
+        {
+            log::warn!("Pending settings save did not finish before exit");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_model(model: &str) -> Settings {
+        Settings {
+            model_filename: model.to_string(),
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn remember_language_is_per_model() {
+        let mut s = with_model("ggml-small.bin");
+        s.remember_language("ggml-small.bin", Some("de"));
+        s.remember_language("ggml-medium.bin", None);
+        assert_eq!(s.active_transcription().language.as_deref(), Some("de"));
+        assert_eq!(s.transcription_for("ggml-medium.bin").language, None);
+        assert_eq!(
+            s.model_overrides["ggml-medium.bin"].language.as_deref(),
+            Some("auto")
+        );
+    }
+
+    #[test]
+    fn setting_the_active_language_remembers_it_for_the_model() {
+        let mut s = with_model("ggml-small.bin");
+        s.remember_language("ggml-small.bin", Some("de"));
+
+        // The UI sends back what get_transcription_settings showed
+        let mut edited = s.active_transcription();
+        edited.best_of = 3;
+        s.set_active_transcription(edited);
+        assert_eq!(s.active_transcription().language.as_deref(), Some("de"));
+
+        let mut edited = s.active_transcription();
+        edited.language = Some("fr".to_string());
+        s.set_active_transcription(edited);
+        assert_eq!(s.active_transcription().language.as_deref(), Some("fr"));
+        assert_eq!(s.active_transcription().best_of, 3);
+    }
+
+    #[test]
+    fn overridden_fields_update_the_override_not_the_global_value() {
+        let mut s = with_model("ggml-small.bin");
+        s.transcription.threads = 4;
+        s.model_overrides.insert(
+            "ggml-small.bin".to_string(),
+            ModelOverrides {
+                threads: Some(8),
+                ..ModelOverrides::default()
+            },
+        );
+
+        let mut edited = s.active_transcription();
+        assert_eq!(edited.threads, 8);
+        edited.threads = 6;
+        edited.initial_prompt = "Names: Ada.".to_string();
+        s.set_active_transcription(edited);
+
+        assert_eq!(s.transcription.threads, 4);
+        assert_eq!(s.model_overrides["ggml-small.bin"].threads, Some(6));
+        assert_eq!(s.transcription.initial_prompt, "Names: Ada.");
+        assert_eq!(s.active_transcription().threads, 6);
+    }
+}
@@ -1,26 +1,150 @@
+use crate::formatting::FormatChoice;
+use crate::system::window::WindowHandle;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Number of past dictations kept in memory for re-injection.
+pub const HISTORY_LIMIT: usize = 20;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AppStatus {
     Idle,
     Recording,
+    /// Capture stopped mid-recording; the buffer is kept for resume.
+    Paused,
     Transcribing,
     Formatting,
     Injecting,
+    /// The microphone is taken by noise calibration or a mic test.
+    Measuring,
     Error(String),
 }
 
+impl AppStatus {
+    /// True while a recording session is open, capturing or paused.
+    pub fn in_recording(&self) -> bool {
+        matches!(self, AppStatus::Recording | AppStatus::Paused)
+    }
+
+    /// Whether the status may change to `next`. A dictation goes Idle ->
+    /// Recording (<-> Paused) -> Transcribing -> [Formatting] -> Injecting ->
+    /// Idle and can drop back to Idle or Error from any step. A new recording
+    /// starts only from Idle or Error, never while the last one is still
+    /// being transcribed, formatted or pasted. Measuring is entered from
+    /// Idle or Error and only left for Idle or Error.
+    ///
+    /// Work started outside a dictation enters its step straight from Idle
+    /// or Error: Transcribing for a dropped file or a model benchmark,
+    /// Formatting for reformat_last, Injecting for a re-inject, test paste or
+    /// the inject command. These, like the mic test and noise calibration,
+    /// claim their status through AppState::claim_from_idle.
+    pub fn allows(&self, next: &AppStatus) -> bool {
+        use AppStatus::*;
+        matches!(
+            (self, next),
+            (_, Idle | Error(_))
+                | (Idle | Error(_), Recording)
+                | (Recording, Paused)
+                | (Paused, Recording)
+                | (Recording | Paused, Transcribing)
+                | (Transcribing, Formatting | Injecting)
+                | (Formatting, Injecting)
+                | (Idle | Error(_), Measuring)
+                | (Idle | Error(_), Transcribing | Formatting | Injecting)
+        )
+    }
+}
+
 impl Default for AppStatus {
     fn default() -> Self {
         AppStatus::Idle
     }
 }
 
+/// The text sent to AI formatting alongside what came back.
+/// When formatting is off both fields are the same.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TranscriptionPair {
+    pub raw: String,
+    pub formatted: String,
+}
+
+/// Payload of "transcription-progress" while a long recording is
+/// transcribed chunk by chunk. `chunk` counts from 1.
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionProgress {
+    pub chunk: usize,
+    pub total: usize,
+}
+
+/// Payload of "file-transcription" while dropped files are transcribed one
+/// after another: sent when a file starts (no text or error yet) and again
+/// when it finishes. `index` counts from 1.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileTranscription {
+    pub path: String,
+    pub index: usize,
+    pub total: usize,
+    pub text: Option<String>,
+    pub error: Option<String>,
+}
+
+/// A finished dictation kept in the recent history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Final text as it was pasted.
+    pub text: String,
+    /// Transcription before AI formatting.
+    pub raw: String,
+    /// Local time the dictation finished, RFC 3339.
+    pub created_at: String,
+}
+
 pub struct AppState {
     pub status: AppStatus,
     pub model_loaded: bool,
+    /// A model load is in progress; transcription waits for it.
+    pub model_loading: bool,
+    /// The model was unloaded after sitting idle and is reloaded on demand.
+    pub model_idle_unloaded: bool,
+    /// When the model last loaded or transcribed, for idle_unload_minutes.
+    pub model_last_used: Instant,
     pub last_transcription: String,
+    /// Transcription before AI formatting, kept for diffing and re-formatting.
+    pub last_raw_transcription: String,
     pub device_sample_rate: u32,
+    /// Incremented on every recording start so background tasks can tell
+    /// whether the recording they were spawned for is still the current one.
+    pub recording_session: u64,
+    /// Language settled for a recording session, as (session, language), so
+    /// the streaming preview and the final transcription detect it once.
+    /// A None language leaves Whisper to detect it per window.
+    pub recording_language: Option<(u64, Option<String>)>,
+    /// Hotkey being held for the current recording (None = started from tray/UI).
+    pub recording_hotkey: Option<String>,
+    /// True while a dictation hotkey is physically down; filters key-repeat presses.
+    pub hotkey_down: bool,
+    /// When the hotkey that started the current recording was pressed.
+    pub hotkey_pressed_at: Option<Instant>,
+    /// The recording keeps going after the hotkey is released (toggle mode,
+    /// or a tap in hybrid mode) and stops on the next press.
+    pub recording_latched: bool,
+    /// Press and release times of a hold-to-talk release whose stop is held
+    /// back in case the key is pressed again right away.
+    pub pending_release: Option<(Instant, Instant)>,
+    /// Formatting selected when the current recording started.
+    pub recording_format: FormatChoice,
+    /// End of the last successfully injected text and when it was pasted,
+    /// used to continue sentences across dictations.
+    pub last_injected_tail: Option<(String, Instant)>,
+    /// Window the last dictation was pasted into, if it could be determined.
+    pub last_injected_window: Option<WindowHandle>,
+    /// Characters typed by the last paste that undo_last_injection can still
+    /// remove. Cleared once undone or when focus moved on with Tab.
+    pub last_injected_chars: Option<usize>,
+    /// Recent dictations, newest first.
+    pub history: VecDeque<HistoryEntry>,
 }
 
 impl Default for AppState {
@@ -28,8 +152,137 @@ impl Default for AppState {
         Self {
             status: AppStatus::Idle,
             model_loaded: false,
+            model_loading: false,
+            model_idle_unloaded: false,
+            model_last_used: Instant::now(),
             last_transcription: String::new(),
+            last_raw_transcription: String::new(),
             device_sample_rate: 48000,
+            recording_session: 0,
+            recording_language: None,
+            recording_hotkey: None,
+            hotkey_down: false,
+            hotkey_pressed_at: None,
+            recording_latched: false,
+            pending_release: None,
+            recording_format: FormatChoice::Configured,
+            last_injected_tail: None,
+            last_injected_window: None,
+            last_injected_chars: None,
+            history: VecDeque::new(),
+        }
+    }
+}
+
+impl AppState {
+    /// Move to `next` for work that runs on its own rather than as a step of
+    /// a dictation. Only from Idle, or Error once the failure is shown: in
+    /// any other status the step belongs to the dictation or measurement in
+    /// flight. Returns whether it was claimed.
+    pub fn claim_from_idle(&mut self, next: AppStatus) -> bool {
+        let settled = matches!(self.status, AppStatus::Idle | AppStatus::Error(_));
+        if !settled || !self.status.allows(&next) {
+            return false;
+        }
+        self.status = next;
+        true
+    }
+
+    /// Record a finished dictation, dropping the oldest past HISTORY_LIMIT.
+    pub fn push_history(&mut self, text: String, raw: String) {
+        self.history.push_front(HistoryEntry {
+            text,
+            raw,
+            created_at: chrono::Local::now().to_rfc3339(),
+        });
+        self.history.truncate(HISTORY_LIMIT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transitions_follow_the_dictation_flow() {
+        use AppStatus::*;
+        let error = || Error("mic unplugged".to_string());
+        let all = [
+            Idle,
+            Recording,
+            Paused,
+            Transcribing,
+            Formatting,
+            Injecting,
+            Measuring,
+            error(),
+        ];
+        let allowed = [
+            (Idle, Recording),
+            (error(), Recording),
+            (Recording, Paused),
+            (Paused, Recording),
+            (Recording, Transcribing),
+            (Paused, Transcribing),
+            (Transcribing, Formatting),
+            (Transcribing, Injecting),
+            (Formatting, Injecting),
+            (Idle, Measuring),
+            (error(), Measuring),
+            (Idle, Transcribing),
+            (Idle, Formatting),
+            (Idle, Injecting),
+            (error(), Transcribing),
+            (error(), Formatting),
+            (error(), Injecting),
+        ];
+        for from in &all {
+            for to in &all {
+                // Anything can be cancelled or fail
+                let expected = matches!(to, Idle | Error(_))
+                    || allowed.iter().any(|(f, t)| f == from && t == to);
+                assert_eq!(from.allows(to), expected, "{:?} -> {:?}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn no_new_recording_until_the_last_one_is_delivered() {
+        use AppStatus::*;
+        for busy in [Transcribing, Formatting, Injecting, Measuring] {
+            assert!(!busy.allows(&Recording), "{:?}", busy);
+        }
+    }
+
+    #[test]
+    fn standalone_work_only_claims_a_settled_status() {
+        use AppStatus::*;
+        let mut state = AppState::default();
+        assert!(state.claim_from_idle(Injecting));
+        assert_eq!(state.status, Injecting);
+
+        // A dictation's own Transcribing -> Injecting step isn't up for grabs
+        state.status = Transcribing;
+        assert!(!state.claim_from_idle(Injecting));
+        assert_eq!(state.status, Transcribing);
+
+        state.status = Measuring;
+        assert!(!state.claim_from_idle(Transcribing));
+        state.status = Idle;
+        assert!(!state.claim_from_idle(Paused));
+        assert_eq!(state.status, Idle);
+    }
+
+    #[test]
+    fn standalone_work_can_start_after_an_error() {
+        use AppStatus::*;
+        let mut state = AppState::default();
+        for next in [Measuring, Transcribing, Formatting, Injecting] {
+            state.status = Error("mic unplugged".to_string());
+            assert!(state.claim_from_idle(next.clone()), "{:?}", next);
+            assert_eq!(state.status, next);
         }
+        state.status = Error("mic unplugged".to_string());
+        assert!(!state.claim_from_idle(Paused));
     }
 }
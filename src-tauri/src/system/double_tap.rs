@@ -0,0 +1,226 @@
+//! Alternative trigger: tap a lone modifier (e.g. Ctrl) twice to toggle
+//! dictation. The global-shortcut plugin only handles combos with a regular
+//! key, so this watches the keyboard hook's press and release events instead.
+
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::keyboard::{self, KeyEvent};
+use crate::state::AppState;
+
+/// A press held longer than this is a hold, not a tap.
+const TAP_MAX_MS: u64 = 300;
+
+/// Whether double-tap detection works on this platform.
+pub fn is_supported() -> bool {
+    keyboard::can_listen_keys()
+}
+
+/// The keyboard hook and detector thread for the configured modifier,
+/// replaced whenever the setting changes. Nothing runs while it is off.
+#[derive(Default)]
+pub struct DoubleTap(Mutex<Option<keyboard::KeyListener>>);
+
+impl DoubleTap {
+    /// Listen for double taps of `modifier` within `window_ms`, or stop
+    /// listening when `modifier` is empty.
+    pub fn configure(&self, app: &AppHandle, modifier: &str, window_ms: u64) {
+        let mut listener = self.0.lock().unwrap();
+        // Removes the old hook, whose detector thread ends with its channel
+        *listener = None;
+        let Some(vk) = keyboard::modifier_virtual_key(modifier) else {
+            return;
+        };
+        let (events, keys) = mpsc::channel::<KeyEvent>();
+        *listener = keyboard::listen_keys(events);
+        if listener.is_none() {
+            log::warn!("Couldn't listen for {} double taps", modifier);
+            return;
+        }
+        let window = Duration::from_millis(window_ms);
+        let app = app.clone();
+        thread::spawn(move || {
+            let mut detector = Detector::default();
+            for key in keys {
+                // Other modifiers neither tap nor spoil a tap, so Ctrl can be
+                // double-tapped with Shift resting on the keyboard
+                if key.vk != vk && keyboard::is_modifier(key.vk) {
+                    continue;
+                }
+                if detector.key(key.vk == vk, key.down, window, key.at) {
+                    let state = app.state::<Mutex<AppState>>();
+                    let recording = state.lock().unwrap().status.in_recording();
+                    toggle(&app, recording);
+                }
+            }
+        });
+    }
+}
+
+/// Same as the tray's start/stop: a toggle with no hotkey being held.
+fn toggle(app: &AppHandle, recording: bool) {
+    if recording {
+        log::info!("Modifier double-tap - stopping recording");
+        let _ = app.emit("tray-stop-recording", ());
+    } else {
+        log::info!("Modifier double-tap - starting recording");
+        let _ = app.emit("tray-start-recording", ());
+    }
+}
+
+/// Tracks clean taps of the modifier: pressed and released quickly with no
+/// other key involved, so shortcuts like Ctrl+C never count.
+#[derive(Default)]
+struct Detector {
+    /// When the modifier went down, while it is held.
+    down_since: Option<Instant>,
+    /// Another key was pressed during the current hold.
+    dirty: bool,
+    /// Press time of the previous clean tap.
+    last_tap: Option<Instant>,
+}
+
+impl Detector {
+    /// Feed one key event; true when it completes a double tap.
+    fn key(&mut self, is_modifier: bool, down: bool, window: Duration, now: Instant) -> bool {
+        if !is_modifier {
+            if down {
+                // A shortcut spoils the current tap, typing between the taps
+                // cancels the sequence
+                if self.down_since.is_some() {
+                    self.dirty = true;
+                } else {
+                    self.last_tap = None;
+                }
+            }
+            return false;
+        }
+
+        if down {
+            // Held keys auto-repeat their down event; keep the first one
+            if self.down_since.is_none() {
+                self.down_since = Some(now);
+                self.dirty = false;
+            }
+            return false;
+        }
+
+        let Some(pressed_at) = self.down_since.take() else {
+            return false;
+        };
+        let clean = !self.dirty && now - pressed_at <= Duration::from_millis(TAP_MAX_MS);
+        if !clean {
+            self.last_tap = None;
+            return false;
+        }
+        match self.last_tap.take() {
+            Some(previous) if pressed_at - previous <= window => true,
+            _ => {
+                self.last_tap = Some(pressed_at);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_millis(400);
+
+    /// Feed (is_modifier, down, ms since start) events and report the ones
+    /// that completed a double tap.
+    fn fire(events: &[(bool, bool, u64)]) -> Vec<u64> {
+        let start = Instant::now();
+        let mut detector = Detector::default();
+        events
+            .iter()
+            .filter(|&&(is_modifier, down, ms)| {
+                let at = start + Duration::from_millis(ms);
+                detector.key(is_modifier, down, WINDOW, at)
+            })
+            .map(|&(_, _, ms)| ms)
+            .collect()
+    }
+
+    #[test]
+    fn clean_double_tap_fires_on_second_release() {
+        let taps = [(true, true, 0), (true, false, 80), (true, true, 200), (true, false, 260)];
+        assert_eq!(fire(&taps), vec![260]);
+    }
+
+    #[test]
+    fn auto_repeat_counts_as_one_press() {
+        let taps = [
+            (true, true, 0),
+            (true, true, 30),
+            (true, false, 80),
+            (true, true, 200),
+            (true, false, 260),
+        ];
+        assert_eq!(fire(&taps), vec![260]);
+    }
+
+    #[test]
+    fn shortcut_does_not_count_as_tap() {
+        // Ctrl+C, then one tap
+        let taps = [
+            (true, true, 0),
+            (false, true, 40),
+            (false, false, 70),
+            (true, false, 90),
+            (true, true, 200),
+            (true, false, 260),
+        ];
+        assert!(fire(&taps).is_empty());
+    }
+
+    #[test]
+    fn slow_second_tap_starts_over() {
+        let taps = [
+            (true, true, 0),
+            (true, false, 80),
+            (true, true, 500),
+            (true, false, 560),
+            (true, true, 700),
+            (true, false, 760),
+        ];
+        assert_eq!(fire(&taps), vec![760]);
+    }
+
+    #[test]
+    fn long_hold_is_not_a_tap() {
+        let taps = [(true, true, 0), (true, false, 80), (true, true, 200), (true, false, 600)];
+        assert!(fire(&taps).is_empty());
+    }
+
+    #[test]
+    fn typing_between_taps_cancels() {
+        let taps = [
+            (true, true, 0),
+            (true, false, 80),
+            (false, true, 120),
+            (false, false, 150),
+            (true, true, 200),
+            (true, false, 260),
+        ];
+        assert!(fire(&taps).is_empty());
+    }
+
+    #[test]
+    fn triple_tap_fires_once() {
+        let taps = [
+            (true, true, 0),
+            (true, false, 60),
+            (true, true, 150),
+            (true, false, 210),
+            (true, true, 300),
+            (true, false, 360),
+        ];
+        assert_eq!(fire(&taps), vec![210]);
+    }
+}
@@ -0,0 +1,52 @@
+//! Append dictations to a text file, e.g. a daily journal at
+//! "C:\Notes\journal-{date}.md".
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+
+use crate::postprocess::template::expand_path_placeholders;
+
+/// Expand placeholders in the configured path for the current moment.
+pub fn resolve_path(path_template: &str) -> PathBuf {
+    PathBuf::from(expand_path_placeholders(path_template.trim(), &Local::now()))
+}
+
+/// Reject paths that would land somewhere unexpected (relative to the app's
+/// working directory) or that point at a directory.
+pub fn validate_path(path_template: &str) -> Result<(), String> {
+    if path_template.trim().is_empty() {
+        return Err("Output file path must not be empty".to_string());
+    }
+    let path = resolve_path(path_template);
+    if !path.is_absolute() {
+        return Err(format!("Output file path must be absolute: {}", path.display()));
+    }
+    if path.is_dir() {
+        return Err(format!("Output file path is a directory: {}", path.display()));
+    }
+    Ok(())
+}
+
+/// Append `text` under a timestamp line, creating the file and its parent
+/// directories if needed. Returns the file written to.
+pub fn append_dictation(path_template: &str, text: &str) -> Result<PathBuf, String> {
+    let path = resolve_path(path_template);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+
+    let entry = format!("{}\n{}\n\n", Local::now().format("%Y-%m-%d %H:%M"), text.trim_end());
+    append(&path, &entry).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+fn append(path: &Path, entry: &str) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    file.write_all(entry.as_bytes())
+}
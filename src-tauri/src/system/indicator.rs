@@ -0,0 +1,145 @@
+//! Small always-on-top status pill shown while dictating, so there is visual
+//! feedback when the main window is hidden.
+
+use std::sync::Mutex;
+use tauri::{
+    AppHandle, Manager, Monitor, PhysicalPosition, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+};
+
+use crate::settings::{IndicatorMonitor, IndicatorPosition, Settings};
+use crate::system::window;
+use crate::state::{AppState, AppStatus};
+
+const INDICATOR_LABEL: &str = "indicator";
+
+/// Logical size of the pill.
+const WIDTH: f64 = 132.0;
+const HEIGHT: f64 = 32.0;
+/// Logical gap between the pill and the screen edges.
+const MARGIN: f64 = 16.0;
+
+/// Serializes updates so a late "show" can't land after a newer "hide".
+static UPDATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Bring the indicator in line with the current app status: visible with the
+/// status while a dictation is in progress, hidden otherwise or when disabled.
+pub fn update(app: &AppHandle) {
+    // Window creation goes through the event loop, so don't block the caller
+    // (which may be the event loop itself, e.g. a hotkey handler)
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let _guard = UPDATE_LOCK.lock().unwrap();
+        let (enabled, position, monitor) = {
+            let settings = app.state::<Mutex<Settings>>();
+            let s = settings.lock().unwrap();
+            (s.show_indicator, s.indicator_position, s.indicator_monitor.clone())
+        };
+        let status = match app.state::<Mutex<AppState>>().lock().unwrap().status {
+            AppStatus::Recording => "Recording",
+            AppStatus::Paused => "Paused",
+            AppStatus::Transcribing => "Transcribing",
+            AppStatus::Formatting => "Formatting",
+            AppStatus::Injecting => "Injecting",
+            // Not a dictation
+            AppStatus::Idle | AppStatus::Measuring | AppStatus::Error(_) => "",
+        };
+        if !enabled || status.is_empty() {
+            hide(&app);
+            return;
+        }
+
+        let window = match app.get_webview_window(INDICATOR_LABEL) {
+            Some(window) => {
+                let _ = window.eval(format!("setStatus({:?})", status));
+                window
+            }
+            None => match create(&app, status) {
+                Ok(window) => window,
+                Err(e) => {
+                    log::warn!("Failed to create recording indicator: {}", e);
+                    return;
+                }
+            },
+        };
+        place(&window, position, &monitor);
+        let _ = window.show();
+    });
+}
+
+/// Show the seconds left before the recording is stopped automatically
+/// (from the auto-stop watchdog). Cleared by the next status update.
+pub fn countdown(app: &AppHandle, seconds: u64) {
+    if let Some(window) = app.get_webview_window(INDICATOR_LABEL) {
+        let _ = window.eval(format!("setCountdown({})", seconds));
+    }
+}
+
+/// Hide the indicator if it exists.
+pub fn hide(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(INDICATOR_LABEL) {
+        let _ = window.hide();
+    }
+}
+
+fn create(app: &AppHandle, status: &str) -> tauri::Result<WebviewWindow> {
+    let url = WebviewUrl::App(format!("indicator.html#{}", status).into());
+    WebviewWindowBuilder::new(app, INDICATOR_LABEL, url)
+        .title("Wispr Local")
+        .inner_size(WIDTH, HEIGHT)
+        .decorations(false)
+        .resizable(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .shadow(false)
+        // Never take focus: the dictation has to land in the user's app
+        .focusable(false)
+        .focused(false)
+        .visible(false)
+        .build()
+}
+
+/// The display `choice` points at, falling back to the one under the
+/// cursor and then the primary display.
+fn pick_monitor(window: &WebviewWindow, choice: &IndicatorMonitor) -> Option<Monitor> {
+    let chosen = match choice {
+        IndicatorMonitor::ActiveWindow => window::foreground_window()
+            .and_then(window::window_center)
+            .and_then(|(x, y)| window.monitor_from_point(x as f64, y as f64).ok().flatten()),
+        IndicatorMonitor::Cursor => None,
+        IndicatorMonitor::Primary => window.primary_monitor().ok().flatten(),
+        IndicatorMonitor::Named(name) => window
+            .available_monitors()
+            .ok()
+            .and_then(|monitors| monitors.into_iter().find(|m| m.name() == Some(name))),
+    };
+    chosen
+        .or_else(|| {
+            let cursor = window.cursor_position().ok()?;
+            window.monitor_from_point(cursor.x, cursor.y).ok().flatten()
+        })
+        .or_else(|| window.primary_monitor().ok().flatten())
+}
+
+/// Put the window in a corner of the chosen monitor's work area
+/// (which excludes the taskbar).
+fn place(window: &WebviewWindow, position: IndicatorPosition, choice: &IndicatorMonitor) {
+    let Some(monitor) = pick_monitor(window, choice) else {
+        return;
+    };
+    let scale = monitor.scale_factor();
+    let area = monitor.work_area();
+    let (w, h) = ((WIDTH * scale) as i32, (HEIGHT * scale) as i32);
+    let margin = (MARGIN * scale) as i32;
+
+    let left = area.position.x + margin;
+    let right = area.position.x + area.size.width as i32 - w - margin;
+    let top = area.position.y + margin;
+    let bottom = area.position.y + area.size.height as i32 - h - margin;
+    let (x, y) = match position {
+        IndicatorPosition::TopLeft => (left, top),
+        IndicatorPosition::TopRight => (right, top),
+        IndicatorPosition::BottomLeft => (left, bottom),
+        IndicatorPosition::BottomRight => (right, bottom),
+    };
+    let _ = window.set_position(PhysicalPosition::new(x, y));
+}
@@ -0,0 +1,270 @@
+/// Check whether every key of a hotkey string like "Ctrl+Shift+Space" is
+/// physically held down right now.
+/// Returns None when the state can't be determined on this platform.
+pub fn is_hotkey_held(hotkey: &str) -> Option<bool> {
+    let keys = hotkey_virtual_keys(hotkey)?;
+    all_keys_down(&keys)
+}
+
+/// Virtual key code of a lone modifier name ("ctrl", "shift", "alt", "super").
+pub fn modifier_virtual_key(name: &str) -> Option<i32> {
+    match name.trim().to_lowercase().as_str() {
+        key @ ("ctrl" | "control" | "shift" | "alt" | "super" | "win" | "meta" | "cmd") => {
+            virtual_key(key)
+        }
+        _ => None,
+    }
+}
+
+/// A physical key press or release seen by the keyboard hook.
+#[derive(Debug, Clone, Copy)]
+pub struct KeyEvent {
+    /// Virtual key code, with left/right modifiers folded into the plain
+    /// ones (Right Ctrl reports as Ctrl).
+    pub vk: i32,
+    pub down: bool,
+    pub at: std::time::Instant,
+}
+
+/// Whether this key code is Ctrl, Shift, Alt or a Win key.
+pub fn is_modifier(vk: i32) -> bool {
+    matches!(vk, 0x10 | 0x11 | 0x12 | 0x5B)
+}
+
+/// Fold the left/right variants reported by the hook into the codes
+/// virtual_key uses.
+#[cfg(windows)]
+fn plain_modifier(vk: i32) -> i32 {
+    match vk {
+        0xA0 | 0xA1 => 0x10,
+        0xA2 | 0xA3 => 0x11,
+        0xA4 | 0xA5 => 0x12,
+        0x5C => 0x5B,
+        other => other,
+    }
+}
+
+/// Whether listen_keys works on this platform.
+pub fn can_listen_keys() -> bool {
+    cfg!(windows)
+}
+
+/// A running keyboard hook; dropping it removes the hook.
+pub struct KeyListener {
+    #[cfg(windows)]
+    thread_id: u32,
+}
+
+/// Send every physical (not simulated) key press and release to `events`
+/// from a system-wide keyboard hook, until the returned listener is
+/// dropped. Keys are reported, never swallowed.
+/// None when unsupported here or already listening.
+pub fn listen_keys(events: std::sync::mpsc::Sender<KeyEvent>) -> Option<KeyListener> {
+    install_key_hook(events)
+}
+
+/// Map a hotkey string to Windows virtual key codes.
+fn hotkey_virtual_keys(hotkey: &str) -> Option<Vec<i32>> {
+    hotkey
+        .split('+')
+        .map(|part| virtual_key(&part.trim().to_lowercase()))
+        .collect()
+}
+
+fn virtual_key(key: &str) -> Option<i32> {
+    let vk = match key {
+        "ctrl" | "control" => 0x11,
+        "shift" => 0x10,
+        "alt" => 0x12,
+        "super" | "win" | "meta" | "cmd" => 0x5B,
+        "space" => 0x20,
+        "enter" | "return" => 0x0D,
+        "tab" => 0x09,
+        "escape" | "esc" => 0x1B,
+        "backspace" => 0x08,
+        "delete" | "del" => 0x2E,
+        "insert" => 0x2D,
+        "home" => 0x24,
+        "end" => 0x23,
+        "pageup" => 0x21,
+        "pagedown" => 0x22,
+        "left" => 0x25,
+        "up" => 0x26,
+        "right" => 0x27,
+        "down" => 0x28,
+        "`" | "backquote" => 0xC0,
+        "-" | "minus" => 0xBD,
+        "=" | "equal" => 0xBB,
+        "[" | "bracketleft" => 0xDB,
+        "]" | "bracketright" => 0xDD,
+        "\\" | "backslash" => 0xDC,
+        ";" | "semicolon" => 0xBA,
+        "'" | "quote" => 0xDE,
+        "," | "comma" => 0xBC,
+        "." | "period" => 0xBE,
+        "/" | "slash" => 0xBF,
+        other => {
+            let mut chars = other.chars();
+            match (chars.next(), chars.next()) {
+                // Single digit or letter: VK codes match uppercase ASCII
+                (Some(c), None) if c.is_ascii_alphanumeric() => c.to_ascii_uppercase() as i32,
+                // F1-F12
+                (Some('f'), Some(_)) => {
+                    let n: i32 = other[1..].parse().ok()?;
+                    if !(1..=12).contains(&n) {
+                        return None;
+                    }
+                    0x70 + n - 1
+                }
+                _ => return None,
+            }
+        }
+    };
+    Some(vk)
+}
+
+#[cfg(windows)]
+#[link(name = "user32")]
+extern "system" {
+    fn GetAsyncKeyState(v_key: i32) -> i16;
+}
+
+#[cfg(windows)]
+fn all_keys_down(keys: &[i32]) -> Option<bool> {
+    // High bit set = key is currently down
+    let down = keys
+        .iter()
+        .all(|&vk| (unsafe { GetAsyncKeyState(vk) } as u16 & 0x8000) != 0);
+    Some(down)
+}
+
+/// Where the hook procedure sends events; it gets no user data pointer.
+/// Some while a KeyListener is alive.
+#[cfg(windows)]
+static KEY_EVENTS: std::sync::Mutex<Option<std::sync::mpsc::Sender<KeyEvent>>> =
+    std::sync::Mutex::new(None);
+
+#[cfg(windows)]
+#[repr(C)]
+struct KbdLlHookStruct {
+    vk_code: u32,
+    scan_code: u32,
+    flags: u32,
+    time: u32,
+    extra_info: usize,
+}
+
+#[cfg(windows)]
+#[repr(C)]
+struct Msg {
+    hwnd: isize,
+    message: u32,
+    w_param: usize,
+    l_param: isize,
+    time: u32,
+    pt_x: i32,
+    pt_y: i32,
+}
+
+#[cfg(windows)]
+type HookProc = unsafe extern "system" fn(i32, usize, isize) -> isize;
+
+#[cfg(windows)]
+#[link(name = "user32")]
+extern "system" {
+    fn SetWindowsHookExW(id_hook: i32, hook: HookProc, module: isize, thread_id: u32) -> isize;
+    fn CallNextHookEx(hook: isize, code: i32, w_param: usize, l_param: isize) -> isize;
+    fn UnhookWindowsHookEx(hook: isize) -> i32;
+    fn GetMessageW(msg: *mut Msg, hwnd: isize, filter_min: u32, filter_max: u32) -> i32;
+    fn PeekMessageW(msg: *mut Msg, hwnd: isize, min: u32, max: u32, remove: u32) -> i32;
+    fn PostThreadMessageW(thread_id: u32, msg: u32, w_param: usize, l_param: isize) -> i32;
+}
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetModuleHandleW(name: *const u16) -> isize;
+    fn GetCurrentThreadId() -> u32;
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn key_hook(code: i32, w_param: usize, l_param: isize) -> isize {
+    const WM_KEYDOWN: usize = 0x0100;
+    const WM_SYSKEYDOWN: usize = 0x0104;
+    // Set on keystrokes sent with SendInput, e.g. our own Ctrl+V
+    const LLKHF_INJECTED: u32 = 0x10;
+
+    if code >= 0 {
+        let info = &*(l_param as *const KbdLlHookStruct);
+        if info.flags & LLKHF_INJECTED == 0 {
+            if let Some(events) = KEY_EVENTS.lock().unwrap().as_ref() {
+                let _ = events.send(KeyEvent {
+                    vk: plain_modifier(info.vk_code as i32),
+                    down: matches!(w_param, WM_KEYDOWN | WM_SYSKEYDOWN),
+                    at: std::time::Instant::now(),
+                });
+            }
+        }
+    }
+    CallNextHookEx(0, code, w_param, l_param)
+}
+
+#[cfg(windows)]
+fn install_key_hook(events: std::sync::mpsc::Sender<KeyEvent>) -> Option<KeyListener> {
+    const WH_KEYBOARD_LL: i32 = 13;
+    const PM_NOREMOVE: u32 = 0;
+
+    {
+        let mut slot = KEY_EVENTS.lock().unwrap();
+        if slot.is_some() {
+            return None;
+        }
+        *slot = Some(events);
+    }
+    let (started, thread_id) = std::sync::mpsc::channel();
+    std::thread::spawn(move || unsafe {
+        // Make sure the thread has a message queue before anyone posts to it
+        let mut msg: Msg = std::mem::zeroed();
+        PeekMessageW(&mut msg, 0, 0, 0, PM_NOREMOVE);
+        let module = GetModuleHandleW(std::ptr::null());
+        let hook = SetWindowsHookExW(WH_KEYBOARD_LL, key_hook, module, 0);
+        if hook == 0 {
+            log::error!("Failed to install the keyboard hook");
+            let _ = started.send(None);
+            return;
+        }
+        let _ = started.send(Some(GetCurrentThreadId()));
+        // A low-level hook is called on the installing thread, which has to
+        // keep pumping messages until KeyListener's drop posts WM_QUIT
+        while GetMessageW(&mut msg, 0, 0, 0) > 0 {}
+        UnhookWindowsHookEx(hook);
+    });
+    match thread_id.recv().ok().flatten() {
+        Some(thread_id) => Some(KeyListener { thread_id }),
+        None => {
+            *KEY_EVENTS.lock().unwrap() = None;
+            None
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for KeyListener {
+    fn drop(&mut self) {
+        const WM_QUIT: u32 = 0x0012;
+        *KEY_EVENTS.lock().unwrap() = None;
+        unsafe {
+            PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn all_keys_down(_keys: &[i32]) -> Option<bool> {
+    None
+}
+
+#[cfg(not(windows))]
+fn install_key_hook(_events: std::sync::mpsc::Sender<KeyEvent>) -> Option<KeyListener> {
+    None
+}
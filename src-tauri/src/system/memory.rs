@@ -0,0 +1,50 @@
+/// Physical memory currently available to new allocations, in bytes.
+/// Returns None when it can't be determined on this platform.
+pub fn available_memory_bytes() -> Option<u64> {
+    platform::available_memory_bytes()
+}
+
+#[cfg(windows)]
+mod platform {
+    #[repr(C)]
+    struct MemoryStatusEx {
+        length: u32,
+        memory_load: u32,
+        total_phys: u64,
+        avail_phys: u64,
+        total_page_file: u64,
+        avail_page_file: u64,
+        total_virtual: u64,
+        avail_virtual: u64,
+        avail_extended_virtual: u64,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GlobalMemoryStatusEx(buffer: *mut MemoryStatusEx) -> i32;
+    }
+
+    pub fn available_memory_bytes() -> Option<u64> {
+        let mut status: MemoryStatusEx = unsafe { std::mem::zeroed() };
+        status.length = std::mem::size_of::<MemoryStatusEx>() as u32;
+        let ok = unsafe { GlobalMemoryStatusEx(&mut status) } != 0;
+        ok.then_some(status.avail_phys)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    pub fn available_memory_bytes() -> Option<u64> {
+        let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+        let line = meminfo.lines().find(|l| l.starts_with("MemAvailable:"))?;
+        let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kb * 1024)
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+mod platform {
+    pub fn available_memory_bytes() -> Option<u64> {
+        None
+    }
+}
@@ -1,3 +1,10 @@
+pub mod double_tap;
+pub mod file_output;
+pub mod indicator;
+pub mod keyboard;
+pub mod memory;
+pub mod post_command;
 pub mod sounds;
 pub mod text_injection;
 pub mod tray;
+pub mod window;
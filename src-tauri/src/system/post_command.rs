@@ -0,0 +1,173 @@
+//! Optional hook that hands every finished dictation to a user-configured
+//! command, e.g. a note app's CLI or a script. The command is run directly,
+//! not through a shell, so dictated text can never be interpreted as shell
+//! syntax; it still runs with the user's full permissions, though.
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Argument placeholder replaced by the dictation. Without it, the text is
+/// written to the command's stdin instead.
+pub const TEXT_PLACEHOLDER: &str = "{text}";
+
+/// A command still running after this long is killed.
+const TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Output kept for the log.
+const MAX_LOGGED_OUTPUT_CHARS: usize = 500;
+
+/// Split a command line into program and arguments. Whitespace separates
+/// arguments; single or double quotes group them (no escapes inside).
+pub fn parse(command: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    for c in command.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            None if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if let Some(q) = quote {
+        return Err(format!("Unclosed {} quote in the command", q));
+    }
+    if in_arg {
+        args.push(current);
+    }
+    if args.is_empty() {
+        return Err("Command must not be empty".to_string());
+    }
+    Ok(args)
+}
+
+/// Put `text` in place of every TEXT_PLACEHOLDER. Each argument stays one
+/// argument, whatever spaces or quotes the text holds.
+fn substitute(args: Vec<String>, text: &str) -> Vec<String> {
+    args.into_iter()
+        .map(|a| a.replace(TEXT_PLACEHOLDER, text))
+        .collect()
+}
+
+/// Run `command` with `text` and wait for it (up to TIMEOUT). Returns the
+/// start of its stdout; a failed start, non-zero exit or timeout is an error
+/// carrying stderr.
+pub fn run(command: &str, text: &str) -> Result<String, String> {
+    let args = parse(command)?;
+    let uses_placeholder = args.iter().any(|a| a.contains(TEXT_PLACEHOLDER));
+    let args = substitute(args, text);
+
+    let mut cmd = Command::new(&args[0]);
+    cmd.args(&args[1..])
+        .stdin(if uses_placeholder { Stdio::null() } else { Stdio::piped() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW: don't flash a console window for every dictation
+        cmd.creation_flags(0x0800_0000);
+    }
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start {:?}: {}", args[0], e))?;
+
+    // Separate threads, so a command that fills one pipe before reading
+    // the other can't deadlock us
+    if let Some(mut stdin) = child.stdin.take() {
+        let text = text.to_string();
+        thread::spawn(move || {
+            let _ = stdin.write_all(text.as_bytes());
+        });
+    }
+    let stdout = child.stdout.take().map(read_in_background);
+    let stderr = child.stderr.take().map(read_in_background);
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() >= TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("{:?} did not finish within {}s", args[0], TIMEOUT.as_secs()));
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(format!("Failed to wait for {:?}: {}", args[0], e)),
+        }
+    };
+
+    let collect = |handle: Option<thread::JoinHandle<String>>| {
+        let output = handle.and_then(|h| h.join().ok()).unwrap_or_default();
+        output.trim().chars().take(MAX_LOGGED_OUTPUT_CHARS).collect::<String>()
+    };
+    let stdout = collect(stdout);
+    let stderr = collect(stderr);
+    if status.success() {
+        Ok(stdout)
+    } else if stderr.is_empty() {
+        Err(format!("{:?} failed ({})", args[0], status))
+    } else {
+        Err(format!("{:?} failed ({}): {}", args[0], status, stderr))
+    }
+}
+
+fn read_in_background(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_group_arguments() {
+        assert_eq!(
+            parse(r#"notes add "my inbox" 'two words' tag"#).unwrap(),
+            vec!["notes", "add", "my inbox", "two words", "tag"]
+        );
+        // Quotes inside a word join it, and the other quote kind is literal
+        assert_eq!(parse(r#"a"b c"d 'say "hi"'"#).unwrap(), vec!["ab cd", r#"say "hi""#]);
+    }
+
+    #[test]
+    fn empty_quotes_are_an_empty_argument() {
+        assert_eq!(parse(r#"cmd "" ''"#).unwrap(), vec!["cmd", "", ""]);
+    }
+
+    #[test]
+    fn unclosed_quotes_and_empty_commands_are_errors() {
+        assert!(parse(r#"cmd "open"#).is_err());
+        assert!(parse("cmd 'open").is_err());
+        assert!(parse("   ").is_err());
+    }
+
+    #[test]
+    fn text_replaces_the_placeholder_as_one_argument() {
+        let args = parse(r#"notes add --body={text} "{text}""#).unwrap();
+        assert_eq!(
+            substitute(args, "hi there; rm -rf \"x\""),
+            vec!["notes", "add", "--body=hi there; rm -rf \"x\"", "hi there; rm -rf \"x\""]
+        );
+    }
+}
@@ -1,12 +1,23 @@
+use rodio::buffer::SamplesBuffer;
 use rodio::{Decoder, OutputStream, Sink, Source};
 use std::io::BufReader;
 use std::path::PathBuf;
 use std::sync::{mpsc, Mutex};
 use std::time::Duration;
 
+use crate::audio::TARGET_SAMPLE_RATE;
+
 enum SoundCommand {
     PlayStart,
     PlayStop,
+    /// Nothing was transcribed from the recording.
+    PlayEmpty,
+    /// The recording is about to hit its length limit.
+    PlayWarning,
+    /// Recorded audio (mono, TARGET_SAMPLE_RATE), e.g. a mic test.
+    PlaySamples(Vec<f32>),
+    /// Keep the output stream open between sounds, or close it when idle.
+    SetKeepOutputOpen(bool),
     /// Update sound config at runtime
     UpdateConfig {
         start_sound: String,
@@ -15,27 +26,69 @@ enum SoundCommand {
     },
 }
 
-/// Persistent sound player with support for custom sound files.
+/// How long an on-demand output stream stays open after the last sound, so
+/// back-to-back cues (stop, then the empty beep) don't reopen the device and
+/// click.
+const OUTPUT_LINGER: Duration = Duration::from_secs(3);
+
+/// Sound player with support for custom sound files. With `keep_output_open`
+/// the output stream stays open for the app's lifetime (lowest latency);
+/// otherwise it is opened per sound and closed after OUTPUT_LINGER, so the
+/// audio device isn't held "in use" and can sleep.
+///
+/// Sounds play in the background while the next command is awaited. A start
+/// or stop cue cuts off whatever is still playing, so a quick tap doesn't
+/// queue up chimes; other sounds wait for the current one to finish.
 pub struct SoundPlayer {
     sender: Mutex<mpsc::Sender<SoundCommand>>,
 }
 
 impl SoundPlayer {
-    pub fn new(start_sound: String, stop_sound: String, volume: f32) -> Self {
+    pub fn new(
+        start_sound: String,
+        stop_sound: String,
+        volume: f32,
+        keep_output_open: bool,
+    ) -> Self {
         let (tx, rx) = mpsc::channel();
 
         std::thread::spawn(move || {
-            let Ok((_stream, handle)) = OutputStream::try_default() else {
-                log::error!("Failed to create audio output stream for sounds");
-                return;
-            };
-            log::info!("Sound player initialized");
-
             let mut cfg_start = start_sound;
             let mut cfg_stop = stop_sound;
             let mut cfg_volume = volume;
+            let mut keep_open = keep_output_open;
+
+            let mut output = if keep_open { open_output() } else { None };
+            // The sound that may still be playing
+            let mut current: Option<Sink> = None;
+            log::info!(
+                "Sound player initialized ({})",
+                if keep_open { "output kept open" } else { "output opened on demand" }
+            );
+
+            loop {
+                let cmd = if output.is_some() && !keep_open {
+                    match rx.recv_timeout(OUTPUT_LINGER) {
+                        Ok(cmd) => cmd,
+                        Err(mpsc::RecvTimeoutError::Timeout) => {
+                            // Never close the stream under a long sound, e.g. a mic test
+                            if current.as_ref().is_some_and(|sink| !sink.empty()) {
+                                continue;
+                            }
+                            current = None;
+                            output = None;
+                            log::debug!("Sound output stream closed");
+                            continue;
+                        }
+                        Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                    }
+                } else {
+                    match rx.recv() {
+                        Ok(cmd) => cmd,
+                        Err(_) => break,
+                    }
+                };
 
-            for cmd in rx {
                 match cmd {
                     SoundCommand::UpdateConfig {
                         start_sound,
@@ -46,14 +99,43 @@ impl SoundPlayer {
                         cfg_stop = stop_sound;
                         cfg_volume = volume;
                         log::info!("Sound config updated (vol={:.0}%)", cfg_volume * 100.0);
+                        continue;
                     }
-                    SoundCommand::PlayStart => {
-                        play_sound(&handle, &cfg_start, cfg_volume, true);
+                    SoundCommand::SetKeepOutputOpen(keep) => {
+                        keep_open = keep;
+                        log::info!("Sound output kept open: {}", keep);
+                        continue;
                     }
-                    SoundCommand::PlayStop => {
-                        play_sound(&handle, &cfg_stop, cfg_volume, false);
+                    _ => {}
+                }
+
+                // Opened on demand, or reopened if an always-on stream failed at startup
+                if output.is_none() {
+                    output = open_output();
+                }
+                let Some((_, handle)) = &output else {
+                    continue;
+                };
+                if let Some(sink) = current.take() {
+                    if matches!(cmd, SoundCommand::PlayStart | SoundCommand::PlayStop) {
+                        sink.stop();
+                    } else {
+                        sink.sleep_until_end();
                     }
                 }
+                current = match cmd {
+                    SoundCommand::PlayStart => play_sound(handle, &cfg_start, cfg_volume, true),
+                    SoundCommand::PlayStop => play_sound(handle, &cfg_stop, cfg_volume, false),
+                    SoundCommand::PlayEmpty => play_empty(handle, cfg_volume),
+                    SoundCommand::PlayWarning => play_warning(handle, cfg_volume),
+                    SoundCommand::PlaySamples(samples) => {
+                        // The user's own voice, not a cue: ignore the cue volume
+                        Sink::try_new(handle).ok().inspect(|sink| {
+                            sink.append(SamplesBuffer::new(1, TARGET_SAMPLE_RATE, samples));
+                        })
+                    }
+                    SoundCommand::UpdateConfig { .. } | SoundCommand::SetKeepOutputOpen(_) => None,
+                };
             }
         });
 
@@ -74,6 +156,30 @@ impl SoundPlayer {
         }
     }
 
+    pub fn play_empty(&self) {
+        if let Ok(tx) = self.sender.lock() {
+            let _ = tx.send(SoundCommand::PlayEmpty);
+        }
+    }
+
+    pub fn play_warning(&self) {
+        if let Ok(tx) = self.sender.lock() {
+            let _ = tx.send(SoundCommand::PlayWarning);
+        }
+    }
+
+    pub fn play_samples(&self, samples: Vec<f32>) {
+        if let Ok(tx) = self.sender.lock() {
+            let _ = tx.send(SoundCommand::PlaySamples(samples));
+        }
+    }
+
+    pub fn set_keep_output_open(&self, keep: bool) {
+        if let Ok(tx) = self.sender.lock() {
+            let _ = tx.send(SoundCommand::SetKeepOutputOpen(keep));
+        }
+    }
+
     pub fn update_config(&self, start_sound: String, stop_sound: String, volume: f32) {
         if let Ok(tx) = self.sender.lock() {
             let _ = tx.send(SoundCommand::UpdateConfig {
@@ -85,16 +191,55 @@ impl SoundPlayer {
     }
 }
 
-/// Play a sound: custom file if path is set, otherwise built-in tone.
+fn open_output() -> Option<(OutputStream, rodio::OutputStreamHandle)> {
+    match OutputStream::try_default() {
+        Ok(output) => Some(output),
+        Err(e) => {
+            log::error!("Failed to create audio output stream for sounds: {}", e);
+            None
+        }
+    }
+}
+
+/// Two short, low beeps on the same note: unlike the start/stop chimes,
+/// which move in pitch, this one says "nothing came of that".
+fn play_empty(handle: &rodio::OutputStreamHandle, volume: f32) -> Option<Sink> {
+    let sink = Sink::try_new(handle).ok()?;
+    sink.set_volume(volume);
+    for _ in 0..2 {
+        let beep = rodio::source::SineWave::new(330.0)
+            .take_duration(Duration::from_millis(50))
+            .amplify(0.07)
+            .fade_in(Duration::from_millis(10));
+        let gap = rodio::source::Zero::<f32>::new(1, 48000)
+            .take_duration(Duration::from_millis(60));
+        sink.append(beep);
+        sink.append(gap);
+    }
+    Some(sink)
+}
+
+/// A single quiet, high tick: noticeable while talking, but kept short so
+/// it doesn't drown out the speech being recorded.
+fn play_warning(handle: &rodio::OutputStreamHandle, volume: f32) -> Option<Sink> {
+    let sink = Sink::try_new(handle).ok()?;
+    sink.set_volume(volume);
+    let tick = rodio::source::SineWave::new(880.0)
+        .take_duration(Duration::from_millis(40))
+        .amplify(0.05)
+        .fade_in(Duration::from_millis(5));
+    sink.append(tick);
+    Some(sink)
+}
+
+/// Start a sound: custom file if path is set, otherwise built-in tone.
 fn play_sound(
     handle: &rodio::OutputStreamHandle,
     custom_path: &str,
     volume: f32,
     is_start: bool,
-) {
-    let Ok(sink) = Sink::try_new(handle) else {
-        return;
-    };
+) -> Option<Sink> {
+    let sink = Sink::try_new(handle).ok()?;
     sink.set_volume(volume);
 
     // Try custom file first
@@ -104,13 +249,14 @@ fn play_sound(
             match std::fs::File::open(&path) {
                 Ok(file) => {
                     let reader = BufReader::new(file);
-                    match Decoder::new(reader) {
-                        Ok(source) => {
+                    // rodio panics instead of erroring on some MP4 files
+                    match std::panic::catch_unwind(|| Decoder::new(reader)) {
+                        Ok(Ok(source)) => {
                             sink.append(source);
-                            sink.sleep_until_end();
-                            return;
+                            return Some(sink);
                         }
-                        Err(e) => log::warn!("Failed to decode {}: {}", custom_path, e),
+                        Ok(Err(e)) => log::warn!("Failed to decode {}: {}", custom_path, e),
+                        Err(_) => log::warn!("Decoder panicked on {}", custom_path),
                     }
                 }
                 Err(e) => log::warn!("Failed to open {}: {}", custom_path, e),
@@ -146,5 +292,5 @@ fn play_sound(
         sink.append(tone1);
         sink.append(tone2);
     }
-    sink.sleep_until_end();
+    Some(sink)
 }
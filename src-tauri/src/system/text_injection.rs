@@ -1,55 +1,470 @@
+use super::window;
+use crate::settings::{AutoAdvance, ClipboardRestore, InjectionMethod};
 use arboard::Clipboard;
 use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use serde::Serialize;
+use std::fmt;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-/// Inject text into the currently focused application using clipboard-paste:
+/// How long to wait for the clipboard to reflect newly set text.
+const CLIPBOARD_VERIFY_TIMEOUT_MS: u64 = 500;
+/// Delay between clipboard read-backs while waiting.
+const CLIPBOARD_POLL_MS: u64 = 10;
+/// Minimum wait after the paste keystroke before restoring the old clipboard.
+const RESTORE_BASE_DELAY_MS: u64 = 300;
+/// Extra wait per 1000 characters pasted: big pastes take longer to read,
+/// especially over RDP.
+const RESTORE_DELAY_PER_KCHAR_MS: u64 = 100;
+/// Upper bound on the restore wait.
+const RESTORE_MAX_DELAY_MS: u64 = 2000;
+/// Wait after focusing a target window before pasting into it.
+const TARGET_FOCUS_DELAY_MS: u64 = 100;
+/// Longer pastes are undone with the undo shortcut instead of Backspaces.
+const MAX_UNDO_BACKSPACES: usize = 2000;
+
+/// Paste shortcut used when none is configured.
+#[cfg(target_os = "macos")]
+pub const DEFAULT_PASTE_SHORTCUT: &str = "Cmd+V";
+#[cfg(not(target_os = "macos"))]
+pub const DEFAULT_PASTE_SHORTCUT: &str = "Ctrl+V";
+
+/// Keys pressed together to paste, in press order (released in reverse).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasteShortcut(Vec<Key>);
+
+impl PasteShortcut {
+    /// Parse a shortcut like "Ctrl+V", "Cmd+V" or "Shift+Insert"
+    /// (empty = the platform default). The last key must not be a modifier.
+    pub fn parse(shortcut: &str) -> Result<Self, String> {
+        let shortcut = if shortcut.trim().is_empty() {
+            DEFAULT_PASTE_SHORTCUT
+        } else {
+            shortcut
+        };
+        let keys = shortcut
+            .split('+')
+            .map(|part| {
+                paste_key(&part.trim().to_lowercase())
+                    .ok_or_else(|| format!("Unsupported key in paste shortcut: {:?}", part.trim()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match keys.last() {
+            Some(Key::Control | Key::Shift | Key::Alt | Key::Meta) | None => Err(format!(
+                "Paste shortcut {:?} needs a key besides modifiers",
+                shortcut
+            )),
+            Some(_) => Ok(PasteShortcut(keys)),
+        }
+    }
+
+    /// The configured shortcut, or the platform default if it doesn't parse
+    /// (the settings command rejects those anyway).
+    pub fn from_setting(shortcut: &str) -> Self {
+        Self::parse(shortcut).unwrap_or_else(|e| {
+            log::warn!("{}, using {}", e, DEFAULT_PASTE_SHORTCUT);
+            Self::parse(DEFAULT_PASTE_SHORTCUT).expect("default paste shortcut parses")
+        })
+    }
+}
+
+/// Injection settings read once per injection.
+#[derive(Debug, Clone)]
+pub struct InjectionOptions {
+    pub paste: PasteShortcut,
+    pub method: InjectionMethod,
+    /// Longest text typed rather than pasted with InjectionMethod::Auto.
+    pub type_max_chars: usize,
+    pub clipboard_restore: ClipboardRestore,
+}
+
+/// What a successful injection left behind that the caller may want to report.
+#[derive(Debug, Clone, Default)]
+pub struct Injected {
+    /// Why the clipboard couldn't get its old contents back after a paste,
+    /// including contents that weren't text and so were never saved; it
+    /// still holds the injected text.
+    pub restore_error: Option<String>,
+}
+
+impl InjectionOptions {
+    /// Whether `text` is typed (true) or pasted.
+    pub fn types(&self, text: &str) -> bool {
+        match self.method {
+            InjectionMethod::Paste => false,
+            InjectionMethod::Type => true,
+            InjectionMethod::Auto => text.chars().count() <= self.type_max_chars,
+        }
+    }
+}
+
+fn paste_key(name: &str) -> Option<Key> {
+    let key = match name {
+        "ctrl" | "control" => Key::Control,
+        "shift" => Key::Shift,
+        "alt" | "option" => Key::Alt,
+        "cmd" | "command" | "super" | "win" | "meta" => Key::Meta,
+        #[cfg(not(target_os = "macos"))]
+        "insert" => Key::Insert,
+        other => {
+            let mut chars = other.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_alphanumeric() => char_key(c),
+                _ => return None,
+            }
+        }
+    };
+    Some(key)
+}
+
+/// On Windows letters are sent as raw virtual key codes (which match
+/// uppercase ASCII): Key::Unicode can fail there with TryFromIntError, and a
+/// VK also works regardless of keyboard layout. Elsewhere enigo maps
+/// Key::Unicode to the right keycode itself.
+#[cfg(windows)]
+fn char_key(c: char) -> Key {
+    Key::Other(c.to_ascii_uppercase() as u32)
+}
+
+#[cfg(not(windows))]
+fn char_key(c: char) -> Key {
+    Key::Unicode(c.to_ascii_lowercase())
+}
+
+/// Distinct failure points of text injection (typed or clipboard-paste).
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "detail")]
+pub enum InjectionError {
+    /// The system clipboard could not be opened.
+    ClipboardUnavailable(String),
+    /// The clipboard was opened but the text could not be written to it.
+    ClipboardWrite(String),
+    /// The text was written but never read back from the clipboard in time.
+    ClipboardNotReady(u64),
+    /// The keyboard simulator could not be created.
+    KeyboardInit(String),
+    /// A simulated keystroke was rejected by the OS.
+    KeySimulation(String),
+    /// No open window matched the configured target window title.
+    TargetWindowNotFound(String),
+    /// The target window exists but couldn't be focused, or lookup isn't supported.
+    TargetWindowFocus(String),
+    /// App state needed around the paste was unavailable, e.g. a lock
+    /// poisoned by an earlier panic.
+    Internal(String),
+    /// A dictation or another paste was in progress.
+    Busy,
+}
+
+impl InjectionError {
+    /// Message suitable for showing to the user, with a suggested remedy.
+    pub fn user_message(&self) -> String {
+        match self {
+            InjectionError::ClipboardUnavailable(_) => {
+                "Could not access the clipboard. Another application may be holding it — try again in a moment.".to_string()
+            }
+            InjectionError::ClipboardWrite(_) => {
+                "Could not copy the transcription to the clipboard. Try again or restart the app.".to_string()
+            }
+            InjectionError::ClipboardNotReady(_) => {
+                "The clipboard did not update in time, so nothing was pasted. The system may be busy — try again.".to_string()
+            }
+            InjectionError::KeyboardInit(_) => {
+                "Could not simulate keyboard input. Grant accessibility / input permission to Wispr Local.".to_string()
+            }
+            InjectionError::KeySimulation(_) => {
+                "Simulated keystrokes were blocked. Grant accessibility permission, or check that the target app is not running as administrator.".to_string()
+            }
+            InjectionError::TargetWindowNotFound(title) => {
+                format!("No open window matches \"{}\". Open it, or change the target window in settings.", title)
+            }
+            InjectionError::TargetWindowFocus(_) => {
+                "Could not switch to the target window, so nothing was pasted. Clear the target window setting to paste into the focused app.".to_string()
+            }
+            InjectionError::Internal(_) => {
+                "Wispr Local hit an internal error, so nothing was pasted. Restart the app and try again.".to_string()
+            }
+            InjectionError::Busy => {
+                "Busy — wait for the current dictation to finish".to_string()
+            }
+        }
+    }
+}
+
+impl fmt::Display for InjectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InjectionError::ClipboardUnavailable(e) => write!(f, "Failed to open clipboard: {}", e),
+            InjectionError::ClipboardWrite(e) => write!(f, "Failed to set clipboard text: {}", e),
+            InjectionError::ClipboardNotReady(ms) => {
+                write!(f, "Clipboard did not contain the new text after {}ms", ms)
+            }
+            InjectionError::KeyboardInit(e) => write!(f, "Failed to create enigo: {}", e),
+            InjectionError::KeySimulation(e) => write!(f, "Failed to simulate paste: {}", e),
+            InjectionError::TargetWindowNotFound(title) => {
+                write!(f, "No window title contains {:?}", title)
+            }
+            InjectionError::TargetWindowFocus(e) => write!(f, "Failed to focus target window: {}", e),
+            InjectionError::Internal(e) => write!(f, "Injection failed internally: {}", e),
+            InjectionError::Busy => write!(f, "Not idle, nothing was pasted"),
+        }
+    }
+}
+
+impl std::error::Error for InjectionError {}
+
+/// Inject text into the currently focused application, typed or pasted
+/// depending on the injection method and the length of the text.
+pub fn inject_text(text: &str, options: &InjectionOptions) -> Result<Injected, InjectionError> {
+    if options.types(text) {
+        type_text(text)?;
+        Ok(Injected::default())
+    } else {
+        paste_text(text, &options.paste, options.clipboard_restore)
+    }
+}
+
+/// Type `text` as keystrokes. Line breaks are sent as Enter, since typing
+/// a raw newline character doesn't start a new line in every app.
+fn type_text(text: &str) -> Result<(), InjectionError> {
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| InjectionError::KeyboardInit(e.to_string()))?;
+    for (i, line) in text.replace("\r\n", "\n").split('\n').enumerate() {
+        if i > 0 {
+            enigo
+                .key(Key::Return, Direction::Click)
+                .map_err(|e| InjectionError::KeySimulation(format!("press Return: {}", e)))?;
+        }
+        if !line.is_empty() {
+            enigo
+                .text(line)
+                .map_err(|e| InjectionError::KeySimulation(format!("type text: {}", e)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Put `text` on the clipboard without pasting it anywhere.
+pub fn copy_to_clipboard(text: &str) -> Result<(), InjectionError> {
+    Clipboard::new()
+        .map_err(|e| InjectionError::ClipboardUnavailable(e.to_string()))?
+        .set_text(text)
+        .map_err(|e| InjectionError::ClipboardWrite(e.to_string()))
+}
+
+/// Paste text into the currently focused application through the clipboard:
 /// 1. Save current clipboard
 /// 2. Set clipboard to transcribed text
-/// 3. Simulate Ctrl+V
-/// 4. Wait for paste to complete
-/// 5. Restore original clipboard
-pub fn inject_text(text: &str) -> Result<(), String> {
+/// 3. Press the paste shortcut (Ctrl+V, or Cmd+V on macOS, by default)
+/// 4. Wait for paste to complete (longer for longer text)
+/// 5. Restore original clipboard if it still holds our text (unless `restore`
+///    is Never)
+fn paste_text(
+    text: &str,
+    paste: &PasteShortcut,
+    restore: ClipboardRestore,
+) -> Result<Injected, InjectionError> {
     let mut clipboard =
-        Clipboard::new().map_err(|e| format!("Failed to open clipboard: {}", e))?;
+        Clipboard::new().map_err(|e| InjectionError::ClipboardUnavailable(e.to_string()))?;
 
-    // Save current clipboard contents
-    let saved_text = clipboard.get_text().ok();
+    // Save current clipboard contents. Only text can be put back, so an
+    // image or copied files are lost once our text replaces them.
+    let mut injected = Injected::default();
+    let saved_text = if restore == ClipboardRestore::Never {
+        None
+    } else {
+        match clipboard.get_text() {
+            Ok(saved) => Some(saved),
+            Err(e) => {
+                log::info!("Clipboard contents can't be saved as text: {}", e);
+                injected.restore_error = Some(format!("the old contents weren't text: {}", e));
+                None
+            }
+        }
+    };
 
-    // Set transcribed text to clipboard
-    clipboard
+    // Set transcribed text to clipboard, make sure the clipboard really
+    // holds it, then paste
+    let pasted = clipboard
         .set_text(text)
-        .map_err(|e| format!("Failed to set clipboard text: {}", e))?;
+        .map_err(|e| InjectionError::ClipboardWrite(e.to_string()))
+        .and_then(|_| wait_for_clipboard(&mut clipboard, text))
+        .and_then(|_| press_shortcut(paste));
+    if let Err(e) = pasted {
+        // Nothing was pasted, so the old contents go back regardless
+        if let Some(original) = saved_text {
+            if let Err(restore) = clipboard.set_text(&original) {
+                log::warn!("Failed to restore the clipboard after a failed paste: {}", restore);
+            }
+        }
+        return Err(e);
+    }
 
-    // Small delay to ensure clipboard is ready
-    thread::sleep(Duration::from_millis(50));
+    // Wait for paste to complete; larger pastes get longer
+    thread::sleep(restore_delay(text));
 
-    // Simulate Ctrl+V using raw Windows virtual key codes
-    // (Key::Unicode can fail with TryFromIntError on some systems)
-    let mut enigo =
-        Enigo::new(&Settings::default()).map_err(|e| format!("Failed to create enigo: {}", e))?;
+    // Restore original clipboard, unless something else has replaced our
+    // text in the meantime
+    if let Some(original) = saved_text {
+        let still_ours = clipboard
+            .get_text()
+            .map(|t| t.replace("\r\n", "\n") == text.replace("\r\n", "\n"))
+            .unwrap_or(false);
+        if !still_ours {
+            log::info!("Clipboard changed after paste, not restoring the old contents");
+        } else if let Err(e) = clipboard.set_text(&original) {
+            log::warn!("Failed to restore the clipboard after pasting: {}", e);
+            injected.restore_error = Some(e.to_string());
+        }
+    }
 
-    // VK_CONTROL = 0x11, VK_V = 0x56
-    enigo
-        .key(Key::Other(0x11), Direction::Press)
-        .map_err(|e| format!("Failed to press Ctrl: {}", e))?;
-    enigo
-        .key(Key::Other(0x56), Direction::Press)
-        .map_err(|e| format!("Failed to press V: {}", e))?;
-    enigo
-        .key(Key::Other(0x56), Direction::Release)
-        .map_err(|e| format!("Failed to release V: {}", e))?;
+    Ok(injected)
+}
+
+/// Press the paste shortcut's keys in order and release them in reverse.
+/// Keys that went down are released even when a later press fails, so no
+/// modifier is left held.
+fn press_shortcut(paste: &PasteShortcut) -> Result<(), InjectionError> {
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| InjectionError::KeyboardInit(e.to_string()))?;
+    let mut pressed = Vec::new();
+    let mut result = Ok(());
+    for key in &paste.0 {
+        match enigo.key(*key, Direction::Press) {
+            Ok(()) => pressed.push(*key),
+            Err(e) => {
+                result = Err(InjectionError::KeySimulation(format!("press {:?}: {}", key, e)));
+                break;
+            }
+        }
+    }
+    for key in pressed.iter().rev() {
+        if let Err(e) = enigo.key(*key, Direction::Release) {
+            let error = InjectionError::KeySimulation(format!("release {:?}: {}", key, e));
+            result = result.and(Err(error));
+        }
+    }
+    result
+}
+
+/// Focus the first window whose title contains `title`, paste `text` there,
+/// then optionally hand focus back to the window that had it before.
+pub fn inject_text_into_window(
+    text: &str,
+    title: &str,
+    restore_focus: bool,
+    options: &InjectionOptions,
+) -> Result<Injected, InjectionError> {
+    let previous = window::foreground_window();
+    let target = window::find_window(title)
+        .map_err(InjectionError::TargetWindowFocus)?
+        .ok_or_else(|| InjectionError::TargetWindowNotFound(title.to_string()))?;
+
+    if !window::focus_window(target) {
+        return Err(InjectionError::TargetWindowFocus(format!(
+            "window {:?} refused focus",
+            title
+        )));
+    }
+    // Give the window a moment to actually take keyboard focus
+    thread::sleep(Duration::from_millis(TARGET_FOCUS_DELAY_MS));
+
+    let result = inject_text(text, options);
+
+    if restore_focus {
+        if let Some(previous) = previous.filter(|p| *p != target) {
+            window::focus_window(previous);
+        }
+    }
+    result
+}
+
+/// Press the auto-advance key in the focused window, e.g. Tab to move on to
+/// the next form field after a paste.
+pub fn send_advance_key(advance: AutoAdvance) -> Result<(), InjectionError> {
+    let key = match advance {
+        AutoAdvance::Off => return Ok(()),
+        AutoAdvance::Tab => Key::Tab,
+        AutoAdvance::Enter => Key::Return,
+    };
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| InjectionError::KeyboardInit(e.to_string()))?;
     enigo
-        .key(Key::Other(0x11), Direction::Release)
-        .map_err(|e| format!("Failed to release Ctrl: {}", e))?;
+        .key(key, Direction::Click)
+        .map_err(|e| InjectionError::KeySimulation(format!("press {:?}: {}", key, e)))
+}
 
-    // Wait for paste to complete
-    thread::sleep(Duration::from_millis(300));
+/// Delete `chars` characters that were just pasted by pressing Backspace that
+/// many times, in `window` if given. Falls back to the undo shortcut (Ctrl+Z,
+/// Cmd+Z on macOS) for very long pastes or if Backspace is rejected. Counts
+/// are in chars, so emoji sequences may leave a stray character behind.
+pub fn undo_injection(
+    chars: usize,
+    window: Option<window::WindowHandle>,
+) -> Result<(), InjectionError> {
+    if let Some(window) = window {
+        if !window::focus_window(window) {
+            return Err(InjectionError::TargetWindowFocus(
+                "window of the last paste refused focus".to_string(),
+            ));
+        }
+        thread::sleep(Duration::from_millis(TARGET_FOCUS_DELAY_MS));
+    }
 
-    // Restore original clipboard (best-effort)
-    if let Some(original) = saved_text {
-        let _ = clipboard.set_text(&original);
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| InjectionError::KeyboardInit(e.to_string()))?;
+    if chars <= MAX_UNDO_BACKSPACES {
+        match enigo.key(Key::Backspace, Direction::Click) {
+            Ok(()) => {
+                for _ in 1..chars {
+                    enigo
+                        .key(Key::Backspace, Direction::Click)
+                        .map_err(|e| InjectionError::KeySimulation(format!("backspace: {}", e)))?;
+                }
+                return Ok(());
+            }
+            Err(e) => log::warn!("Backspace rejected ({}), sending the undo shortcut", e),
+        }
     }
 
-    Ok(())
+    #[cfg(target_os = "macos")]
+    let modifier = Key::Meta;
+    #[cfg(not(target_os = "macos"))]
+    let modifier = Key::Control;
+    let undo = char_key('z');
+    enigo
+        .key(modifier, Direction::Press)
+        .map_err(|e| InjectionError::KeySimulation(format!("press {:?}: {}", modifier, e)))?;
+    let result = enigo
+        .key(undo, Direction::Click)
+        .map_err(|e| InjectionError::KeySimulation(format!("press {:?}: {}", undo, e)));
+    let _ = enigo.key(modifier, Direction::Release);
+    result
+}
+
+/// How long to let the target app read the clipboard before restoring it.
+fn restore_delay(text: &str) -> Duration {
+    let chars = text.chars().count() as u64;
+    let ms = RESTORE_BASE_DELAY_MS + chars * RESTORE_DELAY_PER_KCHAR_MS / 1000;
+    Duration::from_millis(ms.min(RESTORE_MAX_DELAY_MS))
+}
+
+/// Poll the clipboard until it reads back `expected`. On slow systems set_text
+/// can return before the new contents are visible to other apps.
+fn wait_for_clipboard(clipboard: &mut Clipboard, expected: &str) -> Result<(), InjectionError> {
+    // Some platforms normalize line endings on the way in
+    let expected = expected.replace("\r\n", "\n");
+    let start = Instant::now();
+    let timeout = Duration::from_millis(CLIPBOARD_VERIFY_TIMEOUT_MS);
+    loop {
+        let current = clipboard.get_text().map(|t| t.replace("\r\n", "\n"));
+        if current.map(|t| t == expected).unwrap_or(false) {
+            log::debug!("Clipboard verified after {:?}", start.elapsed());
+            return Ok(());
+        }
+        if start.elapsed() >= timeout {
+            return Err(InjectionError::ClipboardNotReady(CLIPBOARD_VERIFY_TIMEOUT_MS));
+        }
+        thread::sleep(Duration::from_millis(CLIPBOARD_POLL_MS));
+    }
 }
@@ -1,10 +1,124 @@
+use std::sync::Mutex;
 use tauri::{
     image::Image,
-    menu::{Menu, MenuItem},
+    menu::{CheckMenuItem, Menu, MenuItem, Submenu},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    AppHandle, Emitter, Manager,
+    AppHandle, Emitter, Manager, Wry,
 };
 
+use crate::settings::{RecordingMode, SaveScheduler, Settings};
+use crate::state::AppState;
+
+const MODE_ITEM_PREFIX: &str = "recording_mode_";
+const RECENT_ITEM_PREFIX: &str = "recent_";
+
+/// Dictations listed in the tray's "Recent Dictations" submenu.
+const TRAY_RECENT_COUNT: usize = 5;
+/// Characters of each dictation shown as its menu label.
+const TRAY_RECENT_LABEL_CHARS: usize = 40;
+
+/// Give the tray menu time to close and focus to return to the previous app.
+const TRAY_REINJECT_DELAY_MS: u64 = 300;
+
+/// Tray check items for the recording modes, kept so the checkmark can follow
+/// changes made from the settings window.
+pub struct RecordingModeItems(Vec<(RecordingMode, CheckMenuItem<Wry>)>);
+
+/// Move the tray checkmark to `mode`.
+pub fn sync_recording_mode(app: &AppHandle, mode: RecordingMode) {
+    if let Some(items) = app.try_state::<RecordingModeItems>() {
+        for (m, item) in &items.0 {
+            let _ = item.set_checked(*m == mode);
+        }
+    }
+}
+
+/// Tray check item for the AI formatting switch.
+pub struct AiEnabledItem(CheckMenuItem<Wry>);
+
+/// Update the tray checkmark after AI formatting is switched on or off.
+pub fn sync_ai_enabled(app: &AppHandle, enabled: bool) {
+    if let Some(item) = app.try_state::<AiEnabledItem>() {
+        let _ = item.0.set_checked(enabled);
+    }
+}
+
+/// The tray's "Recent Dictations" submenu, rebuilt after every dictation.
+pub struct RecentMenu(Submenu<Wry>);
+
+fn recent_label(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    let mut label: String = line.chars().take(TRAY_RECENT_LABEL_CHARS).collect();
+    if line.chars().count() > TRAY_RECENT_LABEL_CHARS || text.lines().nth(1).is_some() {
+        label.push('…');
+    }
+    label
+}
+
+fn fill_recent(app: &AppHandle, menu: &Submenu<Wry>) -> tauri::Result<()> {
+    let labels: Vec<String> = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .unwrap()
+        .history
+        .iter()
+        .take(TRAY_RECENT_COUNT)
+        .map(|entry| recent_label(&entry.text))
+        .collect();
+
+    if labels.is_empty() {
+        let empty = MenuItem::with_id(app, "no_recent", "No dictations yet", false, None::<&str>)?;
+        return menu.append(&empty);
+    }
+    for (i, label) in labels.into_iter().enumerate() {
+        let id = format!("{}{}", RECENT_ITEM_PREFIX, i);
+        menu.append(&MenuItem::with_id(app, id, label, true, None::<&str>)?)?;
+    }
+    Ok(())
+}
+
+/// Refresh the recent dictations submenu from the history.
+pub fn sync_recent(app: &AppHandle) {
+    let Some(recent) = app.try_state::<RecentMenu>() else {
+        return;
+    };
+    let clear = || -> tauri::Result<()> {
+        while recent.0.remove_at(0)?.is_some() {}
+        Ok(())
+    };
+    if let Err(e) = clear().and_then(|_| fill_recent(app, &recent.0)) {
+        log::warn!("Failed to update recent dictations menu: {}", e);
+    }
+}
+
+/// Paste a dictation picked from the tray once focus is back on the user's app.
+fn reinject_from_tray(app: &AppHandle, index: usize) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(TRAY_REINJECT_DELAY_MS)).await;
+        let _ = tauri::async_runtime::spawn_blocking(move || {
+            if let Err(e) = crate::reinject_history(&app, index) {
+                log::warn!("Re-inject from tray failed: {}", e);
+                let _ = app.emit("app-error", e);
+            }
+        })
+        .await;
+    });
+}
+
+/// Persist a recording mode picked from the tray.
+fn select_recording_mode(app: &AppHandle, mode: RecordingMode) {
+    {
+        let settings = app.state::<Mutex<Settings>>();
+        let mut s = settings.lock().unwrap();
+        s.recording_mode = mode;
+    }
+    app.state::<SaveScheduler>().schedule();
+    sync_recording_mode(app, mode);
+    let _ = app.emit("recording-mode-changed", mode);
+    log::info!("Recording mode set to {:?} from tray", mode);
+}
+
 pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     let start_item =
         MenuItem::with_id(app, "start_recording", "Start Recording", true, None::<&str>)?;
@@ -14,7 +128,43 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
         MenuItem::with_id(app, "show_window", "Show Window", true, None::<&str>)?;
     let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
 
-    let menu = Menu::with_items(app, &[&start_item, &stop_item, &show_item, &quit_item])?;
+    let current_mode = app.state::<Mutex<Settings>>().lock().unwrap().recording_mode;
+    let mut mode_items = Vec::new();
+    for mode in RecordingMode::ALL {
+        let id = format!("{}{}", MODE_ITEM_PREFIX, mode.as_str());
+        let item =
+            CheckMenuItem::with_id(app, id, mode.label(), true, mode == current_mode, None::<&str>)?;
+        mode_items.push((mode, item));
+    }
+    let mode_refs: Vec<&dyn tauri::menu::IsMenuItem<Wry>> =
+        mode_items.iter().map(|(_, item)| item as _).collect();
+    let mode_menu = Submenu::with_items(app, "Recording Mode", true, &mode_refs)?;
+    app.manage(RecordingModeItems(mode_items));
+
+    let ai_enabled = app.state::<Mutex<Settings>>().lock().unwrap().ai_enabled;
+    let ai_item =
+        CheckMenuItem::with_id(app, "ai_enabled", "AI Formatting", true, ai_enabled, None::<&str>)?;
+    app.manage(AiEnabledItem(ai_item.clone()));
+
+    let recent_menu = Submenu::with_items(app, "Recent Dictations", true, &[])?;
+    fill_recent(app, &recent_menu)?;
+    app.manage(RecentMenu(recent_menu.clone()));
+    let copy_last_item =
+        MenuItem::with_id(app, "copy_last", "Copy Last Dictation", true, None::<&str>)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &start_item,
+            &stop_item,
+            &mode_menu,
+            &ai_item,
+            &recent_menu,
+            &copy_last_item,
+            &show_item,
+            &quit_item,
+        ],
+    )?;
 
     let icon = app
         .default_window_icon()
@@ -40,6 +190,17 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
             "stop_recording" => {
                 let _ = app.emit("tray-stop-recording", ());
             }
+            "ai_enabled" => {
+                let enabled = app.state::<Mutex<Settings>>().lock().unwrap().ai_enabled;
+                if let Err(e) = crate::set_ai_enabled(app, !enabled) {
+                    log::error!("Failed to save AI formatting switch: {}", e);
+                }
+            }
+            "copy_last" => {
+                if let Err(e) = crate::copy_last_transcription(app) {
+                    let _ = app.emit("app-error", e);
+                }
+            }
             "show_window" => {
                 if let Some(window) = app.get_webview_window("main") {
                     let _ = window.show();
@@ -49,7 +210,17 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
             "quit" => {
                 app.exit(0);
             }
-            _ => {}
+            id => {
+                if let Some(mode) = id.strip_prefix(MODE_ITEM_PREFIX) {
+                    if let Ok(mode) = RecordingMode::parse(mode) {
+                        select_recording_mode(app, mode);
+                    }
+                } else if let Some(index) = id.strip_prefix(RECENT_ITEM_PREFIX) {
+                    if let Ok(index) = index.parse() {
+                        reinject_from_tray(app, index);
+                    }
+                }
+            }
         })
         .on_tray_icon_event(|tray, event| {
             if let TrayIconEvent::Click {
@@ -0,0 +1,128 @@
+/// Native handle of a top-level window (HWND on Windows).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowHandle(isize);
+
+/// Window that currently has keyboard focus.
+/// Returns None when it can't be determined on this platform.
+pub fn foreground_window() -> Option<WindowHandle> {
+    platform::foreground_window()
+}
+
+/// First visible top-level window whose title contains `title`
+/// (case-insensitive). Err when window lookup isn't supported here.
+pub fn find_window(title: &str) -> Result<Option<WindowHandle>, String> {
+    platform::find_window(&title.to_lowercase())
+}
+
+/// Bring a window to the front and give it keyboard focus.
+pub fn focus_window(window: WindowHandle) -> bool {
+    platform::focus_window(window)
+}
+
+/// Center of a window in physical screen coordinates, if known.
+pub fn window_center(window: WindowHandle) -> Option<(i32, i32)> {
+    platform::window_center(window)
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::WindowHandle;
+
+    const SW_RESTORE: i32 = 9;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct Rect {
+        left: i32,
+        top: i32,
+        right: i32,
+        bottom: i32,
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetWindowRect(hwnd: isize, rect: *mut Rect) -> i32;
+        fn EnumWindows(callback: extern "system" fn(isize, isize) -> i32, lparam: isize) -> i32;
+        fn GetWindowTextW(hwnd: isize, buf: *mut u16, max_count: i32) -> i32;
+        fn IsWindowVisible(hwnd: isize) -> i32;
+        fn GetForegroundWindow() -> isize;
+        fn SetForegroundWindow(hwnd: isize) -> i32;
+        fn IsIconic(hwnd: isize) -> i32;
+        fn ShowWindow(hwnd: isize, cmd: i32) -> i32;
+    }
+
+    struct Search {
+        needle: String,
+        found: Option<isize>,
+    }
+
+    extern "system" fn enum_callback(hwnd: isize, lparam: isize) -> i32 {
+        let search = unsafe { &mut *(lparam as *mut Search) };
+        if unsafe { IsWindowVisible(hwnd) } == 0 {
+            return 1;
+        }
+        let mut buf = [0u16; 512];
+        let len = unsafe { GetWindowTextW(hwnd, buf.as_mut_ptr(), buf.len() as i32) };
+        if len <= 0 {
+            return 1;
+        }
+        let title = String::from_utf16_lossy(&buf[..len as usize]).to_lowercase();
+        if title.contains(&search.needle) {
+            search.found = Some(hwnd);
+            return 0; // stop enumerating
+        }
+        1
+    }
+
+    pub fn foreground_window() -> Option<WindowHandle> {
+        let hwnd = unsafe { GetForegroundWindow() };
+        (hwnd != 0).then_some(WindowHandle(hwnd))
+    }
+
+    pub fn find_window(needle: &str) -> Result<Option<WindowHandle>, String> {
+        let mut search = Search {
+            needle: needle.to_string(),
+            found: None,
+        };
+        unsafe { EnumWindows(enum_callback, &mut search as *mut Search as isize) };
+        Ok(search.found.map(WindowHandle))
+    }
+
+    pub fn focus_window(window: WindowHandle) -> bool {
+        unsafe {
+            if IsIconic(window.0) != 0 {
+                ShowWindow(window.0, SW_RESTORE);
+            }
+            SetForegroundWindow(window.0) != 0
+        }
+    }
+
+    pub fn window_center(window: WindowHandle) -> Option<(i32, i32)> {
+        let mut rect = Rect::default();
+        if unsafe { GetWindowRect(window.0, &mut rect) } == 0 {
+            return None;
+        }
+        Some(((rect.left + rect.right) / 2, (rect.top + rect.bottom) / 2))
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::WindowHandle;
+
+    pub fn foreground_window() -> Option<WindowHandle> {
+        None
+    }
+
+    pub fn find_window(_needle: &str) -> Result<Option<WindowHandle>, String> {
+        Err("Targeting a window by title is only supported on Windows".to_string())
+    }
+
+    pub fn focus_window(_window: WindowHandle) -> bool {
+        false
+    }
+
+    pub fn window_center(_window: WindowHandle) -> Option<(i32, i32)> {
+        None
+    }
+}
@@ -0,0 +1,61 @@
+use serde::Serialize;
+use std::path::Path;
+
+use super::engine::{TranscriptionSettings, WhisperEngine};
+
+/// How one model did on the benchmark clip.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkResult {
+    pub model: String,
+    pub text: String,
+    /// Transcription time, excluding model loading.
+    pub ms: u64,
+    /// Real-time factor: processing time / clip duration (below 1 = faster than real time).
+    pub rtf: f32,
+    /// Set when the model failed to load or transcribe; the other fields are empty.
+    pub error: Option<String>,
+}
+
+/// Load each model in `models_dir` in turn and transcribe `audio` with it,
+/// using the settings paired with it (its overrides applied), so the speed
+/// measured is the one it runs at. Leaves the last benchmarked model and its
+/// settings in place; the caller restores its own.
+pub fn run(
+    engine: &mut WhisperEngine,
+    models_dir: &Path,
+    models: &[(String, TranscriptionSettings)],
+    audio: &[f32],
+) -> Vec<BenchmarkResult> {
+    let clip_secs = crate::audio::samples_to_secs(audio.len());
+
+    models
+        .iter()
+        .map(|(model, settings)| {
+            let mut result = BenchmarkResult {
+                model: model.clone(),
+                text: String::new(),
+                ms: 0,
+                rtf: 0.0,
+                error: None,
+            };
+            if let Err(e) = engine.load_model(&models_dir.join(model)) {
+                result.error = Some(e);
+                return result;
+            }
+            engine.set_settings(settings.clone());
+
+            let started = std::time::Instant::now();
+            match engine.transcribe(audio) {
+                Ok(text) => {
+                    let elapsed = started.elapsed();
+                    result.text = text;
+                    result.ms = elapsed.as_millis() as u64;
+                    result.rtf = elapsed.as_secs_f32() / clip_secs;
+                    log::info!("Benchmark {}: {:?} (RTF {:.2})", model, elapsed, result.rtf);
+                }
+                Err(e) => result.error = Some(e),
+            }
+            result
+        })
+        .collect()
+}
@@ -1,71 +1,878 @@
+use serde::{Deserialize, Serialize};
 use std::path::Path;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+use whisper_rs::{
+    DtwMode, DtwModelPreset, DtwParameters, FullParams, SamplingStrategy, WhisperContext,
+    WhisperContextParameters,
+};
+
+use crate::audio::TARGET_SAMPLE_RATE;
+
+/// Bias prompt for the default multilingual setup.
+/// Keeps auto-detection to Russian and English only (suppresses Polish/Czech/etc.)
+pub const MULTILINGUAL_PROMPT: &str =
+    "Текст на русском или английском языке. Text in Russian or English.";
+
+/// Seconds at the start of a recording the language is detected from when
+/// `auto_lock` is on.
+pub const LANGUAGE_DETECT_SECS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptionSettings {
+    /// Drop segments shorter than this many milliseconds (0 = keep all).
+    #[serde(default)]
+    pub min_segment_ms: i64,
+    /// Drop segments whose mean token probability is below this (0.0 = keep all).
+    #[serde(default)]
+    pub min_segment_probability: f32,
+    /// Whisper language code such as "en" (None = auto-detect).
+    #[serde(default)]
+    pub language: Option<String>,
+    /// With auto-detect, detect the language once from the start of the
+    /// recording and force it for the rest, instead of letting Whisper
+    /// switch language between segments.
+    #[serde(default)]
+    pub auto_lock: bool,
+    /// Initial prompt used to bias recognition (empty = none).
+    #[serde(default = "default_initial_prompt")]
+    pub initial_prompt: String,
+    /// Candidates decoded per segment with greedy sampling (1 = fastest).
+    #[serde(default = "default_best_of")]
+    pub best_of: i32,
+    /// Treat the recording as silent when Whisper's no-speech probability,
+    /// averaged over the audio, reaches this and the decoded text is also
+    /// low-confidence (0.0 = never). See `is_no_speech`.
+    #[serde(default = "default_no_speech_threshold")]
+    pub no_speech_threshold: f32,
+    /// Don't let a segment start with a blank token, which is where many
+    /// silence hallucinations begin. whisper.cpp enables this by default.
+    #[serde(default = "crate::settings::default_true")]
+    pub suppress_blank: bool,
+    /// Suppress non-speech tokens (music notes, bracketed sound effects,
+    /// speaker dashes) while decoding. Stops "[Music]"-style output at the
+    /// source, but can occasionally swallow a valid short utterance.
+    #[serde(default)]
+    pub suppress_non_speech_tokens: bool,
+    /// A word, phrase or segment repeated back to back more than this many
+    /// times is a decoding loop ("the the the the the...") and is collapsed
+    /// to a single instance (0 = leave repetitions alone).
+    #[serde(default = "default_max_repeats")]
+    pub max_repeats: usize,
+    /// Don't condition each 30s window on the text decoded before it. Stops a
+    /// loop carrying over into the next window, at some cost in consistency
+    /// on long recordings.
+    #[serde(default)]
+    pub no_context: bool,
+    /// What goes between segments when they are joined into the text.
+    #[serde(default)]
+    pub segment_separator: SegmentSeparator,
+    /// CPU threads Whisper decodes with.
+    #[serde(default = "default_threads")]
+    pub threads: i32,
+}
+
+/// Settings remembered for one model file, applied over the global
+/// transcription settings while that model is in use. Unset fields follow
+/// the global value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelOverrides {
+    /// Whisper language code, or "auto" to auto-detect.
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub initial_prompt: Option<String>,
+    #[serde(default)]
+    pub threads: Option<i32>,
+}
+
+impl ModelOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl TranscriptionSettings {
+    /// These settings with a model's overrides applied.
+    pub fn with_overrides(&self, overrides: &ModelOverrides) -> Self {
+        let mut settings = self.clone();
+        if let Some(language) = &overrides.language {
+            settings.language = (language != "auto").then(|| language.clone());
+        }
+        if let Some(prompt) = &overrides.initial_prompt {
+            settings.initial_prompt = prompt.clone();
+        }
+        if let Some(threads) = overrides.threads {
+            settings.threads = threads;
+        }
+        settings
+    }
+}
+
+/// How `join_segments` puts Whisper's segments together.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentSeparator {
+    /// One running paragraph.
+    #[default]
+    Space,
+    /// Every segment on its own line.
+    Newline,
+    /// A new line after segments that end a sentence, a space otherwise.
+    Smart,
+}
+
+fn default_initial_prompt() -> String {
+    MULTILINGUAL_PROMPT.to_string()
+}
+
+fn default_best_of() -> i32 {
+    1
+}
+
+fn default_no_speech_threshold() -> f32 {
+    0.8
+}
+
+fn default_max_repeats() -> usize {
+    4
+}
+
+fn default_threads() -> i32 {
+    8
+}
+
+impl Default for TranscriptionSettings {
+    fn default() -> Self {
+        Self {
+            min_segment_ms: 0,
+            min_segment_probability: 0.0,
+            language: None,
+            auto_lock: false,
+            initial_prompt: default_initial_prompt(),
+            best_of: default_best_of(),
+            no_speech_threshold: default_no_speech_threshold(),
+            suppress_blank: true,
+            suppress_non_speech_tokens: false,
+            max_repeats: default_max_repeats(),
+            no_context: false,
+            segment_separator: SegmentSeparator::default(),
+            threads: default_threads(),
+        }
+    }
+}
+
+/// Options fixed when the model is loaded; changing any of them needs a reload.
+/// The defaults match a plain `WhisperContextParameters::default()`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContextSettings {
+    /// Flash attention: faster decoding on GPU backends that support it,
+    /// little effect on CPU. whisper.cpp can't combine it with DTW.
+    #[serde(default)]
+    pub flash_attn: bool,
+    /// GPU to run on when several are present (0 = first). Ignored in CPU builds.
+    #[serde(default)]
+    pub gpu_device: i32,
+    /// Token-level timestamps aligned with DTW. Experimental, costs extra
+    /// memory, and only available for the standard OpenAI model sizes.
+    #[serde(default)]
+    pub dtw_token_timestamps: bool,
+}
+
+/// DTW alignment heads preset for a model file, if whisper.cpp ships one.
+fn dtw_preset_for(model_filename: &str) -> Option<DtwModelPreset> {
+    let name = model_filename
+        .trim_start_matches("ggml-")
+        .trim_end_matches(".bin");
+    // Quantized variants such as "small.en-q5_1" share the base model's heads
+    let name = name.split('-').next().unwrap_or(name);
+    let preset = match name {
+        "tiny.en" => DtwModelPreset::TinyEn,
+        "tiny" => DtwModelPreset::Tiny,
+        "base.en" => DtwModelPreset::BaseEn,
+        "base" => DtwModelPreset::Base,
+        "small.en" => DtwModelPreset::SmallEn,
+        "small" => DtwModelPreset::Small,
+        "medium.en" => DtwModelPreset::MediumEn,
+        "medium" => DtwModelPreset::Medium,
+        "large" if model_filename.contains("v3-turbo") => DtwModelPreset::LargeV3Turbo,
+        "large" if model_filename.contains("v3") => DtwModelPreset::LargeV3,
+        "large" if model_filename.contains("v2") => DtwModelPreset::LargeV2,
+        "large" if model_filename.contains("v1") => DtwModelPreset::LargeV1,
+        _ => return None,
+    };
+    Some(preset)
+}
+
+/// One Whisper segment with the timing and confidence used for filtering.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub text: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub avg_probability: f32,
+    /// Whisper's estimate that the segment holds no speech at all.
+    pub no_speech_probability: f32,
+}
 
 pub struct WhisperEngine {
     context: Option<WhisperContext>,
+    settings: TranscriptionSettings,
+    context_settings: ContextSettings,
 }
 
 impl WhisperEngine {
     pub fn new() -> Self {
-        Self { context: None }
+        Self {
+            context: None,
+            settings: TranscriptionSettings::default(),
+            context_settings: ContextSettings::default(),
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: TranscriptionSettings) {
+        self.settings = settings;
+    }
+
+    /// Takes effect the next time a model is loaded.
+    pub fn set_context_settings(&mut self, settings: ContextSettings) {
+        self.context_settings = settings;
     }
 
     /// Load the Whisper model from disk. Expensive (~200-1100ms).
     /// Call once at startup and keep warm.
     pub fn load_model(&mut self, model_path: &Path) -> Result<(), String> {
-        log::info!("Loading Whisper model from {:?}...", model_path);
-        let ctx = WhisperContext::new_with_params(
-            model_path.to_str().ok_or("Invalid model path")?,
-            WhisperContextParameters::default(),
-        )
-        .map_err(|e| format!("Failed to load Whisper model: {}", e))?;
-
+        // A crash leaves the model that was loaded before in place
+        let ctx = catch_crash("loading the model", || self.load_context(model_path))?;
         self.context = Some(ctx);
         log::info!("Whisper model loaded successfully");
         Ok(())
     }
 
+    fn load_context(&self, model_path: &Path) -> Result<WhisperContext, String> {
+        log::info!(
+            "Loading Whisper model from {:?} ({:?})...",
+            model_path,
+            self.context_settings
+        );
+        let mut params = WhisperContextParameters::default();
+        params
+            .flash_attn(self.context_settings.flash_attn)
+            .gpu_device(self.context_settings.gpu_device);
+        if self.context_settings.dtw_token_timestamps {
+            let filename = model_path.file_name().unwrap_or_default().to_string_lossy();
+            match dtw_preset_for(&filename) {
+                Some(model_preset) => {
+                    params.dtw_parameters(DtwParameters {
+                        mode: DtwMode::ModelPreset { model_preset },
+                        ..DtwParameters::default()
+                    });
+                }
+                None => log::warn!("No DTW alignment heads for {}, DTW stays off", filename),
+            }
+        }
+
+        WhisperContext::new_with_params(model_path.to_str().ok_or("Invalid model path")?, params)
+            .map_err(|e| {
+                // whisper.cpp gives no context both for a file it can't parse
+                // and for failed allocations; a valid header means the latter
+                if super::models::has_ggml_magic(model_path) {
+                    log::error!("Whisper failed to allocate the model: {}", e);
+                    format!("{} ({})", MODEL_CRASH_MESSAGE, e)
+                } else {
+                    format!("Failed to load Whisper model: {}", e)
+                }
+            })
+    }
+
+    /// Drop the loaded model and free its memory.
+    pub fn unload(&mut self) {
+        self.context = None;
+    }
+
     pub fn is_loaded(&self) -> bool {
         self.context.is_some()
     }
 
-    /// Transcribe audio samples (must be 16kHz, mono, f32).
+    /// Whether the loaded model handles languages other than English
+    /// (None = no model loaded).
+    pub fn is_multilingual(&self) -> Option<bool> {
+        self.context.as_ref().map(|ctx| ctx.is_multilingual())
+    }
+
+    /// Language to force for a whole recording: the configured one or, with
+    /// `auto_lock`, the one detected at the start of `audio`. None leaves
+    /// Whisper to detect it per window, which is also the fallback when
+    /// detection fails.
+    pub fn recording_language(&self, audio: &[f32]) -> Option<String> {
+        if let Some(language) = &self.settings.language {
+            return Some(language.clone());
+        }
+        if !self.settings.auto_lock {
+            return None;
+        }
+        match self.detect_language(audio) {
+            Ok(language) => {
+                log::info!("Detected {}, locking it for this recording", language);
+                Some(language)
+            }
+            Err(e) => {
+                log::warn!("Language detection failed, falling back to auto-detect: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Whisper language code detected from the first LANGUAGE_DETECT_SECS of `audio`.
+    pub fn detect_language(&self, audio: &[f32]) -> Result<String, String> {
+        catch_crash("detecting the language", || self.detect(audio))
+    }
+
+    fn detect(&self, audio: &[f32]) -> Result<String, String> {
+        let ctx = self.context.as_ref().ok_or("Whisper model not loaded")?;
+        if !ctx.is_multilingual() {
+            return Ok("en".to_string());
+        }
+        if audio.is_empty() {
+            return Err("No audio to detect the language from".to_string());
+        }
+        let len = audio.len().min(LANGUAGE_DETECT_SECS * TARGET_SAMPLE_RATE as usize);
+        let threads = self.settings.threads.max(1) as usize;
+
+        let mut state = ctx
+            .create_state()
+            .map_err(state_allocation_failed)?;
+        state
+            .pcm_to_mel(&audio[..len], threads)
+            .map_err(|e| format!("Failed to compute the spectrogram: {}", e))?;
+        let (id, probs) = state
+            .lang_detect(0, threads)
+            .map_err(|e| format!("Whisper language detection failed: {}", e))?;
+        let code = whisper_rs::get_lang_str(id).ok_or_else(|| format!("Unknown language id {}", id))?;
+        log::debug!(
+            "Language detection: {} (p={:.2})",
+            code,
+            probs.get(id as usize).copied().unwrap_or_default()
+        );
+        Ok(code.to_string())
+    }
+
+    /// Transcribe audio samples (must be 16kHz `audio::TARGET_SAMPLE_RATE`, mono, f32)
+    /// in the configured language. Never runs `auto_lock` detection: a
+    /// recording settles its language once with `recording_language` and
+    /// passes it to `transcribe_as`.
     pub fn transcribe(&self, audio: &[f32]) -> Result<String, String> {
+        self.transcribe_as(audio, self.settings.language.as_deref())
+    }
+
+    /// Transcribe with the language forced to `language` (None = auto-detect
+    /// per window), whatever the settings say.
+    pub fn transcribe_as(&self, audio: &[f32], language: Option<&str>) -> Result<String, String> {
+        // Each decode has its own Whisper state, so the model is still
+        // usable after a caught crash
+        catch_crash("transcribing", || self.decode(audio, language))
+    }
+
+    fn decode(&self, audio: &[f32], language: Option<&str>) -> Result<String, String> {
         let ctx = self.context.as_ref().ok_or("Whisper model not loaded")?;
 
         let mut state = ctx
             .create_state()
-            .map_err(|e| format!("Failed to create Whisper state: {}", e))?;
+            .map_err(state_allocation_failed)?;
 
-        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-        params.set_language(None); // auto-detect language
-        // Bias model toward Russian and English only (suppresses Polish/Czech/etc.)
-        params.set_initial_prompt("Текст на русском или английском языке. Text in Russian or English.");
-        params.set_n_threads(8);
+        let best_of = self.settings.best_of.max(1);
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of });
+        params.set_language(language); // None = auto-detect
+        if !self.settings.initial_prompt.is_empty() {
+            params.set_initial_prompt(&self.settings.initial_prompt);
+        }
+        params.set_n_threads(self.settings.threads.max(1));
         params.set_print_special(false);
         params.set_print_progress(false);
         params.set_print_realtime(false);
         params.set_print_timestamps(false);
         params.set_translate(false);
         params.set_single_segment(false);
+        params.set_suppress_blank(self.settings.suppress_blank);
+        params.set_suppress_nst(self.settings.suppress_non_speech_tokens);
+        params.set_no_context(self.settings.no_context);
 
+        let started = std::time::Instant::now();
         state
             .full(params, audio)
             .map_err(|e| format!("Whisper transcription failed: {}", e))?;
+        if best_of > 1 {
+            // Each extra candidate adds decoding work, so this grows roughly with best_of
+            log::info!("Decoded with best_of={} in {:?}", best_of, started.elapsed());
+        }
 
-        let num_segments = state.full_n_segments();
-
-        let mut text = String::new();
-        for i in 0..num_segments {
+        let eot = ctx.token_eot();
+        let mut segments = Vec::new();
+        for i in 0..state.full_n_segments() {
             if let Some(segment) = state.get_segment(i) {
-                let seg_text = segment.to_string();
-                text.push_str(seg_text.trim());
-                if i < num_segments - 1 {
-                    text.push(' ');
-                }
+                // Average over text tokens only; special tokens sit at and above EOT
+                let probs: Vec<f32> = (0..segment.n_tokens())
+                    .filter_map(|t| segment.get_token(t))
+                    .filter(|tok| tok.token_id() < eot)
+                    .map(|tok| tok.token_probability())
+                    .collect();
+                let avg_probability = if probs.is_empty() {
+                    1.0
+                } else {
+                    probs.iter().sum::<f32>() / probs.len() as f32
+                };
+                segments.push(Segment {
+                    text: segment.to_string(),
+                    // Whisper timestamps are in centiseconds
+                    start_ms: segment.start_timestamp() * 10,
+                    end_ms: segment.end_timestamp() * 10,
+                    avg_probability,
+                    no_speech_probability: segment.no_speech_probability(),
+                });
+            }
+        }
+
+        // Whisper tends to hallucinate a phrase from pure noise
+        if is_no_speech(&segments, self.settings.no_speech_threshold) {
+            log::info!("Recording judged to contain no speech, discarding");
+            return Ok(String::new());
+        }
+
+        let segments = filter_segments(segments, &self.settings);
+        let max_repeats = self.settings.max_repeats;
+        let separator = self.settings.segment_separator;
+        if max_repeats == 0 {
+            return Ok(join_segments(&segments, separator));
+        }
+        let segments = collapse_repeated_segments(segments, max_repeats);
+        let text = join_segments(&segments, separator);
+        // Line by line, since collapsing normalizes whitespace
+        Ok(text
+            .lines()
+            .map(|line| collapse_repetitions(line, max_repeats))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+/// Start of the error returned when Whisper panics or fails to allocate
+/// while loading a model or decoding, which in practice is the model not
+/// fitting in memory.
+pub const MODEL_CRASH_MESSAGE: &str =
+    "Whisper crashed, most likely out of memory — try a smaller model";
+
+/// Whether `error` came from a Whisper crash caught by the engine.
+pub fn is_model_crash(error: &str) -> bool {
+    error.starts_with(MODEL_CRASH_MESSAGE)
+}
+
+/// A decode state (KV cache and compute buffers) that couldn't be created is
+/// an allocation failure, so it reads as a crash too.
+fn state_allocation_failed(e: whisper_rs::WhisperError) -> String {
+    log::error!("Whisper failed to allocate a state: {}", e);
+    format!("{} ({})", MODEL_CRASH_MESSAGE, e)
+}
+
+/// Run `f`, turning a panic into a MODEL_CRASH_MESSAGE error instead of
+/// letting it take the app down. An abort inside whisper.cpp itself can't
+/// be caught this way.
+fn catch_crash<T>(what: &str, f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let reason = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        log::error!("Whisper panicked while {}: {}", what, reason);
+        Err(format!("{} ({})", MODEL_CRASH_MESSAGE, reason))
+    })
+}
+
+/// Drop spurious segments (e.g. a lone word from a mic pop) that are too short
+/// or too low-confidence. If every segment would be dropped, keep them all so a
+/// short recording isn't discarded wholesale.
+pub fn filter_segments(segments: Vec<Segment>, settings: &TranscriptionSettings) -> Vec<Segment> {
+    if settings.min_segment_ms <= 0 && settings.min_segment_probability <= 0.0 {
+        return segments;
+    }
+
+    let (kept, dropped): (Vec<Segment>, Vec<Segment>) = segments.iter().cloned().partition(|s| {
+        s.end_ms - s.start_ms >= settings.min_segment_ms
+            && s.avg_probability >= settings.min_segment_probability
+    });
+
+    if kept.is_empty() {
+        return segments;
+    }
+    for s in &dropped {
+        log::info!(
+            "Dropped segment {:?} ({}ms, p={:.2})",
+            s.text.trim(),
+            s.end_ms - s.start_ms,
+            s.avg_probability
+        );
+    }
+    kept
+}
+
+/// Mean token probability the text must stay under for a recording to be
+/// judged silent. Whisper makes the same pairing, with an average
+/// log-probability below -1 (e^-1 = 0.37): the no-speech probability alone
+/// also runs high on quiet or very short real speech.
+const NO_SPEECH_MAX_PROBABILITY: f32 = 0.37;
+
+/// Whether the recording as a whole is silence: the duration-weighted mean
+/// no-speech probability reaches `threshold` and the decoded text is
+/// low-confidence (mean token probability under NO_SPEECH_MAX_PROBABILITY).
+/// A threshold of 0 disables this.
+pub fn is_no_speech(segments: &[Segment], threshold: f32) -> bool {
+    if threshold <= 0.0 || segments.is_empty() {
+        return false;
+    }
+    // Zero-length segments still count a little so they aren't ignored outright
+    let weight = |s: &Segment| (s.end_ms - s.start_ms).max(1) as f32;
+    let total: f32 = segments.iter().map(weight).sum();
+    let mean = |value: fn(&Segment) -> f32| {
+        segments.iter().map(|s| value(s) * weight(s)).sum::<f32>() / total
+    };
+    let no_speech = mean(|s| s.no_speech_probability);
+    let probability = mean(|s| s.avg_probability);
+    log::debug!(
+        "Mean no-speech probability {:.2}, token probability {:.2}",
+        no_speech,
+        probability
+    );
+    no_speech >= threshold && probability < NO_SPEECH_MAX_PROBABILITY
+}
+
+/// Longest phrase, in words, checked for back-to-back repetition.
+const MAX_REPEATED_PHRASE_WORDS: usize = 8;
+
+/// Keep one of each run of more than `max_repeats` segments with the same
+/// text, the shape a decoding loop takes across 30s windows.
+pub fn collapse_repeated_segments(segments: Vec<Segment>, max_repeats: usize) -> Vec<Segment> {
+    let same = |a: &Segment, b: &Segment| a.text.trim().eq_ignore_ascii_case(b.text.trim());
+    let mut kept: Vec<Segment> = Vec::with_capacity(segments.len());
+    let mut i = 0;
+    while i < segments.len() {
+        let run = segments[i..].iter().take_while(|s| same(s, &segments[i])).count();
+        if run > max_repeats {
+            log::warn!(
+                "Whisper repeated segment {:?} {} times, keeping one",
+                segments[i].text.trim(),
+                run
+            );
+            kept.push(segments[i].clone());
+        } else {
+            kept.extend_from_slice(&segments[i..i + run]);
+        }
+        i += run;
+    }
+    kept
+}
+
+/// Collapse a word or phrase (up to MAX_REPEATED_PHRASE_WORDS words) repeated
+/// back to back more than `max_repeats` times into one instance. Words are
+/// compared case-insensitively and ignoring punctuation, so "the, the, the"
+/// counts. Whitespace inside the text is normalized to single spaces.
+pub fn collapse_repetitions(text: &str, max_repeats: usize) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let norm: Vec<String> = words
+        .iter()
+        .map(|w| {
+            w.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect()
+        })
+        .collect();
+
+    let mut out: Vec<String> = Vec::with_capacity(words.len());
+    let mut i = 0;
+    'words: while i < words.len() {
+        for n in 1..=MAX_REPEATED_PHRASE_WORDS.min(words.len() - i) {
+            let phrase = &norm[i..i + n];
+            if phrase.iter().all(String::is_empty) {
+                continue;
+            }
+            let mut count = 1;
+            while norm.get(i + (count + 1) * n - 1).is_some()
+                && norm[i + count * n..i + (count + 1) * n] == *phrase
+            {
+                count += 1;
             }
+            if count > max_repeats {
+                log::warn!(
+                    "Whisper repeated {:?} {} times, keeping one",
+                    words[i..i + n].join(" "),
+                    count
+                );
+                // Keep the first copy's casing but the last copy's closing
+                // punctuation ("go, go, go!" ends on "go!")
+                let last = words[i + count * n - 1];
+                let tail = last.trim_end_matches(|c: char| !c.is_alphanumeric());
+                let kept_last = words[i + n - 1].trim_end_matches(|c: char| !c.is_alphanumeric());
+                out.extend(words[i..i + n - 1].iter().map(|w| w.to_string()));
+                out.push(format!("{}{}", kept_last, &last[tail.len()..]));
+                i += count * n;
+                continue 'words;
+            }
+        }
+        out.push(words[i].to_string());
+        i += 1;
+    }
+    out.join(" ")
+}
+
+/// Concatenate segment texts into the final transcription.
+pub fn join_segments(segments: &[Segment], separator: SegmentSeparator) -> String {
+    join_texts(segments.iter().map(|s| s.text.as_str()), separator)
+}
+
+/// Join pieces of a transcription (segments, or the texts of chunked
+/// recordings), skipping empty ones. In Smart mode an ellipsis doesn't end a
+/// sentence, since Whisper uses it for speech that trails off mid-thought.
+pub fn join_texts<'a>(
+    texts: impl IntoIterator<Item = &'a str>,
+    separator: SegmentSeparator,
+) -> String {
+    let mut out = String::new();
+    for text in texts.into_iter().map(str::trim).filter(|t| !t.is_empty()) {
+        if !out.is_empty() {
+            let newline = match separator {
+                SegmentSeparator::Space => false,
+                SegmentSeparator::Newline => true,
+                SegmentSeparator::Smart => ends_sentence(&out),
+            };
+            out.push(if newline { '\n' } else { ' ' });
         }
+        out.push_str(text);
+    }
+    out
+}
+
+/// Whether `text` ends with ".", "!" or "?", allowing closing quotes or
+/// brackets after it.
+fn ends_sentence(text: &str) -> bool {
+    let text = text.trim_end_matches(['"', '\'', '”', '»', ')']);
+    if text.ends_with("...") || text.ends_with('…') {
+        return false;
+    }
+    text.ends_with(['.', '!', '?'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(text: &str, start_ms: i64, end_ms: i64, avg_probability: f32) -> Segment {
+        Segment {
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+            avg_probability,
+            no_speech_probability: 0.0,
+        }
+    }
+
+    fn texts(segments: &[Segment]) -> Vec<&str> {
+        segments.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    fn filter_settings(min_segment_ms: i64, min_segment_probability: f32) -> TranscriptionSettings {
+        TranscriptionSettings {
+            min_segment_ms,
+            min_segment_probability,
+            ..TranscriptionSettings::default()
+        }
+    }
+
+    #[test]
+    fn filter_keeps_everything_when_disabled() {
+        let segments = vec![seg(" Uh", 0, 50, 0.1), seg(" Hello there.", 50, 2000, 0.9)];
+        let kept = filter_segments(segments, &filter_settings(0, 0.0));
+        assert_eq!(texts(&kept), [" Uh", " Hello there."]);
+    }
+
+    #[test]
+    fn filter_drops_short_leading_segment() {
+        let segments = vec![seg(" You", 0, 120, 0.9), seg(" Hello there.", 120, 2000, 0.9)];
+        let kept = filter_segments(segments, &filter_settings(200, 0.0));
+        assert_eq!(texts(&kept), [" Hello there."]);
+    }
+
+    #[test]
+    fn filter_drops_low_probability_segment() {
+        let segments = vec![
+            seg(" Hello there.", 0, 1500, 0.9),
+            seg(" Thanks for watching!", 1500, 3000, 0.2),
+        ];
+        let kept = filter_segments(segments, &filter_settings(0, 0.5));
+        assert_eq!(texts(&kept), [" Hello there."]);
+    }
+
+    #[test]
+    fn filter_keeps_a_lone_short_segment() {
+        let segments = vec![seg(" Yes.", 0, 150, 0.3)];
+        let kept = filter_segments(segments, &filter_settings(200, 0.5));
+        assert_eq!(texts(&kept), [" Yes."]);
+    }
+
+    fn speech(no_speech_probability: f32, avg_probability: f32, ms: i64) -> Segment {
+        Segment {
+            no_speech_probability,
+            ..seg(" Thank you.", 0, ms, avg_probability)
+        }
+    }
+
+    #[test]
+    fn no_speech_needs_both_signals() {
+        // Hallucination from noise: Whisper is sure there's no speech and
+        // unsure of the words
+        assert!(is_no_speech(&[speech(0.9, 0.2, 2000)], 0.8));
+        // Quiet real speech: high no-speech probability, confident words
+        assert!(!is_no_speech(&[speech(0.9, 0.8, 2000)], 0.8));
+        // Unsure words, but Whisper hears speech
+        assert!(!is_no_speech(&[speech(0.3, 0.2, 2000)], 0.8));
+    }
+
+    #[test]
+    fn no_speech_is_weighted_by_duration() {
+        let segments = [speech(0.95, 0.1, 9000), speech(0.1, 0.9, 1000)];
+        assert!(is_no_speech(&segments, 0.8));
+        let segments = [speech(0.95, 0.1, 1000), speech(0.1, 0.9, 9000)];
+        assert!(!is_no_speech(&segments, 0.8));
+    }
+
+    #[test]
+    fn no_speech_off_or_empty() {
+        assert!(!is_no_speech(&[speech(1.0, 0.0, 2000)], 0.0));
+        assert!(!is_no_speech(&[], 0.8));
+    }
+
+    #[test]
+    fn join_trims_and_skips_empty_segments() {
+        let segments = vec![
+            seg(" Hello.", 0, 1000, 0.9),
+            seg("  ", 1000, 1100, 0.9),
+            seg(" World", 1100, 2000, 0.9),
+        ];
+        assert_eq!(join_segments(&segments, SegmentSeparator::Space), "Hello. World");
+        assert_eq!(join_segments(&[], SegmentSeparator::Space), "");
+    }
+
+    #[test]
+    fn repeated_segments_collapse_past_the_limit() {
+        let mut segments = vec![seg(" Hello.", 0, 1000, 0.9)];
+        for i in 1..=6 {
+            segments.push(seg(" Thank you.", i * 1000, (i + 1) * 1000, 0.9));
+        }
+        segments.push(seg(" Bye.", 7000, 8000, 0.9));
+        let kept = collapse_repeated_segments(segments, 4);
+        assert_eq!(texts(&kept), [" Hello.", " Thank you.", " Bye."]);
+    }
+
+    #[test]
+    fn repeated_segments_within_the_limit_stay() {
+        let segments = vec![
+            seg(" Go.", 0, 500, 0.9),
+            seg(" go. ", 500, 1000, 0.9),
+            seg(" Stop.", 1000, 1500, 0.9),
+            seg(" Go.", 1500, 2000, 0.9),
+        ];
+        let kept = collapse_repeated_segments(segments, 2);
+        assert_eq!(texts(&kept), [" Go.", " go. ", " Stop.", " Go."]);
+    }
+
+    #[test]
+    fn repeated_words_and_phrases_collapse() {
+        assert_eq!(collapse_repetitions("the, the, the, the, the end", 4), "the end");
+        assert_eq!(
+            collapse_repetitions("I mean I mean I mean I mean I mean it", 3),
+            "I mean it"
+        );
+        assert_eq!(collapse_repetitions("Go, go, go, go, go!", 4), "Go!");
+        assert_eq!(collapse_repetitions("no no no", 4), "no no no");
+        assert_eq!(collapse_repetitions("да да да да да", 4), "да");
+    }
+
+    fn sentences() -> Vec<Segment> {
+        vec![
+            seg(" Hello there.", 0, 1000, 0.9),
+            seg(" so I was thinking", 1000, 2000, 0.9),
+            seg(" we could go…", 2000, 3000, 0.9),
+            seg(" maybe tomorrow?", 3000, 4000, 0.9),
+            seg(" He said \"fine.\"", 4000, 5000, 0.9),
+            seg(" Right...", 5000, 6000, 0.9),
+            seg(" OK", 6000, 7000, 0.9),
+        ]
+    }
+
+    #[test]
+    fn join_space_keeps_one_paragraph() {
+        assert_eq!(
+            join_segments(&sentences(), SegmentSeparator::Space),
+            "Hello there. so I was thinking we could go… maybe tomorrow? \
+             He said \"fine.\" Right... OK"
+        );
+    }
+
+    #[test]
+    fn join_newline_puts_every_segment_on_its_own_line() {
+        assert_eq!(
+            join_segments(&sentences(), SegmentSeparator::Newline),
+            "Hello there.\nso I was thinking\nwe could go…\nmaybe tomorrow?\n\
+             He said \"fine.\"\nRight...\nOK"
+        );
+    }
+
+    #[test]
+    fn join_smart_breaks_only_after_sentences() {
+        assert_eq!(
+            join_segments(&sentences(), SegmentSeparator::Smart),
+            "Hello there.\nso I was thinking we could go… maybe tomorrow?\n\
+             He said \"fine.\"\nRight... OK"
+        );
+    }
+
+    #[test]
+    fn sentence_ends_allow_closing_quotes_but_not_ellipses() {
+        assert!(ends_sentence("Done!"));
+        assert!(ends_sentence("He said \"fine.\""));
+        assert!(ends_sentence("(see above.)"));
+        assert!(ends_sentence("«Да?»"));
+        assert!(!ends_sentence("and then..."));
+        assert!(!ends_sentence("and then…"));
+        assert!(!ends_sentence("and then,"));
+        assert!(!ends_sentence(""));
+    }
+
+    #[test]
+    fn overrides_replace_only_what_they_set() {
+        let global = TranscriptionSettings {
+            language: Some("en".to_string()),
+            initial_prompt: "Hello.".to_string(),
+            threads: 4,
+            ..TranscriptionSettings::default()
+        };
+        assert_eq!(global.with_overrides(&ModelOverrides::default()).language, global.language);
+
+        let overrides = ModelOverrides {
+            language: Some("ru".to_string()),
+            threads: Some(8),
+            ..ModelOverrides::default()
+        };
+        let applied = global.with_overrides(&overrides);
+        assert_eq!(applied.language.as_deref(), Some("ru"));
+        assert_eq!(applied.threads, 8);
+        assert_eq!(applied.initial_prompt, "Hello.");
 
-        Ok(text.trim().to_string())
+        let auto = ModelOverrides {
+            language: Some("auto".to_string()),
+            initial_prompt: Some(String::new()),
+            ..ModelOverrides::default()
+        };
+        let applied = global.with_overrides(&auto);
+        assert_eq!(applied.language, None);
+        assert_eq!(applied.initial_prompt, "");
+        assert_eq!(applied.threads, 4);
     }
 }
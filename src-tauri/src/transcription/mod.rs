@@ -1,2 +1,3 @@
+pub mod benchmark;
 pub mod engine;
 pub mod models;
@@ -1,50 +1,457 @@
-use std::path::PathBuf;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 
+const MODEL_HOST: &str = "https://huggingface.co";
 const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+const MODEL_REPO_PATH: &str = "/ggerganov/whisper.cpp/resolve/main";
 
+/// Check a user-supplied mirror URL and return it normalized (no trailing slash).
+pub fn validate_mirror_url(mirror: &str) -> Result<String, String> {
+    let url = Url::parse(mirror.trim()).map_err(|e| format!("Invalid mirror URL: {}", e))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(format!("Mirror URL must be http or https, got {}", url.scheme()));
+    }
+    if url.host_str().is_none() {
+        return Err("Mirror URL has no host".to_string());
+    }
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
+/// Base URL to download models from. A bare mirror host (e.g. "https://hf-mirror.com")
+/// replaces the HuggingFace host and keeps the repo path; a mirror with its own path
+/// is used as-is. Empty or invalid mirrors fall back to HuggingFace.
+pub fn model_base_url(mirror: &str) -> String {
+    if mirror.trim().is_empty() {
+        return MODEL_BASE_URL.to_string();
+    }
+    let mirror = match validate_mirror_url(mirror) {
+        Ok(m) => m,
+        Err(e) => {
+            log::warn!("{}, using default model host", e);
+            return MODEL_BASE_URL.to_string();
+        }
+    };
+    match Url::parse(&mirror) {
+        Ok(url) if url.path() == "/" => format!("{}{}", mirror, MODEL_REPO_PATH),
+        _ => mirror,
+    }
+}
+
+/// Host for repos outside whisper.cpp (distil-whisper). Only a bare-host
+/// mirror can stand in for it; mirrors with their own path serve whisper.cpp only.
+fn hf_host(mirror: &str) -> String {
+    match validate_mirror_url(mirror).and_then(|m| Url::parse(&m).map_err(|e| e.to_string())) {
+        Ok(url) if url.path() == "/" => url.as_str().trim_end_matches('/').to_string(),
+        _ => MODEL_HOST.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ModelInfo {
     pub name: String,
     pub filename: String,
     pub url: String,
     pub size_bytes: u64,
+    /// Quantization level such as "q5_0" (None = full precision).
+    pub quantization: Option<String>,
+}
+
+fn model(name: &str, filename: &str, url: String, size_bytes: u64) -> ModelInfo {
+    ModelInfo {
+        name: name.to_string(),
+        filename: filename.to_string(),
+        url,
+        size_bytes,
+        quantization: quantization(filename).map(str::to_string),
+    }
+}
+
+/// Quantization level in a model file name: "ggml-medium-q5_0.bin" ->
+/// "q5_0". None for full-precision files.
+pub fn quantization(filename: &str) -> Option<&str> {
+    let stem = filename.strip_suffix(".bin").unwrap_or(filename);
+    let (_, level) = stem.rsplit_once('-')?;
+    let valid = level.starts_with('q')
+        && level.len() > 1
+        && level[1..].chars().all(|c| c.is_ascii_digit() || c == '_');
+    valid.then_some(level)
 }
 
-pub fn get_available_models() -> Vec<ModelInfo> {
+/// Catalog of downloadable models, with URLs pointed at `mirror` when set.
+pub fn get_available_models(mirror: &str) -> Vec<ModelInfo> {
+    let base = model_base_url(mirror);
+    let hf = hf_host(mirror);
+    let ggml = |file: &str| format!("{}/{}", base, file);
+
     vec![
-        ModelInfo {
-            name: "base.en".to_string(),
-            filename: "ggml-base.en.bin".to_string(),
-            url: format!("{}/ggml-base.en.bin", MODEL_BASE_URL),
-            size_bytes: 147_951_465,
-        },
-        ModelInfo {
-            name: "small.en".to_string(),
-            filename: "ggml-small.en.bin".to_string(),
-            url: format!("{}/ggml-small.en.bin", MODEL_BASE_URL),
-            size_bytes: 487_601_024,
-        },
-        ModelInfo {
-            name: "medium.en".to_string(),
-            filename: "ggml-medium.en.bin".to_string(),
-            url: format!("{}/ggml-medium.en.bin", MODEL_BASE_URL),
-            size_bytes: 1_533_774_848,
-        },
+        model("base.en", "ggml-base.en.bin", ggml("ggml-base.en.bin"), 147_951_465),
+        model("small.en", "ggml-small.en.bin", ggml("ggml-small.en.bin"), 487_601_024),
+        model("medium.en", "ggml-medium.en.bin", ggml("ggml-medium.en.bin"), 1_533_774_848),
+        model("large-v3", "ggml-large-v3.bin", ggml("ggml-large-v3.bin"), 3_095_033_483),
+        model(
+            "large-v3-turbo",
+            "ggml-large-v3-turbo.bin",
+            ggml("ggml-large-v3-turbo.bin"),
+            1_624_555_275,
+        ),
+        // Quantized: a fraction of the size and faster on CPU, for a small
+        // loss in accuracy. q8_0 is close to full precision, q5 is smaller.
+        // Sizes are approximate.
+        model("base.en-q5_1", "ggml-base.en-q5_1.bin", ggml("ggml-base.en-q5_1.bin"), 59_700_000),
+        model("base.en-q8_0", "ggml-base.en-q8_0.bin", ggml("ggml-base.en-q8_0.bin"), 81_800_000),
+        model(
+            "small.en-q5_1",
+            "ggml-small.en-q5_1.bin",
+            ggml("ggml-small.en-q5_1.bin"),
+            190_000_000,
+        ),
+        model(
+            "small.en-q8_0",
+            "ggml-small.en-q8_0.bin",
+            ggml("ggml-small.en-q8_0.bin"),
+            264_000_000,
+        ),
+        model("medium-q5_0", "ggml-medium-q5_0.bin", ggml("ggml-medium-q5_0.bin"), 539_000_000),
+        model("medium-q8_0", "ggml-medium-q8_0.bin", ggml("ggml-medium-q8_0.bin"), 823_000_000),
+        model(
+            "medium.en-q5_0",
+            "ggml-medium.en-q5_0.bin",
+            ggml("ggml-medium.en-q5_0.bin"),
+            539_000_000,
+        ),
+        model(
+            "medium.en-q8_0",
+            "ggml-medium.en-q8_0.bin",
+            ggml("ggml-medium.en-q8_0.bin"),
+            823_000_000,
+        ),
+        model(
+            "large-v3-q5_0",
+            "ggml-large-v3-q5_0.bin",
+            ggml("ggml-large-v3-q5_0.bin"),
+            1_080_000_000,
+        ),
+        model(
+            "large-v3-turbo-q5_0",
+            "ggml-large-v3-turbo-q5_0.bin",
+            ggml("ggml-large-v3-turbo-q5_0.bin"),
+            574_000_000,
+        ),
+        model(
+            "large-v3-turbo-q8_0",
+            "ggml-large-v3-turbo-q8_0.bin",
+            ggml("ggml-large-v3-turbo-q8_0.bin"),
+            874_000_000,
+        ),
+        // Distilled models: much faster decoding, English-only except distil-large-v3
+        model(
+            "distil-small.en",
+            "ggml-distil-small.en.bin",
+            format!("{}/distil-whisper/distil-small.en/resolve/main/ggml-distil-small.en.bin", hf),
+            336_000_000,
+        ),
+        model(
+            "distil-medium.en",
+            "ggml-distil-medium.en.bin",
+            format!("{}/distil-whisper/distil-medium.en/resolve/main/ggml-medium-32-2.en.bin", hf),
+            789_000_000,
+        ),
+        model(
+            "distil-large-v3",
+            "ggml-distil-large-v3.bin",
+            format!("{}/distil-whisper/distil-large-v3-ggml/resolve/main/ggml-distil-large-v3.bin", hf),
+            1_520_000_000,
+        ),
     ]
 }
 
+/// How comfortably a model fits in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryFit {
+    Ok,
+    Tight,
+    Insufficient,
+    Unknown,
+}
+
+/// Result of the memory preflight run before downloading or loading a model.
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryAdvisory {
+    pub model: String,
+    pub required_bytes: u64,
+    pub available_bytes: Option<u64>,
+    pub fit: MemoryFit,
+    pub message: String,
+}
+
+/// Rough check of whether a model of `size_bytes` fits in available RAM.
+/// whisper.cpp needs the weights plus compute buffers, so assume ~1.4x the file size.
+pub fn memory_advisory(model: &str, size_bytes: u64, available_bytes: Option<u64>) -> MemoryAdvisory {
+    let required_bytes = size_bytes + size_bytes * 2 / 5;
+    let gb = |b: u64| b as f64 / 1_073_741_824.0;
+
+    let (fit, message) = match available_bytes {
+        None => (
+            MemoryFit::Unknown,
+            format!("{} needs about {:.1} GB of RAM", model, gb(required_bytes)),
+        ),
+        Some(avail) if required_bytes > avail => (
+            MemoryFit::Insufficient,
+            format!(
+                "{} needs about {:.1} GB but only {:.1} GB is free. It will likely thrash or fail to load — pick a smaller or quantized model.",
+                model,
+                gb(required_bytes),
+                gb(avail)
+            ),
+        ),
+        Some(avail) if required_bytes > avail / 4 * 3 => (
+            MemoryFit::Tight,
+            format!(
+                "{} needs about {:.1} GB of the {:.1} GB free. Close other apps before loading it.",
+                model,
+                gb(required_bytes),
+                gb(avail)
+            ),
+        ),
+        Some(_) => (
+            MemoryFit::Ok,
+            format!("{} fits in available memory", model),
+        ),
+    };
+
+    MemoryAdvisory {
+        model: model.to_string(),
+        required_bytes,
+        available_bytes,
+        fit,
+        message,
+    }
+}
+
+/// Weight of the newest measurement in a model's running real-time factor.
+const RTF_SMOOTHING: f32 = 0.3;
+
+/// Transcription speed measured for a model on this machine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelSpeed {
+    /// Processing time / audio duration, smoothed over recent transcriptions.
+    pub rtf: f32,
+    /// Thread count the measurements were taken with.
+    pub threads: i32,
+    pub samples: u32,
+}
+
+impl ModelSpeed {
+    pub fn new(rtf: f32, threads: i32) -> Self {
+        Self {
+            rtf,
+            threads,
+            samples: 1,
+        }
+    }
+
+    /// Fold in a new measurement. A change of thread count starts over,
+    /// since the old measurements no longer apply.
+    pub fn record(&mut self, rtf: f32, threads: i32) {
+        if threads != self.threads {
+            *self = Self::new(rtf, threads);
+            return;
+        }
+        self.rtf += (rtf - self.rtf) * RTF_SMOOTHING;
+        self.samples = self.samples.saturating_add(1);
+    }
+}
+
+/// Rough real-time factor of a model on a typical 8-thread CPU, used until
+/// the model has been measured here.
+pub fn default_rtf(filename: &str) -> f32 {
+    let name = filename.trim_start_matches("ggml-");
+    if name.starts_with("distil-small") {
+        0.15
+    } else if name.starts_with("distil-medium") {
+        0.35
+    } else if name.starts_with("distil-large") {
+        0.6
+    } else if name.starts_with("tiny") {
+        0.05
+    } else if name.starts_with("base") {
+        0.1
+    } else if name.starts_with("small") {
+        0.3
+    } else if name.starts_with("medium") {
+        0.8
+    } else if name.contains("turbo") {
+        0.5
+    } else if name.starts_with("large") {
+        1.5
+    } else {
+        // Unknown size: assume medium rather than promise too much
+        0.8
+    }
+}
+
+/// Expected real-time factor of `filename` at `threads`: the measured speed
+/// when there is one, the default otherwise. Measurements taken with another
+/// thread count are scaled as if speed were proportional to threads, which
+/// overstates the gain from adding threads but is close enough for a warning.
+pub fn expected_rtf(filename: &str, measured: Option<&ModelSpeed>, threads: i32) -> f32 {
+    match measured {
+        Some(speed) => speed.rtf * speed.threads.max(1) as f32 / threads.max(1) as f32,
+        None => default_rtf(filename) * 8.0 / threads.max(1) as f32,
+    }
+}
+
+/// What a model can transcribe.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelCapabilities {
+    pub model_filename: String,
+    /// True when read from the loaded model, false when guessed from the name.
+    pub loaded: bool,
+    pub multilingual: bool,
+}
+
+/// English-only models carry ".en" in the name: "ggml-small.en.bin",
+/// "ggml-base.en-q5_1.bin", "ggml-distil-small.en.bin".
+pub fn is_english_only(filename: &str) -> bool {
+    filename.contains(".en.") || filename.contains(".en-")
+}
+
+/// Warning for dictating in a non-English `language` with an English-only
+/// model, which comes out as garbage or an English translation.
+pub fn language_mismatch(model: &str, multilingual: bool, language: Option<&str>) -> Option<String> {
+    match language {
+        Some(language) if !multilingual && language != "en" => Some(format!(
+            "{} only understands English, so \"{}\" speech won't transcribe correctly. Pick a multilingual model (one without .en in the name).",
+            model, language
+        )),
+        _ => None,
+    }
+}
+
+/// Model files present in the models directory, sorted by name.
+pub fn downloaded_models(models_dir: &PathBuf) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(models_dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| name.ends_with(".bin"))
+        .collect();
+    names.sort();
+    names
+}
+
 pub fn model_exists(models_dir: &PathBuf, filename: &str) -> bool {
     models_dir.join(filename).exists()
 }
 
-/// Download model file. Phase 1: simple blocking download.
-pub async fn download_model(models_dir: &PathBuf, model: &ModelInfo) -> Result<PathBuf, String> {
+/// First bytes of a whisper.cpp model: the ggml magic 0x67676d6c, little-endian.
+const GGML_MAGIC: &[u8] = b"lmgg";
+
+/// Whether the file at `path` starts with the ggml magic.
+pub fn has_ggml_magic(path: &Path) -> bool {
+    let mut header = [0u8; 4];
+    std::fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .is_ok()
+        && header == GGML_MAGIC
+}
+
+/// Why a model download failed, so the UI can suggest the right fix.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "detail")]
+pub enum ModelDownloadError {
+    /// The server couldn't be reached or the connection dropped.
+    Network(String),
+    /// The server answered with an error status (404 = wrong URL or mirror).
+    HttpStatus(u16),
+    /// No space left for the model file.
+    DiskFull,
+    /// Any other file system error.
+    Io(String),
+    /// The file arrived truncated or isn't a ggml model.
+    ChecksumMismatch(String),
+}
+
+impl ModelDownloadError {
+    /// Message suitable for showing to the user, with a suggested remedy.
+    pub fn user_message(&self) -> String {
+        match self {
+            ModelDownloadError::Network(_) => {
+                "Could not reach the download server. Check your internet connection, or set a model mirror if HuggingFace is blocked.".to_string()
+            }
+            ModelDownloadError::HttpStatus(404) => {
+                "The model was not found at its download URL. If you use a model mirror, check that it hosts this model.".to_string()
+            }
+            ModelDownloadError::HttpStatus(status) if *status == 401 || *status == 403 => {
+                format!("The download server refused access (HTTP {}). Try again later or use a different mirror.", status)
+            }
+            ModelDownloadError::HttpStatus(status) => {
+                format!("The download server returned an error (HTTP {}). Try again in a few minutes.", status)
+            }
+            ModelDownloadError::DiskFull => {
+                "Not enough disk space for the model. Free up space, or pick a smaller model.".to_string()
+            }
+            ModelDownloadError::Io(_) => {
+                "Could not save the model file. Check that the models folder is writable.".to_string()
+            }
+            ModelDownloadError::ChecksumMismatch(_) => {
+                "The downloaded file is incomplete or not a Whisper model, so it was discarded. Try downloading again.".to_string()
+            }
+        }
+    }
+
+    fn from_io(e: std::io::Error) -> Self {
+        // For platforms that don't map it to StorageFull. Each code means
+        // something else on the other OS, so it only counts where it applies.
+        let disk_full_code = match e.raw_os_error() {
+            Some(28) => cfg!(unix),     // ENOSPC
+            Some(112) => cfg!(windows), // ERROR_DISK_FULL
+            _ => false,
+        };
+        if e.kind() == std::io::ErrorKind::StorageFull || disk_full_code {
+            ModelDownloadError::DiskFull
+        } else {
+            ModelDownloadError::Io(e.to_string())
+        }
+    }
+}
+
+impl fmt::Display for ModelDownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelDownloadError::Network(e) => write!(f, "Model download failed: {}", e),
+            ModelDownloadError::HttpStatus(status) => {
+                write!(f, "Model download failed with HTTP status {}", status)
+            }
+            ModelDownloadError::DiskFull => write!(f, "Disk full while writing the model file"),
+            ModelDownloadError::Io(e) => write!(f, "Failed to write model file: {}", e),
+            ModelDownloadError::ChecksumMismatch(e) => write!(f, "Downloaded model failed verification: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ModelDownloadError {}
+
+/// Download a model file. It is streamed to "<file>.part" and renamed once
+/// complete, so an interrupted download never shows up as a model. The
+/// catalog has no hashes, so verification is the Content-Length and the
+/// ggml magic at the start of the file.
+pub async fn download_model(
+    models_dir: &PathBuf,
+    model: &ModelInfo,
+) -> Result<PathBuf, ModelDownloadError> {
     let dest = models_dir.join(&model.filename);
     if dest.exists() {
         return Ok(dest);
     }
 
-    std::fs::create_dir_all(models_dir)
-        .map_err(|e| format!("Failed to create models dir: {}", e))?;
+    std::fs::create_dir_all(models_dir).map_err(ModelDownloadError::from_io)?;
 
     log::info!(
         "Downloading model {} ({} bytes)...",
@@ -52,24 +459,47 @@ pub async fn download_model(models_dir: &PathBuf, model: &ModelInfo) -> Result<P
         model.size_bytes
     );
 
-    let response = reqwest::get(&model.url)
+    let mut response = reqwest::get(&model.url)
         .await
-        .map_err(|e| format!("Failed to download model: {}", e))?;
+        .map_err(|e| ModelDownloadError::Network(e.to_string()))?;
 
     if !response.status().is_success() {
-        return Err(format!(
-            "Download failed with status: {}",
-            response.status()
-        ));
+        return Err(ModelDownloadError::HttpStatus(response.status().as_u16()));
     }
+    let expected_len = response.content_length();
 
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+    let partial = models_dir.join(format!("{}.part", model.filename));
+    let result = async {
+        let mut file = std::fs::File::create(&partial).map_err(ModelDownloadError::from_io)?;
+        let mut received: u64 = 0;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| ModelDownloadError::Network(e.to_string()))?
+        {
+            if received == 0 && !chunk.starts_with(GGML_MAGIC) {
+                return Err(ModelDownloadError::ChecksumMismatch(
+                    "file does not start with the ggml magic".to_string(),
+                ));
+            }
+            file.write_all(&chunk).map_err(ModelDownloadError::from_io)?;
+            received += chunk.len() as u64;
+        }
+        if let Some(expected) = expected_len.filter(|&n| n != received) {
+            return Err(ModelDownloadError::ChecksumMismatch(format!(
+                "received {} of {} bytes",
+                received, expected
+            )));
+        }
+        file.sync_all().map_err(ModelDownloadError::from_io)
+    }
+    .await;
 
-    std::fs::write(&dest, &bytes)
-        .map_err(|e| format!("Failed to write model file: {}", e))?;
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    std::fs::rename(&partial, &dest).map_err(ModelDownloadError::from_io)?;
 
     log::info!("Model downloaded to {:?}", dest);
     Ok(dest)
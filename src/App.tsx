@@ -11,11 +11,70 @@ interface SoundSettings {
 }
 
 interface AiSettings {
-  provider: "none" | "openai" | "claude";
+  provider: "none" | "openai" | "claude" | "azure_openai" | "rules";
   api_key: string;
   openai_model: string;
   claude_model: string;
   prompt: string;
+  azure_resource: string;
+  azure_deployment: string;
+  azure_api_version: string;
+  prompt_in_user_message: boolean;
+  max_input_chars: number;
+  raw_fallback: boolean;
+  max_length_ratio: number;
+  min_length_ratio: number;
+  custom_headers: Record<string, string>;
+}
+
+interface FormatOutcome {
+  text: string;
+  provider: AiSettings["provider"] | null;
+  model: string | null;
+  errors: string[];
+  rejected: string | null;
+}
+
+interface MicTest {
+  peak_dbfs: number | null;
+  rms_dbfs: number | null;
+  clipped_ratio: number;
+  level: "too_quiet" | "good" | "clipping";
+}
+
+interface AudioDeviceInfo {
+  name: string;
+  sample_rate: number;
+  channels: number;
+}
+
+interface InputDevice {
+  name: string | null;
+  connected: boolean;
+}
+
+interface ModelDownloadFailure {
+  filename: string;
+  error: { kind: string; detail?: string | number };
+  message: string;
+}
+
+interface ClippingWarning {
+  ratio: number;
+  message: string;
+}
+
+interface TranscriptionPair {
+  raw: string;
+  formatted: string;
+}
+
+interface FileTranscription {
+  path: string;
+  index: number;
+  total: number;
+  text: string | null;
+  error: string | null;
 }
 
 function App() {
@@ -23,20 +82,54 @@ function App() {
   const [lastTranscription, setLastTranscription] = useState("");
   const [streamingPreview, setStreamingPreview] = useState("");
   const [modelLoaded, setModelLoaded] = useState(false);
+  const [modelLoading, setModelLoading] = useState(false);
+  const [modelIdleUnloaded, setModelIdleUnloaded] = useState(false);
   const [modelsDir, setModelsDir] = useState("");
   const [hotkey, setHotkey] = useState("Ctrl+Shift+Space");
   const [isCapturingHotkey, setIsCapturingHotkey] = useState(false);
   const [hotkeyError, setHotkeyError] = useState("");
+  const [aiError, setAiError] = useState("");
+  const [clippingWarning, setClippingWarning] = useState("");
+  const [deviceWarning, setDeviceWarning] = useState("");
+  const [modelWarning, setModelWarning] = useState("");
+  const [busyWarning, setBusyWarning] = useState("");
+  const [formatWarning, setFormatWarning] = useState("");
+  const [clipboardWarning, setClipboardWarning] = useState("");
+  const [aiEnabled, setAiEnabled] = useState(true);
+  const [secondsLeft, setSecondsLeft] = useState<number | null>(null);
+  const [previewInput, setPreviewInput] = useState("");
+  const [previewResult, setPreviewResult] = useState<FormatOutcome | null>(null);
+  const [previewError, setPreviewError] = useState("");
+  const [previewRunning, setPreviewRunning] = useState(false);
+  const [micTest, setMicTest] = useState("");
+  const [micTesting, setMicTesting] = useState(false);
+  const [inputDevices, setInputDevices] = useState<AudioDeviceInfo[]>([]);
+  const [inputDevice, setInputDevice] = useState("");
+  const [inputDeviceError, setInputDeviceError] = useState("");
+  const [keepSoundOutput, setKeepSoundOutput] = useState(true);
   const [startSound, setStartSound] = useState("");
   const [stopSound, setStopSound] = useState("");
   const [soundVolume, setSoundVolume] = useState(0.5);
   const [showSettings, setShowSettings] = useState(false);
+  const [diagnosticsCopied, setDiagnosticsCopied] = useState(false);
+  const [postCommand, setPostCommand] = useState("");
+  const [postCommandError, setPostCommandError] = useState("");
+  const [fileTranscriptions, setFileTranscriptions] = useState<FileTranscription[]>([]);
   const [aiSettings, setAiSettings] = useState<AiSettings>({
     provider: "none",
     api_key: "",
     openai_model: "gpt-4o-mini",
     claude_model: "claude-sonnet-4-20250514",
     prompt: "",
+    azure_resource: "",
+    azure_deployment: "",
+    azure_api_version: "2024-06-01",
+    prompt_in_user_message: false,
+    max_input_chars: 6000,
+    raw_fallback: true,
+    max_length_ratio: 2,
+    min_length_ratio: 0.5,
+    custom_headers: {},
   });
 
   useEffect(() => {
@@ -52,27 +145,141 @@ function App() {
       setStopSound(s.stop_sound);
       setSoundVolume(s.sound_volume);
     });
+    invoke<boolean>("get_keep_sound_output_open").then(setKeepSoundOutput);
     invoke<AiSettings>("get_ai_settings").then((ai) => setAiSettings(ai));
+    invoke<boolean>("get_ai_enabled").then(setAiEnabled);
+    invoke<string>("get_post_command").then(setPostCommand);
+    invoke<AudioDeviceInfo[]>("list_audio_devices").then(setInputDevices);
+    invoke<InputDevice>("get_input_device").then((device) => {
+      setInputDevice(device.name ?? "");
+      if (device.name && !device.connected) {
+        setDeviceWarning(`Microphone "${device.name}" isn't connected, using the default`);
+      }
+    });
 
     const unlisten1 = listen<string>("status-changed", (event) => {
       setStatus(event.payload);
-      if (event.payload !== "Recording") {
+      setSecondsLeft(null);
+      if (event.payload === "Recording") {
+        setDeviceWarning("");
+      }
+      if (event.payload !== "Recording" && event.payload !== "Paused") {
         setStreamingPreview("");
+        setClippingWarning("");
       }
     });
 
-    const unlisten2 = listen<string>("transcription-complete", (event) => {
-      setLastTranscription(event.payload);
+    const unlisten2 = listen<TranscriptionPair>("transcription-complete", (event) => {
+      setLastTranscription(event.payload.formatted);
     });
 
     const unlisten3 = listen<string>("streaming-preview", (event) => {
       setStreamingPreview(event.payload);
     });
 
+    const unlisten4 = listen<string>("model-loaded", () => {
+      setModelLoaded(true);
+      setModelLoading(false);
+      setModelIdleUnloaded(false);
+      setModelWarning("");
+    });
+
+    const unlisten5 = listen<string>("model-load-failed", (event) => {
+      setModelLoaded(false);
+      setModelLoading(false);
+      // Only failures whisper.cpp reports come back here; when it aborts
+      // outright the whole app closes with no message
+      setModelWarning(
+        event.payload.startsWith("Whisper crashed")
+          ? `${event.payload}. A model too large for this machine can also ` +
+              "close the app without warning."
+          : event.payload
+      );
+    });
+
+    const unlisten6 = listen<ClippingWarning>("clipping", (event) => {
+      setClippingWarning(event.payload.message);
+    });
+
+    const unlisten7 = listen<string>("device-disconnected", (event) => {
+      setDeviceWarning(event.payload);
+    });
+
+    const unlisten8 = listen<FileTranscription>("file-transcription", (event) => {
+      const file = event.payload;
+      setFileTranscriptions((prev) => {
+        // A new batch replaces the previous one's results
+        const kept = file.index === 1 && !file.text && !file.error ? [] : prev;
+        return [...kept.filter((f) => f.index !== file.index), file];
+      });
+    });
+
+    const unlisten9 = listen<string>("model-language-mismatch", (event) => {
+      setModelWarning(event.payload);
+    });
+
+    const unlisten10 = listen<string>("recording-rejected", (event) => {
+      setBusyWarning(event.payload);
+      setTimeout(() => setBusyWarning(""), 3000);
+    });
+
+    const unlisten11 = listen<string>("formatting-rejected", (event) => {
+      setFormatWarning(event.payload);
+      setTimeout(() => setFormatWarning(""), 5000);
+    });
+
+    const unlisten12 = listen<boolean>("ai-enabled-changed", (event) => {
+      setAiEnabled(event.payload);
+    });
+
+    const unlisten13 = listen<ModelDownloadFailure>("model-download-error", (event) => {
+      setModelWarning(`${event.payload.filename}: ${event.payload.message}`);
+    });
+
+    const unlisten14 = listen<number>("recording-time-warning", (event) => {
+      setSecondsLeft(event.payload);
+    });
+
+    const unlisten15 = listen<string>("model-loading", () => {
+      setModelLoading(true);
+    });
+
+    const unlisten16 = listen<number>("model-unloaded", () => {
+      setModelLoaded(false);
+      setModelIdleUnloaded(true);
+    });
+
+    const unlisten17 = listen<string>("clipboard-restore-failed", (event) => {
+      setClipboardWarning(event.payload);
+      setTimeout(() => setClipboardWarning(""), 5000);
+    });
+
+    const unlisten18 = listen<string>("input-device-missing", (event) => {
+      const warning = `Microphone "${event.payload}" isn't connected, using the default`;
+      setDeviceWarning(warning);
+      // Leave a disconnect warning that replaced this one in place
+      setTimeout(() => setDeviceWarning((w) => (w === warning ? "" : w)), 5000);
+    });
+
     return () => {
       unlisten1.then((fn) => fn());
       unlisten2.then((fn) => fn());
       unlisten3.then((fn) => fn());
+      unlisten4.then((fn) => fn());
+      unlisten5.then((fn) => fn());
+      unlisten6.then((fn) => fn());
+      unlisten7.then((fn) => fn());
+      unlisten8.then((fn) => fn());
+      unlisten9.then((fn) => fn());
+      unlisten10.then((fn) => fn());
+      unlisten11.then((fn) => fn());
+      unlisten12.then((fn) => fn());
+      unlisten13.then((fn) => fn());
+      unlisten14.then((fn) => fn());
+      unlisten15.then((fn) => fn());
+      unlisten16.then((fn) => fn());
+      unlisten17.then((fn) => fn());
+      unlisten18.then((fn) => fn());
     };
   }, []);
 
@@ -147,7 +354,48 @@ function App() {
   const updateAiSettings = (updates: Partial<AiSettings>) => {
     const newSettings = { ...aiSettings, ...updates };
     setAiSettings(newSettings);
-    invoke("set_ai_settings", { ai: newSettings });
+    invoke("set_ai_settings", { ai: newSettings })
+      .then(() => setAiError(""))
+      .catch((err) => setAiError(String(err)));
+  };
+
+  const selectInputDevice = (name: string) => {
+    invoke("set_input_device", { deviceName: name || null })
+      .then(() => {
+        setInputDevice(name);
+        setInputDeviceError("");
+        setDeviceWarning("");
+      })
+      .catch((err) => setInputDeviceError(String(err)));
+  };
+
+  const runMicTest = () => {
+    setMicTesting(true);
+    setMicTest("Speak now...");
+    invoke<MicTest>("test_mic")
+      .then((result) => {
+        const verdict = {
+          too_quiet: "Too quiet — move closer or raise the input volume",
+          good: "Good level",
+          clipping: "Clipping — lower the input volume",
+        }[result.level];
+        const peak = result.peak_dbfs === null ? "silent" : `peak ${result.peak_dbfs.toFixed(1)} dBFS`;
+        setMicTest(`${verdict} (${peak})`);
+      })
+      .catch((err) => setMicTest(String(err)))
+      .finally(() => setMicTesting(false));
+  };
+
+  const runFormatPreview = () => {
+    setPreviewRunning(true);
+    setPreviewError("");
+    invoke<FormatOutcome>("format_preview", { text: previewInput })
+      .then((outcome) => setPreviewResult(outcome))
+      .catch((err) => {
+        setPreviewResult(null);
+        setPreviewError(String(err));
+      })
+      .finally(() => setPreviewRunning(false));
   };
 
   const saveSoundSettings = (newStart: string, newStop: string, newVol: number) => {
@@ -161,7 +409,7 @@ function App() {
   const pickSoundFile = async (which: "start" | "stop") => {
     const file = await open({
       multiple: false,
-      filters: [{ name: "Audio", extensions: ["wav", "mp3", "ogg", "flac"] }],
+      filters: [{ name: "Audio", extensions: ["wav", "mp3", "ogg", "flac", "m4a"] }],
     });
     if (file) {
       const path = typeof file === "string" ? file : file;
@@ -194,6 +442,22 @@ function App() {
     invoke("test_sound", { which });
   };
 
+  const savePostCommand = () => {
+    invoke("set_post_command", { command: postCommand })
+      .then(() => setPostCommandError(""))
+      .catch((e) => setPostCommandError(String(e)));
+  };
+
+  const copyDiagnostics = () => {
+    invoke("get_diagnostics")
+      .then((info) => navigator.clipboard.writeText(JSON.stringify(info, null, 2)))
+      .then(() => {
+        setDiagnosticsCopied(true);
+        setTimeout(() => setDiagnosticsCopied(false), 2000);
+      })
+      .catch((e) => console.error("Copying diagnostics failed:", e));
+  };
+
   const fileName = (path: string) => {
     if (!path) return "";
     const parts = path.replace(/\\/g, "/").split("/");
@@ -202,6 +466,7 @@ function App() {
 
   const hotkeyParts = hotkey.split("+");
   const isRecording = status === "Recording";
+  const isPaused = status === "Paused";
   const isTranscribing = status === "Transcribing";
   const isFormatting = status === "Formatting";
   const isInjecting = status === "Injecting";
@@ -252,16 +517,37 @@ function App() {
             <div className="status-label">
               {isRecording
                 ? "Listening..."
+                : isPaused
+                ? "Paused"
                 : isTranscribing
                 ? "Transcribing..."
                 : isFormatting
                 ? "Formatting..."
                 : isInjecting
                 ? "Pasting..."
+                : status === "Measuring"
+                ? "Measuring..."
                 : "Ready"}
             </div>
 
-            {isRecording && streamingPreview && (
+            {(isRecording || isPaused) && (
+              <button
+                className="hotkey-change-btn"
+                onClick={() =>
+                  invoke(isPaused ? "resume_recording" : "pause_recording").catch((e) =>
+                    console.error("Pause/resume failed:", e)
+                  )
+                }
+              >
+                {isPaused ? "Resume" : "Pause"}
+              </button>
+            )}
+
+            {(isRecording || isPaused) && secondsLeft !== null && (
+              <div className="hotkey-error">Stopping automatically in {secondsLeft}s</div>
+            )}
+
+            {(isRecording || isPaused) && streamingPreview && (
               <div className="streaming-preview">
                 <div className="streaming-preview-text">{streamingPreview}</div>
               </div>
@@ -307,12 +593,57 @@ function App() {
             </div>
           </div>
 
+          {clippingWarning && (
+            <div className="hotkey-error">{clippingWarning}</div>
+          )}
+
+          {deviceWarning && (
+            <div className="hotkey-error">{deviceWarning}</div>
+          )}
+
+          {modelWarning && (
+            <div className="hotkey-error">{modelWarning}</div>
+          )}
+
+          {busyWarning && (
+            <div className="hotkey-error">{busyWarning}</div>
+          )}
+
+          {formatWarning && (
+            <div className="hotkey-error">{formatWarning}</div>
+          )}
+
+          {clipboardWarning && (
+            <div className="hotkey-error">{clipboardWarning}</div>
+          )}
+
           {lastTranscription && (
             <div className="transcript-card">
               <div className="transcript-label">Last transcription</div>
               <div className="transcript-text">{lastTranscription}</div>
             </div>
           )}
+
+          {fileTranscriptions.map((file) => (
+            <div className="transcript-card" key={file.index}>
+              <div className="transcript-label">
+                {fileName(file.path)} ({file.index}/{file.total})
+                {file.text && (
+                  <button
+                    className="hotkey-change-btn"
+                    onClick={() => navigator.clipboard.writeText(file.text ?? "")}
+                  >
+                    Copy
+                  </button>
+                )}
+              </div>
+              {file.error ? (
+                <div className="hotkey-error">{file.error}</div>
+              ) : (
+                <div className="transcript-text">{file.text ?? "Transcribing..."}</div>
+              )}
+            </div>
+          ))}
         </>
       ) : (
         <div className="settings-section">
@@ -379,11 +710,67 @@ function App() {
               />
               <span className="volume-value">{Math.round(soundVolume * 100)}%</span>
             </div>
+
+            <div className="setting-row">
+              <span className="setting-label">Keep audio output open</span>
+              <input
+                type="checkbox"
+                checked={keepSoundOutput}
+                onChange={(e) => {
+                  setKeepSoundOutput(e.target.checked);
+                  invoke("set_keep_sound_output_open", { keep: e.target.checked });
+                }}
+                title="Quickest sounds. Turn off if Wispr Local keeps your speakers from sleeping."
+              />
+            </div>
+
+            <div className="setting-row">
+              <span className="setting-label">Input device</span>
+              <select
+                className="setting-select"
+                value={inputDevice}
+                onFocus={() =>
+                  invoke<AudioDeviceInfo[]>("list_audio_devices").then(setInputDevices)
+                }
+                onChange={(e) => selectInputDevice(e.target.value)}
+              >
+                <option value="">System default</option>
+                {inputDevice && !inputDevices.some((d) => d.name === inputDevice) && (
+                  <option value={inputDevice}>{inputDevice} (not connected)</option>
+                )}
+                {inputDevices.map((d) => (
+                  <option key={d.name} value={d.name}>
+                    {d.name}
+                  </option>
+                ))}
+              </select>
+            </div>
+            {inputDeviceError && <div className="hotkey-error">{inputDeviceError}</div>}
+
+            <div className="sound-row">
+              <span className="sound-label">Microphone</span>
+              <div className="sound-controls">
+                {micTest && <span className="sound-file">{micTest}</span>}
+                <button className="sound-btn" onClick={runMicTest} disabled={micTesting}>
+                  Test
+                </button>
+              </div>
+            </div>
           </div>
 
           <div className="settings-group">
             <div className="settings-group-title">AI Formatting</div>
 
+            <div className="setting-row">
+              <span className="setting-label">Format dictations</span>
+              <input
+                type="checkbox"
+                checked={aiEnabled}
+                onChange={(e) => invoke("set_ai_enabled", { enabled: e.target.checked })}
+                title="Off pastes the raw transcription but keeps the provider settings"
+              />
+            </div>
+
             <div className="setting-row">
               <span className="setting-label">Provider</span>
               <select
@@ -398,6 +785,8 @@ function App() {
                 <option value="none">None (raw text)</option>
                 <option value="openai">OpenAI</option>
                 <option value="claude">Claude</option>
+                <option value="azure_openai">Azure OpenAI</option>
+                <option value="rules">Rules (offline)</option>
               </select>
             </div>
 
@@ -459,7 +848,59 @@ function App() {
               </>
             )}
 
-            {aiSettings.provider !== "none" && (
+            {aiSettings.provider === "azure_openai" && (
+              <>
+                <div className="setting-row">
+                  <span className="setting-label">API Key</span>
+                  <input
+                    className="setting-input"
+                    type="password"
+                    value={aiSettings.api_key}
+                    onChange={(e) =>
+                      updateAiSettings({ api_key: e.target.value })
+                    }
+                  />
+                </div>
+                <div className="setting-row">
+                  <span className="setting-label">Resource</span>
+                  <input
+                    className="setting-input"
+                    type="text"
+                    value={aiSettings.azure_resource}
+                    onChange={(e) =>
+                      updateAiSettings({ azure_resource: e.target.value })
+                    }
+                    placeholder="my-resource"
+                  />
+                </div>
+                <div className="setting-row">
+                  <span className="setting-label">Deployment</span>
+                  <input
+                    className="setting-input"
+                    type="text"
+                    value={aiSettings.azure_deployment}
+                    onChange={(e) =>
+                      updateAiSettings({ azure_deployment: e.target.value })
+                    }
+                    placeholder="gpt-4o-mini"
+                  />
+                </div>
+                <div className="setting-row">
+                  <span className="setting-label">API Version</span>
+                  <input
+                    className="setting-input"
+                    type="text"
+                    value={aiSettings.azure_api_version}
+                    onChange={(e) =>
+                      updateAiSettings({ azure_api_version: e.target.value })
+                    }
+                    placeholder="2024-06-01"
+                  />
+                </div>
+              </>
+            )}
+
+            {aiSettings.provider !== "none" && aiSettings.provider !== "rules" && (
               <div className="setting-row prompt-row">
                 <span className="setting-label">Prompt</span>
                 <textarea
@@ -473,16 +914,167 @@ function App() {
                 />
               </div>
             )}
+
+            {(aiSettings.provider === "openai" ||
+              aiSettings.provider === "azure_openai") && (
+              <div className="setting-row">
+                <span className="setting-label">Prompt in user message</span>
+                <input
+                  type="checkbox"
+                  checked={aiSettings.prompt_in_user_message}
+                  onChange={(e) =>
+                    updateAiSettings({ prompt_in_user_message: e.target.checked })
+                  }
+                  title="For models that ignore the system role"
+                />
+              </div>
+            )}
+
+            {aiSettings.provider !== "none" && aiSettings.provider !== "rules" && (
+              <div className="setting-row">
+                <span className="setting-label">Max input (chars)</span>
+                <input
+                  className="setting-input"
+                  type="number"
+                  min={0}
+                  step={500}
+                  value={aiSettings.max_input_chars}
+                  onChange={(e) =>
+                    updateAiSettings({ max_input_chars: Number(e.target.value) })
+                  }
+                  placeholder="0 = no limit"
+                />
+              </div>
+            )}
+
+            {aiSettings.provider !== "none" && aiSettings.provider !== "rules" && (
+              <div className="setting-row">
+                <span className="setting-label">Keep raw text on bad output</span>
+                <input
+                  type="checkbox"
+                  checked={aiSettings.raw_fallback}
+                  onChange={(e) => updateAiSettings({ raw_fallback: e.target.checked })}
+                  title="Discard output that changes length too much or adds commentary"
+                />
+              </div>
+            )}
+
+            {aiSettings.raw_fallback &&
+              aiSettings.provider !== "none" &&
+              aiSettings.provider !== "rules" && (
+                <div className="setting-row">
+                  <span className="setting-label">Allowed length change</span>
+                  <input
+                    className="setting-input"
+                    type="number"
+                    min={0}
+                    max={0.95}
+                    step={0.05}
+                    value={aiSettings.min_length_ratio}
+                    onChange={(e) =>
+                      updateAiSettings({ min_length_ratio: Number(e.target.value) })
+                    }
+                    title="Shortest output, as a fraction of the input (0 = no limit)"
+                  />
+                  <input
+                    className="setting-input"
+                    type="number"
+                    min={0}
+                    step={0.5}
+                    value={aiSettings.max_length_ratio}
+                    onChange={(e) =>
+                      updateAiSettings({ max_length_ratio: Number(e.target.value) })
+                    }
+                    title="Longest output, as a multiple of the input (0 = no limit)"
+                  />
+                </div>
+              )}
+
+            {aiError && <div className="hotkey-error">{aiError}</div>}
+
+            {aiSettings.provider !== "none" && (
+              <div className="setting-row prompt-row">
+                <span className="setting-label">Try it</span>
+                <textarea
+                  className="setting-textarea"
+                  value={previewInput}
+                  onChange={(e) => setPreviewInput(e.target.value)}
+                  rows={3}
+                  placeholder="Paste sample text to format with the settings above"
+                />
+                <button
+                  className="sound-btn"
+                  onClick={runFormatPreview}
+                  disabled={previewRunning || !previewInput.trim()}
+                >
+                  {previewRunning ? "Formatting..." : "Format"}
+                </button>
+              </div>
+            )}
+
+            {previewError && <div className="hotkey-error">{previewError}</div>}
+
+            {previewResult && (
+              <div className="transcript-card">
+                <div className="transcript-label">
+                  {previewResult.provider
+                    ? `Formatted by ${previewResult.provider}${
+                        previewResult.model ? ` (${previewResult.model})` : ""
+                      }`
+                    : "Every provider failed, text unchanged"}
+                </div>
+                <div className="transcript-text">{previewResult.text}</div>
+                {previewResult.errors.map((e, i) => (
+                  <div className="hotkey-error" key={i}>{e}</div>
+                ))}
+              </div>
+            )}
+          </div>
+
+          <div className="settings-group">
+            <div className="settings-group-title">After Dictation</div>
+
+            <div className="setting-row">
+              <span className="setting-label">Run command</span>
+              <input
+                className="setting-input"
+                type="text"
+                value={postCommand}
+                onChange={(e) => setPostCommand(e.target.value)}
+                onBlur={savePostCommand}
+                placeholder="notes add {text}"
+                title="Gets every dictation on stdin, or in place of {text} (empty = off)"
+              />
+            </div>
+
+            {postCommand.trim() && (
+              <div className="hotkey-error">
+                Every dictation is passed to this program, which runs with your
+                permissions. Only use programs you trust.
+              </div>
+            )}
+            {postCommandError && <div className="hotkey-error">{postCommandError}</div>}
           </div>
         </div>
       )}
 
       <div className="footer">
-        <div className={`model-indicator ${modelLoaded ? "ok" : "err"}`}>
-          <span className="dot" />
-          {modelLoaded ? "Model ready" : "Model not loaded"}
+        <div className="footer-row">
+          <div className={`model-indicator ${modelLoaded ? "ok" : "err"}`}>
+            <span className="dot" />
+            {modelLoaded
+              ? "Model ready"
+              : modelLoading
+                ? "Loading model..."
+                : modelIdleUnloaded
+                  ? "Model unloaded while idle"
+                  : "Model not loaded"}
+          </div>
+          <button className="sound-btn" onClick={copyDiagnostics}>
+            {diagnosticsCopied ? "Copied" : "Copy diagnostics"}
+          </button>
         </div>
-        {!modelLoaded && (
+        {!modelLoaded && !modelLoading && !modelIdleUnloaded && (
           <div className="model-help">
             Download <code>ggml-base.en.bin</code> to:
             <span className="model-path">{modelsDir}</span>